@@ -1,7 +1,4 @@
-use std::path::Path;
-
 use diploma_thesis::res::asset_manager::AssetManager;
-use gltf::Gltf;
 use pollster::block_on;
 use wgpu::MemoryHints;
 use winit::{event_loop::EventLoop, window::{Window, WindowBuilder}};
@@ -47,26 +44,27 @@ async fn run(window: Window) {
         .unwrap();
 
 
-    let mut assets = AssetManager::new();
-    
-    let gltf_path = Path::new("examples/assets/cube_model/scene.gltf");
-    let gltf = Gltf::open(gltf_path).unwrap();
-    let meshes = match assets.load_gltf_meshes(&gltf, "examples/assets/cube_model/", &device, &queue) {
-        Ok(meshes) => meshes,
-        Err(_) => todo!(),
-    };
+    let mut assets = AssetManager::for_adapter(&adapter);
 
-    let gltf_path = Path::new("examples/assets/reflection_mirror.glb");
-    let gltf = Gltf::open(gltf_path).unwrap();
-    let meshes1 = assets.load_gltf_meshes(&gltf, "examples/assets/", &device, &queue);
+    let cube = assets.load_gltf_file("examples/assets/cube_model/scene.gltf", &device, &queue).unwrap();
+    let mirror = assets.load_gltf_file("examples/assets/reflection_mirror.glb", &device, &queue);
+    let pyramid = assets.load_gltf_file("examples/assets/pyramid/scene.gltf", &device, &queue);
 
-    let gltf_path = Path::new("examples/assets/pyramid/scene.gltf");
-    let gltf = Gltf::open(gltf_path).unwrap();
-    let meshes2 = assets.load_gltf_meshes(&gltf, "examples/assets/pyramid/", &device, &queue);
+    // Повторная загрузка не создаёт новых мешей и текстур
+    let meshes_before = assets.meshes.len();
+    let cube_again = assets.load_gltf_file("examples/assets/cube_model/scene.gltf", &device, &queue).unwrap();
+    assert_eq!(assets.meshes.len(), meshes_before);
+    assert_eq!(cube.models[0].key(), cube_again.models[0].key());
 
-    // println!("{:?}", assets);
-    println!("{:?}", meshes);
+    println!("{:?}", cube.models);
     println!("\n");
-    println!("{:?}", meshes1);
+    println!("{:?}", mirror.map(|import| import.models));
+    println!("{:?}", pyramid.map(|import| import.models));
 
-}
\ No newline at end of file
+    // Ресурсы удаляются только после выгрузки обеих загрузок
+    assets.unload("examples/assets/cube_model/scene.gltf");
+    assert!(assets.models.contains(cube.models[0].clone()));
+    assets.unload("examples/assets/cube_model/scene.gltf");
+    assert!(!assets.models.contains(cube.models[0].clone()));
+    println!("{}", assets.meshes.debug_stats());
+}
@@ -1,4 +1,4 @@
-use diploma_thesis::{controll::camera::fpv_camera::FpvCameraController, res::{asset_manager::AssetManager, texture::gpu_texture::{get_texture_bind_group_layout, DEPTH_FORMAT}, vertex::Vertex}, scene::{camera::get_camera_bind_group_layout, entity::SceneEntity}};
+use diploma_thesis::{core::{pipeline_cache::PipelineCache, shader::ShaderComposer},controll::camera::fpv_camera::FpvCameraController, res::{asset_manager::AssetManager, texture::gpu_texture::{get_texture_bind_group_layout, DEPTH_FORMAT}}, scene::{camera::get_camera_bind_group_layout, entity::SceneEntity}};
 use gltf::Gltf;
 use wgpu::{DepthStencilState, MemoryHints, PipelineCompilationOptions};
 use winit::{
@@ -61,6 +61,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         view_formats: vec![],
     };
     surface.configure(&device, &config);
+
+    let mut pipeline_cache = PipelineCache::new(ShaderComposer::with_default_modules());
     
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Shader"),
@@ -69,7 +71,7 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         ),
     });
 
-    let mut assets = AssetManager::new();
+    let mut assets = AssetManager::for_adapter(&adapter);
     let gltf_path = Path::new("examples/assets/cube_model/scene.gltf");
     let gltf = Gltf::open(gltf_path).unwrap();
     let meshes = assets.load_gltf_meshes(&gltf, "examples/assets/cube_model/", &device, &queue);
@@ -83,6 +85,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     println!("{:?}", cube);
 
+    let vertex_layout = cube.unwrap().layout;
+
     let material = match assets.materials.get_mut(cube.unwrap().material.clone().unwrap()){
         Some(mat) => mat,
         None => todo!(),
@@ -95,7 +99,7 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     material.create_bind_group(
         &device, 
-        None, 
+        &get_texture_bind_group_layout(&mut pipeline_cache, &device), 
         Some(&texture.view), 
         Some(&texture.sampler)
     );
@@ -104,6 +108,7 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     let mut aspect_ratio = config.width as f32 / config.height as f32;
     let mut camera = SceneEntity::new_camera(
         &device, 
+        &get_camera_bind_group_layout(&mut pipeline_cache, &device), 
         Vec3::new(0., 0., 5.), 
         Quat::from_rotation_y(0.0), 
         Vec3::ONE, 
@@ -131,8 +136,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         label: Some("Render Pipeline Layout"),
         bind_group_layouts: &[
                 // &get_light_bind_group_layout(&device),
-                &get_texture_bind_group_layout(&device),
-                &get_camera_bind_group_layout(&device),
+                &get_texture_bind_group_layout(&mut pipeline_cache, &device),
+                &get_camera_bind_group_layout(&mut pipeline_cache, &device),
             ],
         push_constant_ranges: &[],
     });
@@ -144,7 +149,7 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
             module: &shader,
             entry_point: Some("vs_main"),
             buffers: &[
-                Vertex::desc(),
+                vertex_layout.desc(),
             ],
             compilation_options: PipelineCompilationOptions::default(),
         },
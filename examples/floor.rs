@@ -1,4 +1,4 @@
-use diploma_thesis::{ controll::camera::fly_camera::FlyCameraController, res::{asset_manager::AssetManager, texture::{self, gpu_texture::DEPTH_FORMAT},  vertex::Vertex}, scene::{camera::get_camera_bind_group_layout, entity::SceneEntity}};
+use diploma_thesis::{core::{pipeline_cache::PipelineCache, shader::ShaderComposer}, controll::camera::fly_camera::FlyCameraController, res::{asset_manager::AssetManager, texture::{self, gpu_texture::{get_texture_bind_group_layout, DEPTH_FORMAT}}}, scene::{camera::get_camera_bind_group_layout, entity::SceneEntity}};
 use gltf::Gltf;
 use wgpu::{util::DeviceExt, DepthStencilState, MemoryHints, PipelineCompilationOptions};
 use winit::{
@@ -62,7 +62,9 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     };
     surface.configure(&device, &config);
 
-    let mut assets = AssetManager::new();
+    let mut pipeline_cache = PipelineCache::new(ShaderComposer::with_default_modules());
+
+    let mut assets = AssetManager::for_adapter(&adapter);
     let gltf_path = Path::new("examples/assets/cube_model/scene.gltf");
     let gltf = Gltf::open(gltf_path).unwrap();
     let meshes = assets.load_gltf_meshes(&gltf, "examples/assets/cube_model/", &device, &queue);
@@ -76,6 +78,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     println!("{:?}", cube);
 
+    let vertex_layout = cube.unwrap().layout;
+
     let material = match assets.materials.get_mut(cube.unwrap().material.clone().unwrap()){
         Some(mat) => mat,
         None => todo!(),
@@ -88,13 +92,14 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     material.create_bind_group(
         &device, 
-        None, 
+        &get_texture_bind_group_layout(&mut pipeline_cache, &device), 
         Some(&texture.view), 
         Some(&texture.sampler)
     );
 
     let mut camera = SceneEntity::new_camera(
         &device, 
+        &get_camera_bind_group_layout(&mut pipeline_cache, &device), 
         Vec3::new(0., 0., 5.), 
         Quat::from_rotation_y(0.0), 
         Vec3::ONE, 
@@ -143,7 +148,7 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     let floor_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("Floor Pipeline Layout"),
-        bind_group_layouts: &[&get_camera_bind_group_layout(&device),],
+        bind_group_layouts: &[&get_camera_bind_group_layout(&mut pipeline_cache, &device),],
         push_constant_ranges: &[],
     });
 
@@ -206,8 +211,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         label: Some("Cube Render Pipeline Layout"),
         bind_group_layouts: &[
                 // &get_light_bind_group_layout(&device),
-                // &get_texture_bind_group_layout(&device),
-                &get_camera_bind_group_layout(&device),
+                // &get_texture_bind_group_layout(&mut pipeline_cache, &device),
+                &get_camera_bind_group_layout(&mut pipeline_cache, &device),
             ],
         push_constant_ranges: &[],
     });
@@ -220,7 +225,7 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
             module: &shader,
             entry_point: Some("vs_main"),
             buffers: &[
-                Vertex::desc(),
+                vertex_layout.desc(),
             ],
             compilation_options: PipelineCompilationOptions::default(),
         },
@@ -1,8 +1,8 @@
 pub extern crate nalgebra_glm as glm;
 
 use std::{collections::VecDeque, time::Instant};
-use diploma_thesis::{controll::camera::raycast_camera::RayCastCameraController, core::raytracer::{
-    sky::SkyParams, Raytracer, RenderParams, SamplingParams, Scene}, gui::GpuContext, math::{angle::Angle, sphere::Sphere}, res::{material::{GpuMaterial, Material, RayCastMaterial}, texture::Texture}, scene::{camera::RayCastCameraParams, entity::SceneEntity}};
+use diploma_thesis::{controll::camera::raycast_camera::RayCastCameraController, core::{pipeline_cache::PipelineCache, raytracer::{
+    sky::SkyParams, Raytracer, RenderParams, SamplingParams, Scene}, shader::ShaderComposer}, gui::GpuContext, math::{angle::Angle, sphere::Sphere}, res::{material::{GpuMaterial, Material, RayCastMaterial}, texture::Texture}, scene::{camera::{get_camera_bind_group_layout, RayCastCameraParams}, entity::SceneEntity}};
 use glam::{Quat, Vec3};
 use wgpu::StoreOp;
 use winit::{
@@ -39,8 +39,10 @@ fn main() {
     let look_at = Vec3::new(0.0, 1.0, 0.0);
     let focus_distance = (look_at - look_from).length();
     let mut aspect_ratio = window.inner_size().width as f32 / window.inner_size().height as f32;
+    let mut pipeline_cache = PipelineCache::new(ShaderComposer::with_default_modules());
     let mut camera = SceneEntity::new_camera(
         &context.device, 
+        &get_camera_bind_group_layout(&mut pipeline_cache, &context.device), 
         look_from, 
         Quat::from_rotation_y(0.0), 
         Vec3::ONE, 
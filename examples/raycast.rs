@@ -1,7 +1,8 @@
 use std::time::Instant;
-use diploma_thesis::{controll::camera::raycast_camera::RayCastCameraController, core::raytracer::{
-    sky::SkyParams, Raytracer, RenderParams, SamplingParams, Scene}, gui::GpuContext, math::{angle::Angle, sphere::Sphere}, res::{material::RayCastMaterial, texture::Texture}, scene::{camera::RayCastCameraParams, entity::SceneEntity}};
-use glam::{Quat, Vec3};
+use diploma_thesis::{controll::camera::raycast_camera::RayCastCameraController, core::{raytracer::{
+    sky::SkyParams, Raytracer, RenderParams, SamplingParams, Scene}, pipeline_cache::PipelineCache, shader::{hot_reload::ShaderHotReload, ShaderComposer}}, gui::GpuContext, math::{angle::Angle, sphere::Sphere}, res::{asset_manager::AssetManager, material::RayCastMaterial, texture::Texture}, scene::{camera::{get_camera_bind_group_layout, RayCastCameraParams}, entity::SceneEntity}};
+use glam::{Mat4, Quat, Vec3};
+use gltf::Gltf;
 use wgpu::StoreOp;
 use winit::{
     event::{Event, WindowEvent},
@@ -28,7 +29,17 @@ fn main() {
         .max()
         .expect("There should be at least one monitor available");
 
-    let scene = scene();
+    let mut scene = scene();
+
+    // Куб из glTF попадает в трассировщик описанной сферой со своим материалом
+    let mut assets = AssetManager::new();
+    let gltf = Gltf::open("examples/assets/cube_model/scene.gltf").unwrap();
+    let cube = assets
+        .load_gltf(&gltf, "examples/assets/cube_model/", &context.device, &context.queue)
+        .unwrap();
+    if let Some(cube_scene) = cube.default_scene.and_then(|handle| assets.scenes.get(handle)) {
+        scene.add_gltf_scene(cube_scene, &assets, Mat4::from_translation(Vec3::new(0.0, 2.0, 8.0)));
+    }
 
 
     let look_from = Vec3::new(-10.0, 2.0, -4.0);
@@ -37,8 +48,10 @@ fn main() {
     let aspect_ratio = window.inner_size().width as f32 / window.inner_size().height as f32;
 // ... предыдущий код остается без изменений до этой части ...
 
+let mut pipeline_cache = PipelineCache::new(ShaderComposer::with_default_modules());
 let mut camera = SceneEntity::new_camera(
     &context.device, 
+    &get_camera_bind_group_layout(&mut pipeline_cache, &context.device), 
     look_from, 
     Quat::from_rotation_y(0.0), 
     Vec3::ONE, 
@@ -77,6 +90,10 @@ let mut raytracer = Raytracer::new(
 )
 .expect("The default values should be selected correctly");
 
+// Горячая перезагрузка шейдеров включается переменной окружения SHADER_HOT_RELOAD
+let mut hot_reload = std::env::var_os("SHADER_HOT_RELOAD")
+    .map(|_| ShaderHotReload::new("shaders").unwrap());
+
 
     let mut last_time = Instant::now();
 
@@ -101,6 +118,14 @@ event_loop.run( |event, _elwt: &winit::event_loop::EventLoopWindowTarget<()>| {
                 event: WindowEvent::RedrawRequested,
                 ..
           } => {
+            if let Some(hot_reload) = hot_reload.as_mut() {
+                if hot_reload.poll().iter().any(|module| module == "raytracer") {
+                    if let Err(e) = raytracer.reload_shader(&context.device, &hot_reload.composer) {
+                        eprintln!("Error reloading shader: {e}");
+                    }
+                }
+            }
+
             let dt = last_time.elapsed().as_secs_f32();
             let now = Instant::now();
             camera_controller.update_camera(render_params.viewport_size, 2.0 * dt, &mut camera);
@@ -1,14 +1,12 @@
-use diploma_thesis::{controll::camera::fly_camera::FlyCameraController, res::{asset_manager::AssetManager, texture::{gpu_texture::{GpuTexture, DEPTH_FORMAT}},  
-vertex::Vertex}, scene::{camera::get_camera_bind_group_layout, entity::SceneEntity}};
-use gltf::Gltf;
-use wgpu::{util::DeviceExt, DepthStencilState, MemoryHints, PipelineCompilationOptions};
+use diploma_thesis::{controll::camera::fly_camera::FlyCameraController, core::{msaa::{next_sample_count, MsaaTargets}, pipeline_cache::{PipelineCache, PipelineKey}, post_process::{PostEffectKind, PostProcessStack, HDR_FORMAT}, scene_renderer::SceneRenderer, shader::{hot_reload::ShaderHotReload, ShaderComposer}, skybox::SkyboxRenderer, PipelineType}, res::{animation::{AnimationPlayer, Playback}, asset_manager::AssetManager, hot_reload::AssetHotReload, texture::ibl::{IblSettings, LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES}}, scene::{camera::get_camera_bind_group_layout, entity::{SceneEntity, SceneEntityKind}, AppScene}};
+use wgpu::{util::DeviceExt, MemoryHints};
 use winit::{
-    event::{Event, WindowEvent}, event_loop::EventLoop, window::{Window, WindowBuilder}
+    dpi::PhysicalPosition, event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent}, event_loop::EventLoop, keyboard::{KeyCode, PhysicalKey}, window::{Window, WindowBuilder}
 };
 use pollster::block_on;
-use glam::{Quat, Vec3};
+use glam::{Quat, Vec2, Vec3};
 
-use std::path::Path;
+use std::time::Instant;
 
 fn main() {
     env_logger::init();
@@ -63,40 +61,55 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     };
     surface.configure(&device, &config);
 
-    let mut assets = AssetManager::new();
-    let gltf_path = Path::new("examples/assets/cube_model/scene.gltf");
-    let gltf = Gltf::open(gltf_path).unwrap();
-    let meshes = assets.load_gltf_meshes(&gltf, "examples/assets/cube_model/", &device, &queue);
-
-
-    let cube = assets.meshes.get(
-        match meshes{
-        Ok(asset) => {asset[0].clone()},
-        Err(_) => todo!(),
+    // SHADER_HOT_RELOAD=1 включает загрузку шейдеров с диска и перезагрузку на лету
+    // шейдеров и модели
+    let mut hot_reload = std::env::var_os("SHADER_HOT_RELOAD")
+        .map(|_| ShaderHotReload::new("shaders").unwrap());
+    let mut asset_hot_reload = hot_reload.as_ref().map(|_| AssetHotReload::new().unwrap());
+    let composer = ShaderComposer::with_default_modules();
+
+    let mut pipeline_cache = PipelineCache::new(hot_reload.as_ref().map_or(&composer, |h| &h.composer).clone());
+
+    let mut assets = AssetManager::for_adapter(&adapter);
+    // Копия геометрии на CPU нужна для выбора узла щелчком мыши; дальние
+    // меши рисуются упрощёнными уровнями детализации
+    assets.mesh_import.keep_data = true;
+    assets.mesh_import.lod_ratios = vec![0.5, 0.25, 0.1];
+    // GLTF=<путь> подменяет модель; анимированные и скинированные модели проигрывают первый клип
+    let gltf_path = std::env::var("GLTF").unwrap_or_else(|_| "examples/assets/cube_model/scene.gltf".to_string());
+    // Через load_gltf_file, чтобы AssetHotReload следил за файлом
+    let import = assets.load_gltf_file(&gltf_path, &device, &queue).unwrap();
+    let model_scene = import.default_scene.clone().expect("glTF file should contain a scene");
+
+    let mut animation_player = AnimationPlayer::new();
+    if let Some(animation) = import.animations.first() {
+        animation_player.play(Playback::new(animation.clone()));
+    }
+
+    // Небо сцены и освещение от него: панорама Земли, перепроецированная в кубическую карту
+    let mut scene = AppScene::new();
+    let sky_image = image::open("examples/assets/jpeg/earthmap.jpeg").unwrap();
+    let environment = assets.load_environment(&device, &queue, &sky_image, &IblSettings::default()).unwrap();
+    scene.skybox = Some(environment.skybox.clone());
+
+    // Один точечный источник (`PointLight` в shaders/light.wgsl): направление, цвет и интенсивность
+    let point_lights = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("point_lights"),
+        contents: bytemuck::cast_slice(&[[2.0f32, 4.0, 3.0, 0.0, 1.0, 0.95, 0.9, 1.0]]),
+        usage: wgpu::BufferUsages::STORAGE,
     });
-
-    println!("{:?}", cube);
-
-    let material = match assets.materials.get_mut(cube.unwrap().material.clone().unwrap()){
-        Some(mat) => mat,
-        None => todo!(),
-    };
-
-    let texture = match assets.textures.get_mut(material.base_color_texture.clone().unwrap()){
-        Some(text) => text,
-        None => todo!(),
-    };
-
-    material.create_bind_group(
-        &device, 
-        None, 
-        Some(&texture.view), 
-        Some(&texture.sampler)
-    );
+    let environment_buffer = environment.create_uniform_buffer(&device);
+    let lighting_layout = pipeline_cache.bind_group_layout(&device, LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES);
+    let lighting_bind_group = environment
+        .create_bind_group(&device, &lighting_layout, &assets.textures, &point_lights, &environment_buffer)
+        .unwrap();
+    let scene_key = PipelineKey::new(PipelineType::Lit, HDR_FORMAT);
+    let mut scene_renderer = SceneRenderer::new(&device, &mut pipeline_cache, scene_key);
 
     let mut aspect_ratio = config.width as f32 / config.height as f32;
     let mut camera = SceneEntity::new_camera(
         &device, 
+        &get_camera_bind_group_layout(&mut pipeline_cache, &device), 
         Vec3::new(0., 0., 5.), 
         Quat::from_rotation_y(0.0), 
         Vec3::ONE, 
@@ -109,157 +122,22 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     let mut camera_controler = FlyCameraController::new(0.1, 0.1);
 
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("../examples/shaders/cube_camera.wgsl").into() ),
-    });
-
-    let skybox_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Skybox Shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/skybox.wgsl").into()),
-    });
-
-    // Вершины для скайбокса (большой куб)
-    let skybox_vertices = [
-        [-1.0,  1.0, -1.0], [-1.0, -1.0, -1.0], [ 1.0, -1.0, -1.0], [ 1.0,  1.0, -1.0], // зад
-        [-1.0, -1.0,  1.0], [-1.0,  1.0,  1.0], [ 1.0,  1.0,  1.0], [ 1.0, -1.0,  1.0], // перед
-        [-1.0, -1.0, -1.0], [-1.0, -1.0,  1.0], [ 1.0, -1.0,  1.0], [ 1.0, -1.0, -1.0], // низ
-        [-1.0,  1.0,  1.0], [-1.0,  1.0, -1.0], [ 1.0,  1.0, -1.0], [ 1.0,  1.0,  1.0], // верх
-        [ 1.0, -1.0, -1.0], [ 1.0, -1.0,  1.0], [ 1.0,  1.0,  1.0], [ 1.0,  1.0, -1.0], // право
-        [-1.0, -1.0,  1.0], [-1.0, -1.0, -1.0], [-1.0,  1.0, -1.0], [-1.0,  1.0,  1.0], // лево
-    ];
-
-    let skybox_indices = [
-        0, 1, 2, 0, 2, 3,       // зад
-        4, 5, 6, 4, 6, 7,       // перед
-        8, 9, 10, 8, 10, 11,    // низ
-        12, 13, 14, 12, 14, 15, // верх
-        16, 17, 18, 16, 18, 19, // право
-        20, 21, 22, 20, 22, 23, // лево
-    ];
-
-    let skybox_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Skybox Vertex Buffer"),
-        contents: bytemuck::cast_slice(&skybox_vertices),
-        usage: wgpu::BufferUsages::VERTEX,
-    });
-
-    let skybox_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: Some("Skybox Index Buffer"),
-        contents: bytemuck::cast_slice(&skybox_indices),
-        usage: wgpu::BufferUsages::INDEX,
-    });
-
-    // Создаем pipeline для скайбокса
-    let skybox_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Skybox Pipeline Layout"),
-        bind_group_layouts: &[&get_camera_bind_group_layout(&device),],
-        push_constant_ranges: &[],
-    });
-
-    let skybox_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Skybox Pipeline"),
-        layout: Some(&skybox_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &skybox_shader,
-            entry_point: Some("vs_main"),
-            buffers: &[wgpu::VertexBufferLayout {
-                array_stride: std::mem::size_of::<[f32; 3]>() as u64,
-                step_mode: wgpu::VertexStepMode::Vertex,
-                attributes: &[wgpu::VertexAttribute {
-                    offset: 0,
-                    shader_location: 0,
-                    format: wgpu::VertexFormat::Float32x3,
-                }],
-            }],
-            compilation_options: Default::default(),
-        },
-        fragment: Some(wgpu::FragmentState {
-            module: &skybox_shader,
-            entry_point: Some("fs_main"),
-            targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-            compilation_options: Default::default(),
-        }),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList,
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Cw, // Обратный порядок для скайбокса
-            cull_mode: None, // Отключаем отсечение
-            polygon_mode: wgpu::PolygonMode::Fill,
-            unclipped_depth: false,
-            conservative: false,
-        },
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: false, // Не записываем глубину для скайбокса
-            depth_compare: wgpu::CompareFunction::LessEqual, // <= чтобы скайбокс был на максимальной глубине
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-        multisample: wgpu::MultisampleState::default(),
-        multiview: None,
-        cache: Default::default(),
-    });
-
+    // Сцена рисуется в HDR-текстуру, на экран её выводит цепочка постобработки.
+    // Клавиши 1-5 переключают bloom, тонмаппинг, цветокоррекцию, FXAA и виньетку.
+    let mut post_process = PostProcessStack::new(&device, &queue, &mut pipeline_cache, config.width, config.height);
 
-    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Cube Render Pipeline Layout"),
-        bind_group_layouts: &[
-                // &get_light_bind_group_layout(&device),
-                // &get_texture_bind_group_layout(&device),
-                &get_camera_bind_group_layout(&device),
-            ],
-        push_constant_ranges: &[],
-    });
-    
-
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Cube Render Pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &shader,
-            entry_point: Some("vs_main"),
-            buffers: &[
-                Vertex::desc(),
-            ],
-            compilation_options: PipelineCompilationOptions::default(),
-        },
-        fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: Some("fs_main"),
-            targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-            compilation_options: PipelineCompilationOptions::default(),
-        }),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology:: TriangleList,
-            strip_index_format: None,
-            front_face: wgpu::FrontFace::Ccw,
-            cull_mode: Some(wgpu::Face::Back),
-            polygon_mode: wgpu::PolygonMode::Fill,
-            unclipped_depth: false,
-            conservative: false,
-        },
-        depth_stencil: Some(DepthStencilState{
-            format: DEPTH_FORMAT,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-        multisample: wgpu::MultisampleState::default(),
-        multiview: None,
-        cache: Default::default(),
-    });
+    let mut skybox = SkyboxRenderer::new(&device, &mut pipeline_cache);
 
-    let mut depth_texture = GpuTexture::create_depth_texture(&device, &config, "depth_texture");
+    // MSAA=<1|2|4|8> задаёт число выборок, клавиша M переключает его по кругу
+    let requested_samples = std::env::var("MSAA").ok().and_then(|v| v.parse().ok()).unwrap_or(4);
+    let mut msaa = MsaaTargets::new(&adapter, &device, config.width, config.height, HDR_FORMAT, requested_samples)
+        .or_else(|err| {
+            println!("{}, falling back to 1x", err);
+            MsaaTargets::new(&adapter, &device, config.width, config.height, HDR_FORMAT, 1)
+        })
+        .unwrap();
+    let mut last_frame = Instant::now();
+    let mut cursor = PhysicalPosition::new(0.0, 0.0);
 
     event_loop.run( |event, elwt: &winit::event_loop::EventLoopWindowTarget<()>| {
         match event {
@@ -276,12 +154,45 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
                 surface.configure(&device, &config);
                 aspect_ratio = new_size.width as f32 / new_size.height as f32;
 
-                depth_texture = GpuTexture::create_depth_texture(&device, &config, "depth_texture");
+                msaa.resize(&device, config.width, config.height);
+                post_process.resize(&device, config.width, config.height);
             }
             Event::WindowEvent {
                     event: WindowEvent::RedrawRequested,
                     ..
             } => {
+                if let Some(hot_reload) = hot_reload.as_mut() {
+                    let changed = hot_reload.poll();
+                    pipeline_cache.update_shaders(&hot_reload.composer, &changed);
+                    post_process.reload_shaders(&hot_reload.composer, &changed);
+                }
+                // Handle'ы модели сохраняются, а SceneRenderer пересобирает
+                // материалы по версиям ресурсов
+                if let Some(asset_hot_reload) = asset_hot_reload.as_mut() {
+                    asset_hot_reload.poll(&mut assets, &device, &queue);
+                }
+
+                // Поза анимации, затем матрицы узлов и палитры суставов скинов
+                animation_player.update(last_frame.elapsed().as_secs_f32());
+                last_frame = Instant::now();
+                let pose = animation_player.sample(&assets.animations);
+                if let Some(model_scene) = assets.scenes.get_mut(model_scene.clone()) {
+                    pose.apply_to_scene(model_scene);
+                }
+                // Камера обновляется до отсечения и выбора LOD
+                camera_controler.update_camera(&mut camera, &queue);
+                scene_renderer.target = scene_key.with_sample_count(msaa.sample_count());
+                if let Some(model_scene) = assets.scenes.get(model_scene.clone()) {
+                    scene_renderer
+                        .prepare(&device, &queue, &mut pipeline_cache, &assets, model_scene, &camera)
+                        .unwrap();
+                }
+
+                skybox.prepare(&device, &scene, &assets.textures);
+                let skybox_key = PipelineKey::new(PipelineType::Skybox, HDR_FORMAT).with_sample_count(msaa.sample_count());
+                let skybox_pipeline = pipeline_cache.pipeline(&device, skybox_key).unwrap();
+                let camera_bind_group = camera.get_bind_group().unwrap();
+
                 let frame = surface.get_current_texture().unwrap();
                 let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -292,41 +203,22 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
                 {
                     let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                         label: Some("Render Pass"),
-                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                            view: &view,
-                            resolve_target: None,
-                            ops: wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
-                                store: wgpu::StoreOp::Store,
-                            },
-                        })],
-                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                            view: &depth_texture.view,
-                            depth_ops: Some(wgpu::Operations {
-                                load: wgpu::LoadOp::Clear(1.0),
-                                store: wgpu::StoreOp::Store,
-                            }),
-                            stencil_ops: None,
-                        }),
+                        color_attachments: &[Some(
+                            msaa.color_attachment(post_process.scene_view(), wgpu::LoadOp::Clear(wgpu::Color::BLACK)),
+                        )],
+                        depth_stencil_attachment: Some(msaa.depth_attachment()),
                         timestamp_writes: Default::default(),
                         occlusion_query_set: Default::default(),
                     });
 
-                    camera_controler.update_camera(&mut camera, &queue);
-        
-                    render_pass.set_pipeline(&skybox_pipeline);
-                    render_pass.set_bind_group(0, &camera.get_bind_group(), &[]);
-                    render_pass.set_vertex_buffer(0, skybox_vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(skybox_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-                    render_pass.draw_indexed(0..skybox_indices.len() as u32, 0, 0..1);
-
-                    render_pass.set_pipeline(&render_pipeline);
-                    render_pass.set_bind_group(0, &camera.get_bind_group(), &[]);
-                    render_pass.set_vertex_buffer(0, cube.unwrap().vertex_buffer.slice(..));
-                    render_pass.set_index_buffer(cube.unwrap().index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-                    render_pass.draw_indexed(0..cube.unwrap().indices.len() as u32, 0, 0..1);
+                    scene_renderer.draw(&mut render_pass, &assets, &camera_bind_group, &lighting_bind_group);
+
+                    // Небо после непрозрачной геометрии: остаётся только в пустых пикселях
+                    skybox.draw(&mut render_pass, &skybox_pipeline, &camera_bind_group);
                 }
 
+                post_process.render(&device, &queue, &mut encoder, &view, config.format).unwrap();
+
                 queue.submit(std::iter::once(encoder.finish()));
                 frame.present();
                 window.request_redraw();
@@ -335,6 +227,56 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
                 event: key_event,
                 ..
             } => {
+                if let WindowEvent::KeyboardInput {
+                    event: KeyEvent { physical_key: PhysicalKey::Code(code), state: ElementState::Pressed, repeat: false, .. },
+                    ..
+                } = &key_event {
+                    if *code == KeyCode::KeyM {
+                        let next = next_sample_count(msaa.sample_count(), msaa.supported_sample_counts());
+                        match msaa.set_sample_count(&device, next) {
+                            Ok(changed) => {
+                                if changed {
+                                    // Число выборок входит в состояние пайплайна, пересобираем зависящие
+                                    pipeline_cache.retain_sample_count(next);
+                                }
+                                println!("MSAA: {}x", next);
+                            }
+                            Err(err) => println!("{}", err),
+                        }
+                    }
+                    let effect = match code {
+                        KeyCode::Digit1 => Some(PostEffectKind::Bloom),
+                        KeyCode::Digit2 => Some(PostEffectKind::Tonemap),
+                        KeyCode::Digit3 => Some(PostEffectKind::ColorGrading),
+                        KeyCode::Digit4 => Some(PostEffectKind::Fxaa),
+                        KeyCode::Digit5 => Some(PostEffectKind::Vignette),
+                        _ => None,
+                    };
+                    if let Some(effect) = effect {
+                        println!("{:?}: {:?}", effect, post_process.chain.toggle(effect));
+                    }
+                }
+                if let WindowEvent::CursorMoved { position, .. } = &key_event {
+                    cursor = *position;
+                }
+                // Щелчок выбирает узел модели под курсором
+                if let WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } = &key_event {
+                    if let SceneEntityKind::Camera { camera: lens, .. } = &camera.kind {
+                        let ndc = Vec2::new(
+                            2.0 * cursor.x as f32 / config.width as f32 - 1.0,
+                            1.0 - 2.0 * cursor.y as f32 / config.height as f32,
+                        );
+                        let (origin, direction) = camera.transform.screen_ray(lens, ndc);
+                        let picked = assets.scenes.get(model_scene.clone()).and_then(|scene| {
+                            let pick = scene.raycast(&assets.models, &assets.meshes, origin, direction)?;
+                            Some((scene.nodes[pick.node].name.clone(), pick.hit.distance))
+                        });
+                        match picked {
+                            Some((name, distance)) => println!("Picked '{}' at {:.2}", name, distance),
+                            None => println!("Nothing picked"),
+                        }
+                    }
+                }
                 camera_controler.process_events(&key_event);
             }
             _ => (),
@@ -0,0 +1,64 @@
+// Освещение от окружения (image-based lighting): диффузная часть из SH9,
+// зеркальная — из предфильтрованной кубической карты и BRDF LUT (split-sum)
+
+struct Environment {
+    // Коэффициенты SH9, уже свёрнутые с косинусом (rgb в xyz)
+    irradiance_sh: array<vec4<f32>, 9>,
+    // x — число mip-уровней зеркальной карты, y — интенсивность
+    params: vec4<f32>,
+};
+
+@group(1) @binding(1)
+var<uniform> environment: Environment;
+
+@group(1) @binding(2)
+var specular_map: texture_cube<f32>;
+
+@group(1) @binding(3)
+var environment_sampler: sampler;
+
+@group(1) @binding(4)
+var brdf_lut: texture_2d<f32>;
+
+// Облучённость для нормали `n` (без деления на π)
+fn irradiance_sh(n: vec3<f32>) -> vec3<f32> {
+    let sh = environment.irradiance_sh;
+    let result = sh[0].xyz * 0.282095
+        + sh[1].xyz * 0.488603 * n.y
+        + sh[2].xyz * 0.488603 * n.z
+        + sh[3].xyz * 0.488603 * n.x
+        + sh[4].xyz * 1.092548 * n.x * n.y
+        + sh[5].xyz * 1.092548 * n.y * n.z
+        + sh[6].xyz * 0.315392 * (3.0 * n.z * n.z - 1.0)
+        + sh[7].xyz * 1.092548 * n.x * n.z
+        + sh[8].xyz * 0.546274 * (n.x * n.x - n.y * n.y);
+    return max(result, vec3<f32>(0.0));
+}
+
+fn fresnel_schlick_roughness(cos_theta: f32, f0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    return f0 + (max(vec3<f32>(1.0 - roughness), f0) - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// Фоновое освещение материала от окружения; `f0` — отражение при нормальном
+// падении (`material_f0`)
+fn ambient_ibl(
+    normal: vec3<f32>,
+    view_dir: vec3<f32>,
+    albedo: vec3<f32>,
+    f0: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+) -> vec3<f32> {
+    let n_dot_v = max(dot(normal, view_dir), 1e-4);
+    let fresnel = fresnel_schlick_roughness(n_dot_v, f0, roughness);
+    let kd = (vec3<f32>(1.0) - fresnel) * (1.0 - metallic);
+    let diffuse = kd * albedo * irradiance_sh(normal) * 0.318310;
+
+    let reflected = reflect(-view_dir, normal);
+    let lod = roughness * (environment.params.x - 1.0);
+    let prefiltered = textureSampleLevel(specular_map, environment_sampler, reflected, lod).rgb;
+    let brdf = textureSampleLevel(brdf_lut, environment_sampler, vec2<f32>(n_dot_v, roughness), 0.0).rg;
+    let specular = prefiltered * (fresnel * brdf.x + brdf.y);
+
+    return (diffuse + specular) * environment.params.y;
+}
@@ -15,6 +15,28 @@ fn calculate_lighting(normal: vec3<f32>) -> vec3<f32> {
     return result;
 }
 
+// Блики точечных источников по GGX со Шликом; `f0` — отражение при нормальном падении
+fn calculate_specular(normal: vec3<f32>, view_dir: vec3<f32>, f0: vec3<f32>, roughness: f32) -> vec3<f32> {
+    let alpha = max(roughness * roughness, 1e-3);
+    let alpha2 = alpha * alpha;
+    let k = alpha * 0.5;
+    let n_dot_v = max(dot(normal, view_dir), 1e-4);
+    var result = vec3<f32>(0.0);
+    for (var i = 0u; i < arrayLength(&lights); i++) {
+        let light_dir = normalize(lights[i].position);
+        let half_dir = normalize(light_dir + view_dir);
+        let n_dot_l = max(dot(normal, light_dir), 0.0);
+        let n_dot_h = max(dot(normal, half_dir), 0.0);
+        let denom = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+        let distribution = alpha2 / (3.14159265 * denom * denom);
+        let geometry = n_dot_l / (n_dot_l * (1.0 - k) + k) / (n_dot_v * (1.0 - k) + k);
+        let fresnel = f0 + (vec3<f32>(1.0) - f0) * pow(1.0 - max(dot(half_dir, view_dir), 0.0), 5.0);
+        // n·v из геометрии сокращается со знаменателем 4(n·l)(n·v), n·l — с косинусом падения
+        result += lights[i].color * distribution * geometry * fresnel * 0.25;
+    }
+    return result;
+}
+
 
 
 
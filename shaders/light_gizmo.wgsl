@@ -0,0 +1,28 @@
+#import camera::Camera
+
+struct GizmoLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+}
+
+@group(1) @binding(0)
+var<uniform> light: GizmoLight;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> VertexOutput {
+    let scale = 0.25;
+    var output: VertexOutput;
+    output.clip_position = camera.view_proj * vec4<f32>(position * scale + light.position.xyz, 1.0);
+    output.color = light.color.rgb;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(input.color, 1.0);
+}
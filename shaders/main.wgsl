@@ -1,25 +1,46 @@
 #import camera::Camera
-#import lighting::{PointLight, calculate_lighting}
+#import lighting::{PointLight, calculate_lighting, calculate_specular}
+#ifdef IBL
+#import ibl::ambient_ibl
+#import material::{Material, material_uv, material_f0, material_alpha, material_layers, SLOT_BASE_COLOR, SLOT_METALLIC_ROUGHNESS, SLOT_NORMAL, SLOT_OCCLUSION, SLOT_EMISSIVE}
+#endif
 
 struct Transform {
     model: mat4x4<f32>,
-    // view: mat4x4<f32>,
-    // projection: mat4x4<f32>,
 }
 
-@group(0) @binding(0)
+@group(2) @binding(0)
 var<uniform> transform: Transform;
 
-@group(1) @binding(0)
+#ifdef SKINNED
+// Матрицы суставов в пространстве меша (`JointPalette`)
+@group(2) @binding(1)
+var<storage, read> joint_matrices: array<mat4x4<f32>>;
+#endif
+
+@group(3) @binding(0)
 var base_color_texture: texture_2d<f32>;
 
-@group(1) @binding(1)
+@group(3) @binding(1)
 var texture_sampler: sampler;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) tex_coords: vec2<f32>,
+#ifdef SKINNED
+    @location(3) joints: vec4<u32>,
+    @location(4) weights: vec4<f32>,
+#endif
+#ifdef VERTEX_TANGENT
+    @location(5) tangent: vec4<f32>,
+#endif
+#ifdef VERTEX_COLOR
+    @location(6) color: vec4<f32>,
+#endif
+#ifdef VERTEX_UV1
+    @location(7) tex_coords_1: vec2<f32>,
+#endif
 }
 
 struct VertexOutput {
@@ -27,45 +48,122 @@ struct VertexOutput {
     @location(0) tex_coords: vec2<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) world_position: vec3<f32>,
+#ifdef VERTEX_COLOR
+    @location(3) color: vec4<f32>,
+#endif
+#ifdef VERTEX_TANGENT
+    @location(4) tangent: vec4<f32>,
+#endif
+#ifdef VERTEX_UV1
+    @location(5) tex_coords_1: vec2<f32>,
+#endif
 }
 
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
 
-    // let model_view = transform.view * transform.model;
-    let model_view = transform.model;
-    // let mvp = transform.projection * model_view;
-    let mvp = model_view;
-
-    output.clip_position = mvp * vec4<f32>(input.position * 0.3, 1.0);
+#ifdef SKINNED
+    let skin = joint_matrices[input.joints.x] * input.weights.x
+        + joint_matrices[input.joints.y] * input.weights.y
+        + joint_matrices[input.joints.z] * input.weights.z
+        + joint_matrices[input.joints.w] * input.weights.w;
+    let model = transform.model * skin;
+#else
+    let model = transform.model;
+#endif
+
+    let world_position = model * vec4<f32>(input.position, 1.0);
+    output.clip_position = camera.view_proj * world_position;
     output.tex_coords = input.tex_coords;
+#ifdef VERTEX_UV1
+    output.tex_coords_1 = input.tex_coords_1;
+#endif
 
     let normal_matrix = mat3x3<f32>(
-        transform.model[0].xyz,
-        transform.model[1].xyz,
-        transform.model[2].xyz
+        model[0].xyz,
+        model[1].xyz,
+        model[2].xyz
     );
     output.normal = normalize(normal_matrix * input.normal);
-    output.world_position = (transform.model * vec4<f32>(input.position, 1.0)).xyz;
+    output.world_position = world_position.xyz;
+#ifdef VERTEX_TANGENT
+    output.tangent = vec4<f32>(normalize(normal_matrix * input.tangent.xyz), input.tangent.w);
+#endif
+#ifdef VERTEX_COLOR
+    output.color = input.color;
+#endif
 
     return output;
 }
 
-
-
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    let texture_color = textureSample(base_color_texture, texture_sampler, input.tex_coords).rgb;
-    let light_dir = normalize(vec3<f32>(1.0, 1.0, 1.0));
-    // let view_dir = normalize(camera.view_pos.xyz - in.world_position);
-    // let half_dir = normalize(view_dir + light_dir);
-
-    let light_color = vec3<f32>(1.0, 1.0, 1.0);
-    let ambient = 0.1;
-    let diffuse = max(dot(input.normal, light_dir), 0.0);
-    let lighting = ambient + diffuse;
-
-    return vec4<f32>(texture_color * lighting * light_color, 1.0);
+#ifdef IBL
+#ifdef VERTEX_UV1
+    let uv1 = input.tex_coords_1;
+#else
+    // Без TEXCOORD_1 все текстуры читаются по TEXCOORD_0
+    let uv1 = input.tex_coords;
+#endif
+    let uv0 = input.tex_coords;
+    let base_uv = material_uv(SLOT_BASE_COLOR, uv0, uv1);
+    var sampled = textureSample(base_color_texture, texture_sampler, base_uv) * material.base_color;
+    // Все выборки до discard, пока поток управления однороден
+    let metallic_roughness = textureSample(
+        metallic_roughness_texture,
+        texture_sampler,
+        material_uv(SLOT_METALLIC_ROUGHNESS, uv0, uv1)
+    );
+    let occlusion_sample = textureSample(occlusion_texture, texture_sampler, material_uv(SLOT_OCCLUSION, uv0, uv1)).r;
+    let emissive_sample = textureSample(emissive_texture, texture_sampler, material_uv(SLOT_EMISSIVE, uv0, uv1));
+    let emissive = material.emissive * emissive_sample.rgb;
+#ifdef VERTEX_TANGENT
+    let normal_uv = material_uv(SLOT_NORMAL, uv0, uv1);
+    let normal_sample = textureSample(normal_texture, texture_sampler, normal_uv).xyz * 2.0 - 1.0;
+#endif
+#else
+    var sampled = textureSample(base_color_texture, texture_sampler, input.tex_coords);
+#endif
+#ifdef VERTEX_COLOR
+    // COLOR_0 glTF умножается на базовый цвет
+    sampled = sampled * input.color;
+#endif
+    let texture_color = sampled.rgb;
+#ifdef UNLIT
+    return vec4<f32>(texture_color, 1.0);
+#else
+#ifdef IBL
+    // Порог AlphaMode::Mask; у остальных режимов он равен 0
+    if sampled.a < material.alpha_cutoff {
+        discard;
+    }
+    let metallic = material.metallic * metallic_roughness.b;
+    let roughness = material.roughness * metallic_roughness.g;
+    let occlusion = mix(1.0, occlusion_sample, material.occlusion_strength);
+#ifdef VERTEX_TANGENT
+    // Карта нормалей нужна касательная; без неё остаётся нормаль вершины
+    let vertex_normal = normalize(input.normal);
+    let tangent = normalize(input.tangent.xyz - vertex_normal * dot(vertex_normal, input.tangent.xyz));
+    let bitangent = cross(vertex_normal, tangent) * input.tangent.w;
+    let scaled = vec3<f32>(normal_sample.xy * material.normal_scale, normal_sample.z);
+    let normal = normalize(mat3x3<f32>(tangent, bitangent, vertex_normal) * scaled);
+#else
+    let normal = normalize(input.normal);
+#endif
+    let view_dir = normalize(camera.position - input.world_position);
+    let f0 = material_f0(texture_color, metallic);
+    let ambient = ambient_ibl(normal, view_dir, texture_color, f0, metallic, roughness) * occlusion;
+    let diffuse = texture_color * calculate_lighting(normal) * (1.0 - metallic);
+    let base = ambient + diffuse + calculate_specular(normal, view_dir, f0, roughness);
+    let n_dot_v = max(dot(normal, view_dir), 0.0);
+    let color = material_layers(base, n_dot_v, ambient) + emissive;
+    return vec4<f32>(color, material_alpha(sampled.a, metallic));
+#else
+    let normal = normalize(input.normal);
+    let ambient = vec3<f32>(0.1);
+    let lighting = ambient + calculate_lighting(normal);
+    return vec4<f32>(texture_color * lighting, 1.0);
+#endif
+#endif
 }
-
@@ -0,0 +1,78 @@
+// Параметры PBR-материала с расширениями KHR_materials_* (`MaterialUniform`)
+
+struct Material {
+    base_color: vec4<f32>,
+    // Уже умножено на emissive_strength
+    emissive: vec3<f32>,
+    normal_scale: f32,
+    metallic: f32,
+    roughness: f32,
+    occlusion_strength: f32,
+    alpha_cutoff: f32,
+    sheen_color: vec3<f32>,
+    sheen_roughness: f32,
+    specular_f0: vec3<f32>,
+    transmission: f32,
+    clearcoat: f32,
+    clearcoat_roughness: f32,
+    ior: f32,
+    // Бит SLOT_*: текстура читается по TEXCOORD_1
+    uv_sets: u32,
+    // Строки матриц KHR_texture_transform, по две на текстуру SLOT_*
+    uv_transforms: array<vec4<f32>, 10>,
+};
+
+// Номера текстур материала (`TextureSlot`)
+const SLOT_BASE_COLOR: u32 = 0u;
+const SLOT_METALLIC_ROUGHNESS: u32 = 1u;
+const SLOT_NORMAL: u32 = 2u;
+const SLOT_OCCLUSION: u32 = 3u;
+const SLOT_EMISSIVE: u32 = 4u;
+
+@group(3) @binding(2)
+var<uniform> material: Material;
+
+// Читаются сэмплером базового цвета (binding 1)
+@group(3) @binding(3)
+var metallic_roughness_texture: texture_2d<f32>;
+
+@group(3) @binding(4)
+var normal_texture: texture_2d<f32>;
+
+@group(3) @binding(5)
+var occlusion_texture: texture_2d<f32>;
+
+@group(3) @binding(6)
+var emissive_texture: texture_2d<f32>;
+
+// UV текстуры `slot` из её набора с преобразованием KHR_texture_transform
+fn material_uv(slot: u32, uv0: vec2<f32>, uv1: vec2<f32>) -> vec2<f32> {
+    let uv = select(uv0, uv1, (material.uv_sets & (1u << slot)) != 0u);
+    let p = vec3<f32>(uv, 1.0);
+    let row = slot * 2u;
+    return vec2<f32>(dot(material.uv_transforms[row].xyz, p), dot(material.uv_transforms[row + 1u].xyz, p));
+}
+
+// F0 с учётом IOR, specular и металличности (уже умноженной на текстуру)
+fn material_f0(albedo: vec3<f32>, metallic: f32) -> vec3<f32> {
+    return mix(material.specular_f0, albedo, metallic);
+}
+
+// Пропускание приближается прозрачностью: фон виден через смешивание
+// (`Material::render_alpha_mode`), но без преломления
+fn material_alpha(albedo_alpha: f32, metallic: f32) -> f32 {
+    return albedo_alpha * (1.0 - material.transmission * (1.0 - metallic));
+}
+
+// Накладывает sheen (ткань) и clearcoat (лак) поверх базового слоя
+fn material_layers(base: vec3<f32>, n_dot_v: f32, ambient: vec3<f32>) -> vec3<f32> {
+    // Sheen: отблеск по краям, тем шире, чем выше шероховатость
+    let edge = pow(1.0 - n_dot_v, 5.0 - 4.0 * material.sheen_roughness);
+    let sheen = material.sheen_color * edge * ambient;
+
+    // Clearcoat: диэлектрик с F0 = 0.04, ослабляющий нижний слой на величину своего отражения
+    let fresnel = 0.04 + 0.96 * pow(1.0 - n_dot_v, 5.0);
+    let coat = material.clearcoat * fresnel;
+    let gloss = 1.0 - material.clearcoat_roughness * 0.5;
+    return base * (1.0 - coat) + sheen + ambient * coat * gloss;
+}
@@ -0,0 +1,132 @@
+#import fullscreen::{FullscreenOutput, fullscreen_vertex}
+#import tonemap::uncharted2WithExposure
+
+struct PostParams {
+    // 1 / размер исходной текстуры
+    texel_size: vec2<f32>,
+    // Направление размытия (только для bloom)
+    direction: vec2<f32>,
+    // Параметры эффекта, см. PostEffect
+    params: vec4<f32>,
+};
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> post: PostParams;
+
+@group(1) @binding(0) var bloom_texture: texture_2d<f32>;
+@group(1) @binding(1) var lut_texture: texture_3d<f32>;
+
+const LUMA = vec3<f32>(0.299, 0.587, 0.114);
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> FullscreenOutput {
+    return fullscreen_vertex(index);
+}
+
+fn sample_source(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(source_texture, source_sampler, uv, 0.0).rgb;
+}
+
+@fragment
+fn fs_blit(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(sample_source(in.uv), 1.0);
+}
+
+// params.x — exposure bias
+@fragment
+fn fs_tonemap(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(uncharted2WithExposure(sample_source(in.uv), post.params.x), 1.0);
+}
+
+// params.x — порог яркости, params.y — мягкость порога
+@fragment
+fn fs_bloom_prefilter(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+    let threshold = post.params.x;
+    let knee = threshold * post.params.y;
+    let brightness = max(color.r, max(color.g, color.b));
+
+    var soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+    let contribution = max(soft, brightness - threshold) / max(brightness, 1e-4);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+// Отделимое гауссово размытие на 9 выборок вдоль post.direction
+@fragment
+fn fs_bloom_blur(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    let step = post.direction * post.texel_size;
+
+    var result = sample_source(in.uv) * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = step * f32(i);
+        result += (sample_source(in.uv + offset) + sample_source(in.uv - offset)) * weights[i];
+    }
+    return vec4<f32>(result, 1.0);
+}
+
+// params.x — интенсивность bloom
+@fragment
+fn fs_bloom_composite(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let bloom = textureSampleLevel(bloom_texture, source_sampler, in.uv, 0.0).rgb;
+    return vec4<f32>(sample_source(in.uv) + post.params.x * bloom, 1.0);
+}
+
+// params.x — сила эффекта
+@fragment
+fn fs_color_grading(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let color = sample_source(in.uv);
+    let size = f32(textureDimensions(lut_texture).x);
+    let coords = saturate(color) * ((size - 1.0) / size) + 0.5 / size;
+    let graded = textureSampleLevel(lut_texture, source_sampler, coords, 0.0).rgb;
+    return vec4<f32>(mix(color, graded, post.params.x), 1.0);
+}
+
+// FXAA по мотивам FXAA 3.11 (консольная версия)
+@fragment
+fn fs_fxaa(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let reduce_min = 1.0 / 128.0;
+    let reduce_mul = 1.0 / 8.0;
+    let span_max = 8.0;
+    let texel = post.texel_size;
+
+    let luma_nw = dot(sample_source(in.uv + vec2<f32>(-1.0, -1.0) * texel), LUMA);
+    let luma_ne = dot(sample_source(in.uv + vec2<f32>(1.0, -1.0) * texel), LUMA);
+    let luma_sw = dot(sample_source(in.uv + vec2<f32>(-1.0, 1.0) * texel), LUMA);
+    let luma_se = dot(sample_source(in.uv + vec2<f32>(1.0, 1.0) * texel), LUMA);
+    let luma_m = dot(sample_source(in.uv), LUMA);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * (0.25 * reduce_mul), reduce_min);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2<f32>(-span_max), vec2<f32>(span_max)) * texel;
+
+    let rgb_a = 0.5 * (
+        sample_source(in.uv + dir * (1.0 / 3.0 - 0.5)) +
+        sample_source(in.uv + dir * (2.0 / 3.0 - 0.5))
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        sample_source(in.uv - dir * 0.5) +
+        sample_source(in.uv + dir * 0.5)
+    );
+    let luma_b = dot(rgb_b, LUMA);
+    if luma_b < luma_min || luma_b > luma_max {
+        return vec4<f32>(rgb_a, 1.0);
+    }
+    return vec4<f32>(rgb_b, 1.0);
+}
+
+// params.x — сила затемнения, params.y — ширина перехода
+@fragment
+fn fs_vignette(in: FullscreenOutput) -> @location(0) vec4<f32> {
+    let distance_to_center = distance(in.uv, vec2<f32>(0.5)) * 1.41421356;
+    let falloff = smoothstep(1.0 - post.params.y, 1.0, distance_to_center);
+    return vec4<f32>(sample_source(in.uv) * (1.0 - post.params.x * falloff), 1.0);
+}
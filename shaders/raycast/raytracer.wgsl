@@ -1,3 +1,5 @@
+#import tonemap::uncharted2
+
 const EPSILON = 0.001f;
 
 const PI = 3.1415927f;
@@ -81,28 +83,6 @@ fn fsMain(in: VertexOutput) -> @location(0) vec4<f32> {
     );
 }
 
-fn uncharted2(x: vec3<f32>) -> vec3<f32> {
-    // Based on uncharted2 tonemapping function
-    // https://dmnsgn.github.io/glsl-tone-map/
-    let exposureBias = 0.246;   // determined experimentally for the scene
-    let curr = uncharted2Tonemap(exposureBias * x);
-
-    let w = 11.2;
-    let whiteScale = 1f / uncharted2Tonemap(vec3(w));
-    return whiteScale * curr;
-}
-
-fn uncharted2Tonemap(x: vec3<f32>) -> vec3<f32> {
-    let a = 0.15;
-    let b = 0.50;
-    let c = 0.10;
-    let d = 0.20;
-    let e = 0.02;
-    let f = 0.30;
-    let w = 11.2;
-    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
-}
-
 fn samplePixel(x: u32, y: u32, rngState: ptr<function, u32>) -> vec3<f32> {
     let imageWidth = frameData.x;
     let imageHeight = frameData.y;
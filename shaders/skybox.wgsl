@@ -1,9 +1,9 @@
-struct Camera {
-    view_proj: mat4x4<f32>,
-}
+#import camera::Camera
 
-@group(0) @binding(0)
-var<uniform> camera: Camera;
+@group(1) @binding(0)
+var skybox_texture: texture_cube<f32>;
+@group(1) @binding(1)
+var skybox_sampler: sampler;
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
@@ -13,7 +13,9 @@ struct VertexOutput {
 @vertex
 fn vs_main(@location(0) position: vec3<f32>) -> VertexOutput {
     var output: VertexOutput;
-    output.position = camera.view_proj * vec4<f32>(position, 1.0);
+    // Куб следует за камерой, а z = w кладёт его на дальнюю плоскость
+    let clip = camera.view_proj * vec4<f32>(position + camera.position, 1.0);
+    output.position = clip.xyww;
     output.tex_coords = position;
     return output;
 }
@@ -21,6 +23,10 @@ fn vs_main(@location(0) position: vec3<f32>) -> VertexOutput {
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
     let direction = normalize(input.tex_coords);
+#ifdef PROCEDURAL_SKY
     let sky_color = mix(vec3<f32>(0.1, 0.3, 0.8), vec3<f32>(0.6, 0.8, 1.0), direction.y * 0.5 + 0.5);
     return vec4<f32>(sky_color, 1.0);
-}
\ No newline at end of file
+#else
+    return textureSample(skybox_texture, skybox_sampler, direction);
+#endif
+}
@@ -0,0 +1,28 @@
+// Тонмаппинг, общий для трассировщика и растеризатора
+
+// Подобран экспериментально для сцены трассировщика
+const UNCHARTED2_EXPOSURE_BIAS = 0.246;
+
+fn uncharted2(x: vec3<f32>) -> vec3<f32> {
+    return uncharted2WithExposure(x, UNCHARTED2_EXPOSURE_BIAS);
+}
+
+fn uncharted2WithExposure(x: vec3<f32>, exposureBias: f32) -> vec3<f32> {
+    // Based on uncharted2 tonemapping function
+    // https://dmnsgn.github.io/glsl-tone-map/
+    let curr = uncharted2Tonemap(exposureBias * x);
+
+    let w = 11.2;
+    let whiteScale = 1f / uncharted2Tonemap(vec3(w));
+    return whiteScale * curr;
+}
+
+fn uncharted2Tonemap(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
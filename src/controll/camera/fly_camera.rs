@@ -126,6 +126,7 @@ impl FlyCameraController {
             self.update_camera_transform(transform);
             let view_proj = transform.calculate_view_projection(&camera);
             uniform.update_view_proj(view_proj);
+            uniform.update_view_position(transform.position);
             queue.write_buffer(
                 &camera_entity.buffer,
                 0,
@@ -0,0 +1,107 @@
+//! Выбор уровня детализации мешей по размеру на экране.
+//!
+//! Размер — диаметр сферы вокруг [`Mesh::bounds`] в долях высоты кадра;
+//! [`LodPolicy`] переводит его в номер уровня для [`Mesh::lod`].
+
+use glam::Mat4;
+
+use crate::{
+    math::bounds::Aabb,
+    res::mesh::Mesh,
+    scene::{camera::Camera, transform::Transform},
+};
+
+/// Доля высоты кадра, которую занимает сфера вокруг `bounds` после
+/// преобразования `model`; бесконечность, если камера внутри сферы
+pub fn projected_size(bounds: &Aabb, model: &Mat4, view: &Mat4, projection: &Mat4) -> f32 {
+    if bounds.is_empty() {
+        return 0.0;
+    }
+    let sphere = bounds.bounding_sphere().transformed(model);
+    // В перспективе w после проекции — глубина, в ортографии — 1
+    let w = (*projection * *view * sphere.center.extend(1.0)).w;
+    let perspective = projection.z_axis.w != 0.0;
+    if perspective && w <= sphere.radius {
+        return f32::INFINITY;
+    }
+    sphere.radius * projection.y_axis.y.abs() / w
+}
+
+/// Пороги размера на экране для уровней детализации
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodPolicy {
+    /// По убыванию: размер меньше `screen_sizes[i]` даёт уровень `i + 1`
+    pub screen_sizes: Vec<f32>,
+    /// Множитель размера; меньше 1 — грубые уровни включаются раньше
+    pub bias: f32,
+}
+
+impl Default for LodPolicy {
+    fn default() -> Self {
+        Self {
+            screen_sizes: vec![0.5, 0.25, 0.1],
+            bias: 1.0,
+        }
+    }
+}
+
+impl LodPolicy {
+    pub fn level_for_size(&self, size: f32) -> usize {
+        let size = size * self.bias;
+        self.screen_sizes.iter().take_while(|&&threshold| size < threshold).count()
+    }
+
+    /// Уровень меша с матрицей `model`; не больше последнего уровня меша
+    pub fn select(&self, mesh: &Mesh, model: &Mat4, view: &Mat4, projection: &Mat4) -> usize {
+        if mesh.lods.is_empty() {
+            return 0;
+        }
+        let size = projected_size(&mesh.bounds, model, view, projection);
+        self.level_for_size(size).min(mesh.lods.len())
+    }
+
+    /// Уровень для активной камеры сцены; проекция та же, что в
+    /// [`Transform::calculate_view_projection`] (`fov` в градусах)
+    pub fn select_for_camera(&self, mesh: &Mesh, model: &Mat4, camera: &Camera, camera_transform: &Transform) -> usize {
+        let projection = Mat4::perspective_rh(camera.fov.to_radians(), camera.aspect, camera.near, camera.far);
+        self.select(mesh, model, &camera_transform.view_matrix(), &projection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn test_projected_size() {
+        let bounds = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+        let radius = 3f32.sqrt() * 0.5;
+        let projection = Mat4::perspective_rh(90f32.to_radians(), 1.0, 0.1, 100.0);
+        let view = |distance: f32| Mat4::look_at_rh(Vec3::new(0.0, 0.0, distance), Vec3::ZERO, Vec3::Y);
+
+        let near = projected_size(&bounds, &Mat4::IDENTITY, &view(5.0), &projection);
+        assert!((near - radius / 5.0).abs() < 1e-5);
+        let far = projected_size(&bounds, &Mat4::IDENTITY, &view(10.0), &projection);
+        assert!((far - near / 2.0).abs() < 1e-5);
+        let scaled = projected_size(&bounds, &Mat4::from_scale(Vec3::splat(2.0)), &view(10.0), &projection);
+        assert!((scaled - near).abs() < 1e-5);
+        assert_eq!(projected_size(&bounds, &Mat4::IDENTITY, &view(0.5), &projection), f32::INFINITY);
+
+        let orthographic = Mat4::orthographic_rh(-2.0, 2.0, -2.0, 2.0, 0.1, 100.0);
+        let size = projected_size(&bounds, &Mat4::IDENTITY, &view(50.0), &orthographic);
+        assert!((size - radius / 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_level_for_size() {
+        let mut policy = LodPolicy::default();
+        assert_eq!(policy.level_for_size(f32::INFINITY), 0);
+        assert_eq!(policy.level_for_size(0.6), 0);
+        assert_eq!(policy.level_for_size(0.3), 1);
+        assert_eq!(policy.level_for_size(0.01), 3);
+        policy.bias = 2.0;
+        assert_eq!(policy.level_for_size(0.3), 0);
+    }
+}
@@ -4,12 +4,194 @@ pub mod app;
 pub mod window;
 pub mod renderer;
 pub mod raytracer;
+pub mod shader;
+pub mod pipeline_cache;
+pub mod post_process;
+pub mod msaa;
+pub mod skybox;
+pub mod lod;
+pub mod scene_renderer;
 
+use crate::{
+    res::{
+        material::MATERIAL_BIND_GROUP_LAYOUT_ENTRIES,
+        scin::SKINNED_TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES,
+        texture::{
+            gpu_texture::{CUBE_TEXTURE_BIND_GROUP_LAYOUT_ENTRIES, TEXTURE_BIND_GROUP_LAYOUT_ENTRIES},
+            ibl::LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES,
+        },
+    },
+    scene::{
+        camera::CAMERA_BIND_GROUP_LAYOUT_ENTRIES,
+        light::{LIGHT_BIND_GROUP_LAYOUT_ENTRIES, POINT_LIGHTS_BIND_GROUP_LAYOUT_ENTRIES},
+        transform::TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES,
+    },
+};
 
 /// Типы пайплайнов рендеринга
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PipelineType {
     /// Простой пайплайн для отрисовки мешей
     Simple,
+    /// Меш с текстурой, точечными источниками и освещением от окружения
+    Lit,
+    /// [`PipelineType::Lit`] для мешей со скелетом ([`SkinnedVertex`](crate::res::vertex::SkinnedVertex))
+    LitSkinned,
+    /// Меш с текстурой без освещения
+    Unlit,
+    /// Небо из кубической карты, рисуется после непрозрачной геометрии
+    Skybox,
+    /// Клетчатый пол
+    Floor,
+    /// Каркасное отображение меша (требует `Features::POLYGON_MODE_LINE`)
+    Wireframe,
+    /// Маркер источника света
+    LightGizmo,
+    /// Меш из отрезков (`LINES`, `LINE_STRIP`, `LINE_LOOP` в glTF) цветом
+    /// материала без освещения; bind group'ы те же, что у [`PipelineType::Lit`]
+    Lines,
+    /// Меш из точек, как [`PipelineType::Lines`]
+    Points,
 }
 
+type BindGroupLayoutEntries = &'static [wgpu::BindGroupLayoutEntry];
+
+impl PipelineType {
+    pub const ALL: [PipelineType; 10] = [
+        PipelineType::Simple,
+        PipelineType::Lit,
+        PipelineType::LitSkinned,
+        PipelineType::Unlit,
+        PipelineType::Skybox,
+        PipelineType::Floor,
+        PipelineType::Wireframe,
+        PipelineType::LightGizmo,
+        PipelineType::Lines,
+        PipelineType::Points,
+    ];
+
+    /// Тип для топологии меша: отрезки и точки рисуются своими пайплайнами
+    pub fn for_topology(self, topology: wgpu::PrimitiveTopology) -> Self {
+        match topology {
+            wgpu::PrimitiveTopology::LineList | wgpu::PrimitiveTopology::LineStrip => PipelineType::Lines,
+            wgpu::PrimitiveTopology::PointList => PipelineType::Points,
+            _ => self,
+        }
+    }
+
+    /// Имя модуля шейдера в [`shader::ShaderComposer`]
+    pub fn shader(self) -> &'static str {
+        match self {
+            PipelineType::Simple
+            | PipelineType::Lit
+            | PipelineType::LitSkinned
+            | PipelineType::Unlit
+            | PipelineType::Wireframe
+            | PipelineType::Lines
+            | PipelineType::Points => "main",
+            PipelineType::Skybox => "skybox",
+            PipelineType::Floor => "floor",
+            PipelineType::LightGizmo => "light_gizmo",
+        }
+    }
+
+    /// Define'ы, с которыми собирается шейдер
+    pub fn defines(self) -> &'static [&'static str] {
+        match self {
+            PipelineType::Lit => &["IBL"],
+            PipelineType::LitSkinned => &["IBL", "SKINNED"],
+            // IBL подключает материал, UNLIT отключает освещение
+            PipelineType::Lines | PipelineType::Points => &["IBL", "UNLIT"],
+            PipelineType::Unlit | PipelineType::Wireframe => &["UNLIT"],
+            _ => &[],
+        }
+    }
+
+    /// Записи layout'ов bind group'ов по номерам групп
+    pub fn bind_group_layouts(self) -> &'static [BindGroupLayoutEntries] {
+        match self {
+            PipelineType::Lit | PipelineType::Lines | PipelineType::Points => &[
+                CAMERA_BIND_GROUP_LAYOUT_ENTRIES,
+                LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES,
+                TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES,
+                MATERIAL_BIND_GROUP_LAYOUT_ENTRIES,
+            ],
+            PipelineType::LitSkinned => &[
+                CAMERA_BIND_GROUP_LAYOUT_ENTRIES,
+                LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES,
+                SKINNED_TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES,
+                MATERIAL_BIND_GROUP_LAYOUT_ENTRIES,
+            ],
+            PipelineType::Simple | PipelineType::Unlit | PipelineType::Wireframe => &[
+                CAMERA_BIND_GROUP_LAYOUT_ENTRIES,
+                POINT_LIGHTS_BIND_GROUP_LAYOUT_ENTRIES,
+                TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES,
+                TEXTURE_BIND_GROUP_LAYOUT_ENTRIES,
+            ],
+            PipelineType::Skybox => &[
+                CAMERA_BIND_GROUP_LAYOUT_ENTRIES,
+                CUBE_TEXTURE_BIND_GROUP_LAYOUT_ENTRIES,
+            ],
+            PipelineType::Floor => &[CAMERA_BIND_GROUP_LAYOUT_ENTRIES],
+            PipelineType::LightGizmo => &[
+                CAMERA_BIND_GROUP_LAYOUT_ENTRIES,
+                LIGHT_BIND_GROUP_LAYOUT_ENTRIES,
+            ],
+        }
+    }
+
+    /// Раскладка вершин по умолчанию; меши с другими атрибутами задают свою
+    /// через [`PipelineKey::with_vertex_layout`](pipeline_cache::PipelineKey::with_vertex_layout)
+    pub fn vertex_layout(self) -> wgpu::VertexBufferLayout<'static> {
+        match self {
+            PipelineType::Skybox => pipeline_cache::POSITION_VERTEX_LAYOUT,
+            PipelineType::Floor => pipeline_cache::POSITION_UV_VERTEX_LAYOUT,
+            PipelineType::LitSkinned => crate::res::vertex::SkinnedVertex::desc(),
+            _ => crate::res::vertex::Vertex::desc(),
+        }
+    }
+
+    pub fn primitive(self) -> wgpu::PrimitiveState {
+        let (cull_mode, polygon_mode) = match self {
+            PipelineType::Skybox | PipelineType::Lines | PipelineType::Points => (None, wgpu::PolygonMode::Fill),
+            PipelineType::Wireframe => (None, wgpu::PolygonMode::Line),
+            _ => (Some(wgpu::Face::Back), wgpu::PolygonMode::Fill),
+        };
+        let topology = match self {
+            PipelineType::Lines => wgpu::PrimitiveTopology::LineList,
+            PipelineType::Points => wgpu::PrimitiveTopology::PointList,
+            _ => wgpu::PrimitiveTopology::TriangleList,
+        };
+        wgpu::PrimitiveState {
+            topology,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode,
+            polygon_mode,
+            unclipped_depth: false,
+            conservative: false,
+        }
+    }
+
+    /// Состояние теста глубины; небо рисуется на дальней плоскости без записи глубины
+    pub fn depth_stencil(self, format: wgpu::TextureFormat) -> wgpu::DepthStencilState {
+        let (depth_write_enabled, depth_compare) = match self {
+            PipelineType::Skybox => (false, wgpu::CompareFunction::LessEqual),
+            _ => (true, wgpu::CompareFunction::Less),
+        };
+        wgpu::DepthStencilState {
+            format,
+            depth_write_enabled,
+            depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+
+    pub fn required_features(self) -> wgpu::Features {
+        match self {
+            PipelineType::Wireframe => wgpu::Features::POLYGON_MODE_LINE,
+            _ => wgpu::Features::empty(),
+        }
+    }
+}
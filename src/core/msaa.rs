@@ -0,0 +1,241 @@
+//! Мультисэмплинг (MSAA) для растровых пайплайнов.
+//!
+//! [`MsaaTargets`] владеет multisampled цветом и глубиной; цвет разрешается
+//! в обычную цель (поверхность или HDR-текстуру постобработки). После смены
+//! числа выборок пайплайны нужно пересобрать: оно входит в [`PipelineKey`].
+//!
+//! [`PipelineKey`]: super::pipeline_cache::PipelineKey
+
+use crate::res::texture::gpu_texture::{GpuTexture, DEPTH_FORMAT};
+
+/// Допустимые значения числа выборок
+pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum MsaaError {
+    #[error("sample count {0} is not one of 1, 2, 4, 8")]
+    InvalidSampleCount(u32),
+    #[error("sample count {count} is not supported by the adapter, supported: {supported:?}")]
+    Unsupported { count: u32, supported: Vec<u32> },
+}
+
+/// Числа выборок, которые устройство примет одновременно для всех `formats`.
+/// Возможности конкретного адаптера (2x, 8x) доступны, только если устройство
+/// создано с `TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`; без неё остаются
+/// гарантированные WebGPU 1 и 4.
+pub fn supported_sample_counts(
+    adapter: &wgpu::Adapter,
+    device: &wgpu::Device,
+    formats: &[wgpu::TextureFormat],
+) -> Vec<u32> {
+    let features = device.features();
+    if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+        common_sample_counts(formats, |format| adapter.get_texture_format_features(format).flags)
+    } else {
+        common_sample_counts(formats, |format| format.guaranteed_format_features(features).flags)
+    }
+}
+
+fn common_sample_counts(
+    formats: &[wgpu::TextureFormat],
+    flags: impl Fn(wgpu::TextureFormat) -> wgpu::TextureFormatFeatureFlags,
+) -> Vec<u32> {
+    SAMPLE_COUNTS
+        .into_iter()
+        .filter(|&count| formats.iter().all(|&format| flags(format).sample_count_supported(count)))
+        .collect()
+}
+
+/// Проверяет, что `count` — степень двойки до 8 и есть среди `supported`
+pub fn validate_sample_count(count: u32, supported: &[u32]) -> Result<u32, MsaaError> {
+    if !SAMPLE_COUNTS.contains(&count) {
+        return Err(MsaaError::InvalidSampleCount(count));
+    }
+    if count != 1 && !supported.contains(&count) {
+        return Err(MsaaError::Unsupported {
+            count,
+            supported: supported.to_vec(),
+        });
+    }
+    Ok(count)
+}
+
+/// Следующее поддерживаемое число выборок по кругу: 1 → 2 → 4 → 8 → 1
+pub fn next_sample_count(count: u32, supported: &[u32]) -> u32 {
+    SAMPLE_COUNTS
+        .into_iter()
+        .filter(|candidate| *candidate == 1 || supported.contains(candidate))
+        .find(|&candidate| candidate > count)
+        .unwrap_or(1)
+}
+
+/// Цели render pass'а с учётом MSAA
+pub struct MsaaTargets {
+    sample_count: u32,
+    supported: Vec<u32>,
+    color_format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+    /// Multisampled цвет; `None` при одной выборке
+    color: Option<GpuTexture>,
+    depth: GpuTexture,
+}
+
+impl MsaaTargets {
+    /// Создаёт цели для цвета в `color_format` и глубины [`DEPTH_FORMAT`]
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Result<Self, MsaaError> {
+        let supported = supported_sample_counts(adapter, device, &[color_format, DEPTH_FORMAT]);
+        let sample_count = validate_sample_count(sample_count, &supported)?;
+        let (color, depth) = Self::create_textures(device, width, height, color_format, sample_count);
+
+        Ok(Self {
+            sample_count,
+            supported,
+            color_format,
+            width,
+            height,
+            color,
+            depth,
+        })
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    pub fn supported_sample_counts(&self) -> &[u32] {
+        &self.supported
+    }
+
+    pub fn multisample_state(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        }
+    }
+
+    /// Меняет число выборок. Возвращает `true`, если цели пересозданы
+    /// и зависящие от них пайплайны нужно пересобрать.
+    pub fn set_sample_count(&mut self, device: &wgpu::Device, sample_count: u32) -> Result<bool, MsaaError> {
+        let sample_count = validate_sample_count(sample_count, &self.supported)?;
+        if sample_count == self.sample_count {
+            return Ok(false);
+        }
+        self.sample_count = sample_count;
+        (self.color, self.depth) =
+            Self::create_textures(device, self.width, self.height, self.color_format, sample_count);
+        Ok(true)
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        (self.color, self.depth) =
+            Self::create_textures(device, width, height, self.color_format, self.sample_count);
+    }
+
+    /// Цветовое вложение, разрешаемое в `target` (при одной выборке рисуем прямо в него)
+    pub fn color_attachment<'a>(
+        &'a self,
+        target: &'a wgpu::TextureView,
+        load: wgpu::LoadOp<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        match &self.color {
+            Some(color) => wgpu::RenderPassColorAttachment {
+                view: &color.view,
+                resolve_target: Some(target),
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Discard,
+                },
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load,
+                    store: wgpu::StoreOp::Store,
+                },
+            },
+        }
+    }
+
+    pub fn depth_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.depth.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(1.0),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    pub fn depth_texture(&self) -> &GpuTexture {
+        &self.depth
+    }
+
+    fn create_textures(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        color_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> (Option<GpuTexture>, GpuTexture) {
+        let color = (sample_count > 1).then(|| {
+            GpuTexture::create_render_target(device, width, height, color_format, sample_count, "msaa_color")
+        });
+        let depth =
+            GpuTexture::create_multisampled_depth_texture(device, width, height, sample_count, "msaa_depth");
+        (color, depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_sample_count() {
+        let supported = [1, 4];
+        assert_eq!(validate_sample_count(1, &supported), Ok(1));
+        assert_eq!(validate_sample_count(4, &supported), Ok(4));
+        assert_eq!(validate_sample_count(3, &supported), Err(MsaaError::InvalidSampleCount(3)));
+        assert_eq!(
+            validate_sample_count(8, &supported),
+            Err(MsaaError::Unsupported {
+                count: 8,
+                supported: vec![1, 4],
+            })
+        );
+        // Одна выборка доступна всегда
+        assert_eq!(validate_sample_count(1, &[]), Ok(1));
+    }
+
+    #[test]
+    fn test_next_sample_count() {
+        let supported = [1, 4, 8];
+        assert_eq!(next_sample_count(1, &supported), 4);
+        assert_eq!(next_sample_count(4, &supported), 8);
+        assert_eq!(next_sample_count(8, &supported), 1);
+        assert_eq!(next_sample_count(1, &[1]), 1);
+    }
+
+    #[test]
+    fn test_guaranteed_sample_counts() {
+        // Без расширенных возможностей адаптера WebGPU гарантирует только 1 и 4
+        let features = wgpu::Features::empty();
+        let counts = common_sample_counts(&[wgpu::TextureFormat::Rgba16Float, DEPTH_FORMAT], |format| {
+            format.guaranteed_format_features(features).flags
+        });
+        assert_eq!(counts, vec![1, 4]);
+    }
+}
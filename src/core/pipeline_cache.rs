@@ -0,0 +1,347 @@
+//! Кэш GPU-объектов, которые не зависят от конкретного ресурса.
+//!
+//! Одинаковые bind group layout'ы, сэмплеры и render pipeline'ы создаются
+//! один раз и переиспользуются: ключом служит содержимое дескриптора.
+
+use std::collections::HashMap;
+
+pub use crate::res::texture::sampler::SamplerKey;
+use crate::res::{
+    material::{AlphaMode, Material},
+    texture::sampler::SamplerCache,
+    vertex::VertexLayout,
+};
+
+use super::{
+    shader::{with_error_scope, ShaderComposer, ShaderError},
+    PipelineType,
+};
+
+/// Вершина из одной позиции (`[f32; 3]`), используется небом
+pub const POSITION_VERTEX_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[wgpu::VertexAttribute {
+        offset: 0,
+        shader_location: 0,
+        format: wgpu::VertexFormat::Float32x3,
+    }],
+};
+
+/// Вершина из позиции и UV (`[f32; 5]`), используется полом
+pub const POSITION_UV_VERTEX_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+    array_stride: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode::Vertex,
+    attributes: &[
+        wgpu::VertexAttribute {
+            offset: 0,
+            shader_location: 0,
+            format: wgpu::VertexFormat::Float32x3,
+        },
+        wgpu::VertexAttribute {
+            offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+            shader_location: 1,
+            format: wgpu::VertexFormat::Float32x2,
+        },
+    ],
+};
+
+/// Ключ пайплайна: тип и параметры целевого render pass'а
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub kind: PipelineType,
+    pub color_format: wgpu::TextureFormat,
+    pub depth_format: Option<wgpu::TextureFormat>,
+    pub sample_count: u32,
+    /// Раскладка вершин меша; `None` — [`PipelineType::vertex_layout`]
+    pub vertex_layout: Option<VertexLayout>,
+    /// [`AlphaMode::Blend`] включает смешивание и отключает запись глубины
+    pub alpha_mode: AlphaMode,
+    /// Без отсечения задних граней
+    pub double_sided: bool,
+}
+
+impl PipelineKey {
+    pub fn new(kind: PipelineType, color_format: wgpu::TextureFormat) -> Self {
+        Self {
+            kind,
+            color_format,
+            depth_format: Some(crate::res::texture::gpu_texture::DEPTH_FORMAT),
+            sample_count: 1,
+            vertex_layout: None,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = sample_count;
+        self
+    }
+
+    /// Пайплайн под вершины меша (`Mesh::layout`)
+    pub fn with_vertex_layout(mut self, layout: VertexLayout) -> Self {
+        self.vertex_layout = Some(layout);
+        self
+    }
+
+    /// Пайплайн под режим прозрачности ([`Material::render_alpha_mode`])
+    /// и двусторонность материала
+    pub fn with_material(mut self, material: &Material) -> Self {
+        self.alpha_mode = material.render_alpha_mode();
+        self.double_sided = material.double_sided;
+        self
+    }
+}
+
+/// Центральный кэш layout'ов, сэмплеров и пайплайнов
+pub struct PipelineCache {
+    composer: ShaderComposer,
+    bind_group_layouts: HashMap<Vec<wgpu::BindGroupLayoutEntry>, wgpu::BindGroupLayout>,
+    samplers: SamplerCache,
+    pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+}
+
+impl PipelineCache {
+    pub fn new(composer: ShaderComposer) -> Self {
+        Self {
+            composer,
+            bind_group_layouts: HashMap::new(),
+            samplers: SamplerCache::new(),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    pub fn composer(&self) -> &ShaderComposer {
+        &self.composer
+    }
+
+    /// Заменяет реестр шейдеров и сбрасывает пайплайны, зависящие от `changed`
+    /// (используется вместе с горячей перезагрузкой)
+    pub fn update_shaders(&mut self, composer: &ShaderComposer, changed: &[String]) {
+        self.composer = composer.clone();
+        self.pipelines
+            .retain(|key, _| !changed.iter().any(|name| name == key.kind.shader()));
+    }
+
+    /// Возвращает layout для набора записей, создавая его при первом запросе
+    pub fn bind_group_layout(
+        &mut self,
+        device: &wgpu::Device,
+        entries: &[wgpu::BindGroupLayoutEntry],
+    ) -> wgpu::BindGroupLayout {
+        self.bind_group_layouts
+            .entry(entries.to_vec())
+            .or_insert_with(|| {
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("cached_bind_group_layout"),
+                    entries,
+                })
+            })
+            .clone()
+    }
+
+    pub fn sampler(
+        &mut self,
+        device: &wgpu::Device,
+        desc: &wgpu::SamplerDescriptor,
+    ) -> wgpu::Sampler {
+        self.samplers.get_or_create(device, desc)
+    }
+
+    /// Возвращает пайплайн для ключа, собирая его при первом запросе
+    pub fn pipeline(
+        &mut self,
+        device: &wgpu::Device,
+        key: PipelineKey,
+    ) -> Result<wgpu::RenderPipeline, ShaderError> {
+        if let Some(pipeline) = self.pipelines.get(&key) {
+            return Ok(pipeline.clone());
+        }
+        let pipeline = self.create_pipeline(device, key)?;
+        self.pipelines.insert(key, pipeline.clone());
+        Ok(pipeline)
+    }
+
+    /// Удаляет пайплайны, собранные под другое число выборок (после смены MSAA)
+    pub fn retain_sample_count(&mut self, sample_count: u32) {
+        self.pipelines.retain(|key, _| key.sample_count == sample_count);
+    }
+
+    pub fn clear_pipelines(&mut self) {
+        self.pipelines.clear();
+    }
+
+    pub fn debug_stats(&self) -> String {
+        format!(
+            "PipelineCache: {} bind group layouts, {} samplers, {} pipelines",
+            self.bind_group_layouts.len(),
+            self.samplers.len(),
+            self.pipelines.len()
+        )
+    }
+
+    fn create_pipeline(
+        &mut self,
+        device: &wgpu::Device,
+        key: PipelineKey,
+    ) -> Result<wgpu::RenderPipeline, ShaderError> {
+        let kind = key.kind;
+        let missing = kind.required_features() - device.features();
+        if !missing.is_empty() {
+            return Err(ShaderError::Device {
+                name: kind.shader().to_string(),
+                message: format!("{:?} pipeline requires {:?}", kind, missing),
+            });
+        }
+
+        let layouts: Vec<wgpu::BindGroupLayout> = kind
+            .bind_group_layouts()
+            .iter()
+            .map(|entries| self.bind_group_layout(device, entries))
+            .collect();
+        let layout_refs: Vec<&wgpu::BindGroupLayout> = layouts.iter().collect();
+        let label = format!("{:?} pipeline", kind);
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&label),
+            bind_group_layouts: &layout_refs,
+            push_constant_ranges: &[],
+        });
+        let vertex_layout = key.vertex_layout.as_ref();
+        let mut defines = kind.defines().to_vec();
+        defines.extend(vertex_layout.map(VertexLayout::defines).unwrap_or_default());
+        let shader = self
+            .composer
+            .create_shader_module(device, kind.shader(), &defines)?;
+
+        let blended = key.alpha_mode == AlphaMode::Blend;
+        let mut primitive = kind.primitive();
+        if key.double_sided {
+            primitive.cull_mode = None;
+        }
+        let depth_stencil = key.depth_format.map(|format| {
+            let mut state = kind.depth_stencil(format);
+            // Прозрачное не закрывает то, что нарисовано после него
+            state.depth_write_enabled &= !blended;
+            state
+        });
+
+        with_error_scope(device, kind.shader(), || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(&label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[vertex_layout.map_or_else(|| kind.vertex_layout(), VertexLayout::desc)],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: key.color_format,
+                        blend: Some(if blended {
+                            wgpu::BlendState::ALPHA_BLENDING
+                        } else {
+                            wgpu::BlendState::REPLACE
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive,
+                depth_stencil,
+                multisample: wgpu::MultisampleState {
+                    count: key.sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+                cache: None,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Каждый тип пайплайна должен собираться, а его глобальные ресурсы —
+    /// совпадать с объявленными layout'ами
+    #[test]
+    fn test_pipeline_types_match_shader_bindings() {
+        let composer = ShaderComposer::with_default_modules();
+        for kind in PipelineType::ALL {
+            let module = composer
+                .compose_with_defines(kind.shader(), kind.defines())
+                .unwrap()
+                .validate()
+                .unwrap();
+            let groups = kind.bind_group_layouts();
+
+            for (_, global) in module.global_variables.iter() {
+                let Some(binding) = &global.binding else {
+                    continue;
+                };
+                let entries = groups
+                    .get(binding.group as usize)
+                    .unwrap_or_else(|| panic!("{:?}: group {} has no layout", kind, binding.group));
+                assert!(
+                    entries.iter().any(|entry| entry.binding == binding.binding),
+                    "{:?}: binding {}:{} has no layout entry",
+                    kind,
+                    binding.group,
+                    binding.binding
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn test_material_pipeline() {
+        use crate::res::vertex::VertexAttribute;
+
+        let (device, _) = crate::res::test::test_device();
+        let mut cache = PipelineCache::new(ShaderComposer::with_default_modules());
+        let material = Material {
+            alpha_mode: AlphaMode::Blend,
+            double_sided: true,
+            ..Material::default()
+        };
+        let layout = VertexLayout::new(&[
+            VertexAttribute::Position,
+            VertexAttribute::Normal,
+            VertexAttribute::TexCoord0,
+            VertexAttribute::Tangent,
+        ]);
+        let key = PipelineKey::new(PipelineType::Lit, crate::core::post_process::HDR_FORMAT)
+            .with_vertex_layout(layout)
+            .with_material(&material);
+        cache.pipeline(&device, key).unwrap();
+        cache.pipeline(&device, PipelineKey { alpha_mode: AlphaMode::Mask, ..key }).unwrap();
+        // Отрезки и точки рисуются с теми же bind group'ами, что и Lit
+        for topology in [wgpu::PrimitiveTopology::LineList, wgpu::PrimitiveTopology::PointList] {
+            let kind = PipelineType::Lit.for_topology(topology);
+            assert_eq!(kind.bind_group_layouts(), PipelineType::Lit.bind_group_layouts());
+            cache.pipeline(&device, PipelineKey { kind, ..key }).unwrap();
+        }
+        assert_eq!(cache.pipelines.len(), 4);
+    }
+
+    #[test]
+    fn test_vertex_layout_defines() {
+        let composer = ShaderComposer::with_default_modules();
+        for defines in [
+            ["IBL", "VERTEX_COLOR", "VERTEX_TANGENT"],
+            ["IBL", "SKINNED", "VERTEX_TANGENT"],
+            ["IBL", "VERTEX_UV1", "VERTEX_TANGENT"],
+            ["UNLIT", "VERTEX_COLOR", "VERTEX_TANGENT"],
+        ] {
+            composer.compose_with_defines("main", &defines).unwrap().validate().unwrap();
+        }
+    }
+}
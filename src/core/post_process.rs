@@ -0,0 +1,868 @@
+//! Постобработка растеризатора.
+//!
+//! Сцена рисуется в HDR-текстуру [`HDR_FORMAT`], после чего цепочка
+//! полноэкранных проходов переводит её в формат поверхности. Каждый эффект —
+//! узел [`PostNode`], узлы можно включать, выключать и переставлять на лету.
+
+use std::collections::HashMap;
+
+use wgpu::util::DeviceExt;
+
+use crate::res::texture::gpu_texture::GpuTexture;
+
+use super::{
+    pipeline_cache::PipelineCache,
+    shader::{with_error_scope, ShaderComposer, ShaderError},
+};
+
+/// Формат HDR-цели, в которую рисуется сцена
+pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Модуль шейдера постобработки в [`ShaderComposer`]
+const POST_SHADER: &str = "post";
+
+#[derive(thiserror::Error, Debug)]
+pub enum PostProcessError {
+    #[error(transparent)]
+    Shader(#[from] ShaderError),
+    #[error("color grading LUT must be a size² x size strip, got {width}x{height}")]
+    InvalidLut { width: u32, height: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PostEffectKind {
+    Bloom,
+    Tonemap,
+    ColorGrading,
+    Fxaa,
+    Vignette,
+}
+
+/// Эффект постобработки и его настройки
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PostEffect {
+    /// Свечение ярких областей, имеет смысл до тонмаппинга
+    Bloom {
+        threshold: f32,
+        knee: f32,
+        intensity: f32,
+    },
+    /// Тонмаппинг uncharted2, та же кривая, что и в трассировщике
+    Tonemap { exposure_bias: f32 },
+    /// Цветокоррекция по 3D LUT
+    ColorGrading { strength: f32 },
+    Fxaa,
+    Vignette { intensity: f32, smoothness: f32 },
+}
+
+impl PostEffect {
+    pub fn kind(&self) -> PostEffectKind {
+        match self {
+            PostEffect::Bloom { .. } => PostEffectKind::Bloom,
+            PostEffect::Tonemap { .. } => PostEffectKind::Tonemap,
+            PostEffect::ColorGrading { .. } => PostEffectKind::ColorGrading,
+            PostEffect::Fxaa => PostEffectKind::Fxaa,
+            PostEffect::Vignette { .. } => PostEffectKind::Vignette,
+        }
+    }
+
+    /// Настройки эффекта по умолчанию
+    pub fn new(kind: PostEffectKind) -> Self {
+        match kind {
+            PostEffectKind::Bloom => PostEffect::Bloom {
+                threshold: 1.0,
+                knee: 0.5,
+                intensity: 0.3,
+            },
+            PostEffectKind::Tonemap => PostEffect::Tonemap { exposure_bias: 0.246 },
+            PostEffectKind::ColorGrading => PostEffect::ColorGrading { strength: 1.0 },
+            PostEffectKind::Fxaa => PostEffect::Fxaa,
+            PostEffectKind::Vignette => PostEffect::Vignette {
+                intensity: 0.3,
+                smoothness: 0.5,
+            },
+        }
+    }
+}
+
+/// Узел цепочки постобработки
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PostNode {
+    pub effect: PostEffect,
+    pub enabled: bool,
+}
+
+/// Порядок и настройки эффектов, без GPU-ресурсов
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostProcessChain {
+    nodes: Vec<PostNode>,
+}
+
+impl Default for PostProcessChain {
+    /// Bloom → Tonemap → ColorGrading → FXAA → Vignette, всё включено
+    fn default() -> Self {
+        Self {
+            nodes: [
+                PostEffectKind::Bloom,
+                PostEffectKind::Tonemap,
+                PostEffectKind::ColorGrading,
+                PostEffectKind::Fxaa,
+                PostEffectKind::Vignette,
+            ]
+            .into_iter()
+            .map(|kind| PostNode {
+                effect: PostEffect::new(kind),
+                enabled: true,
+            })
+            .collect(),
+        }
+    }
+}
+
+impl PostProcessChain {
+    /// Пустая цепочка: сцена копируется на экран без изменений
+    pub fn empty() -> Self {
+        Self { nodes: Vec::new() }
+    }
+
+    pub fn nodes(&self) -> &[PostNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, kind: PostEffectKind) -> Option<&PostNode> {
+        self.nodes.iter().find(|node| node.effect.kind() == kind)
+    }
+
+    pub fn node_mut(&mut self, kind: PostEffectKind) -> Option<&mut PostNode> {
+        self.nodes.iter_mut().find(|node| node.effect.kind() == kind)
+    }
+
+    /// Добавляет эффект в конец цепочки или заменяет настройки существующего
+    pub fn push(&mut self, effect: PostEffect) {
+        match self.node_mut(effect.kind()) {
+            Some(node) => node.effect = effect,
+            None => self.nodes.push(PostNode {
+                effect,
+                enabled: true,
+            }),
+        }
+    }
+
+    /// Возвращает `false`, если эффекта нет в цепочке
+    pub fn set_enabled(&mut self, kind: PostEffectKind, enabled: bool) -> bool {
+        match self.node_mut(kind) {
+            Some(node) => {
+                node.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Переключает эффект и возвращает новое состояние
+    pub fn toggle(&mut self, kind: PostEffectKind) -> Option<bool> {
+        let node = self.node_mut(kind)?;
+        node.enabled = !node.enabled;
+        Some(node.enabled)
+    }
+
+    /// Перемещает эффект на позицию `index` (с ограничением длиной цепочки)
+    pub fn move_to(&mut self, kind: PostEffectKind, index: usize) -> bool {
+        let Some(position) = self.nodes.iter().position(|node| node.effect.kind() == kind) else {
+            return false;
+        };
+        let node = self.nodes.remove(position);
+        let index = index.min(self.nodes.len());
+        self.nodes.insert(index, node);
+        true
+    }
+
+    pub fn enabled_effects(&self) -> impl Iterator<Item = PostEffect> + '_ {
+        self.nodes.iter().filter(|node| node.enabled).map(|node| node.effect)
+    }
+
+    /// Раскладывает включённые эффекты на полноэкранные проходы
+    fn plan(&self) -> Vec<PostDraw> {
+        let effects: Vec<PostEffect> = self.enabled_effects().collect();
+        if effects.is_empty() {
+            return vec![PostDraw::new(PostPass::Blit, PostSlot::Scene, PostSlot::Output)];
+        }
+
+        let mut draws = Vec::new();
+        let mut source = PostSlot::Scene;
+        for (index, effect) in effects.iter().enumerate() {
+            let target = if index + 1 == effects.len() {
+                PostSlot::Output
+            } else {
+                PostSlot::PingPong(index % 2)
+            };
+            let draw = match *effect {
+                PostEffect::Bloom {
+                    threshold,
+                    knee,
+                    intensity,
+                } => {
+                    draws.extend([
+                        PostDraw::new(PostPass::BloomPrefilter, source, PostSlot::Bloom(0))
+                            .with_params([threshold, knee, 0.0, 0.0]),
+                        PostDraw::new(PostPass::BloomBlur, PostSlot::Bloom(0), PostSlot::Bloom(1))
+                            .with_direction([1.0, 0.0]),
+                        PostDraw::new(PostPass::BloomBlur, PostSlot::Bloom(1), PostSlot::Bloom(0))
+                            .with_direction([0.0, 1.0]),
+                    ]);
+                    PostDraw::new(PostPass::BloomComposite, source, target)
+                        .with_params([intensity, 0.0, 0.0, 0.0])
+                }
+                PostEffect::Tonemap { exposure_bias } => {
+                    PostDraw::new(PostPass::Tonemap, source, target)
+                        .with_params([exposure_bias, 0.0, 0.0, 0.0])
+                }
+                PostEffect::ColorGrading { strength } => {
+                    PostDraw::new(PostPass::ColorGrading, source, target)
+                        .with_params([strength, 0.0, 0.0, 0.0])
+                }
+                PostEffect::Fxaa => PostDraw::new(PostPass::Fxaa, source, target),
+                PostEffect::Vignette {
+                    intensity,
+                    smoothness,
+                } => PostDraw::new(PostPass::Vignette, source, target)
+                    .with_params([intensity, smoothness, 0.0, 0.0]),
+            };
+            draws.push(draw);
+            source = target;
+        }
+        draws
+    }
+}
+
+/// Полноэкранный проход: точка входа фрагментного шейдера модуля `post`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum PostPass {
+    Blit,
+    Tonemap,
+    BloomPrefilter,
+    BloomBlur,
+    BloomComposite,
+    ColorGrading,
+    Fxaa,
+    Vignette,
+}
+
+impl PostPass {
+    fn entry_point(self) -> &'static str {
+        match self {
+            PostPass::Blit => "fs_blit",
+            PostPass::Tonemap => "fs_tonemap",
+            PostPass::BloomPrefilter => "fs_bloom_prefilter",
+            PostPass::BloomBlur => "fs_bloom_blur",
+            PostPass::BloomComposite => "fs_bloom_composite",
+            PostPass::ColorGrading => "fs_color_grading",
+            PostPass::Fxaa => "fs_fxaa",
+            PostPass::Vignette => "fs_vignette",
+        }
+    }
+}
+
+/// Текстура, участвующая в проходе
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PostSlot {
+    Scene,
+    PingPong(usize),
+    /// Цели bloom в половинном разрешении
+    Bloom(usize),
+    Output,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PostDraw {
+    pass: PostPass,
+    source: PostSlot,
+    target: PostSlot,
+    direction: [f32; 2],
+    params: [f32; 4],
+}
+
+impl PostDraw {
+    fn new(pass: PostPass, source: PostSlot, target: PostSlot) -> Self {
+        Self {
+            pass,
+            source,
+            target,
+            direction: [0.0; 2],
+            params: [0.0; 4],
+        }
+    }
+
+    fn with_direction(mut self, direction: [f32; 2]) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    fn with_params(mut self, params: [f32; 4]) -> Self {
+        self.params = params;
+        self
+    }
+}
+
+/// Uniform `PostParams` из `post.wgsl`
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct PostUniform {
+    texel_size: [f32; 2],
+    direction: [f32; 2],
+    params: [f32; 4],
+}
+
+/// Группа 0: исходная текстура, сэмплер и параметры прохода
+pub const POST_SOURCE_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 2,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+];
+
+/// Группа 1 прохода bloom composite: размытые яркие области
+pub const POST_BLOOM_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[wgpu::BindGroupLayoutEntry {
+    binding: 0,
+    visibility: wgpu::ShaderStages::FRAGMENT,
+    ty: wgpu::BindingType::Texture {
+        multisampled: false,
+        view_dimension: wgpu::TextureViewDimension::D2,
+        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+    },
+    count: None,
+}];
+
+/// Группа 1 прохода цветокоррекции: 3D LUT
+pub const POST_LUT_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[wgpu::BindGroupLayoutEntry {
+    binding: 1,
+    visibility: wgpu::ShaderStages::FRAGMENT,
+    ty: wgpu::BindingType::Texture {
+        multisampled: false,
+        view_dimension: wgpu::TextureViewDimension::D3,
+        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+    },
+    count: None,
+}];
+
+/// 3D таблица цветокоррекции в RGBA8: `r` меняется быстрее всего, затем `g`, затем `b`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorLut {
+    size: u32,
+    data: Vec<u8>,
+}
+
+impl ColorLut {
+    /// Таблица, не меняющая цвет
+    pub fn identity(size: u32) -> Self {
+        let size = size.max(2);
+        let scale = 255.0 / (size - 1) as f32;
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.extend([
+                        (r as f32 * scale).round() as u8,
+                        (g as f32 * scale).round() as u8,
+                        (b as f32 * scale).round() as u8,
+                        255,
+                    ]);
+                }
+            }
+        }
+        Self { size, data }
+    }
+
+    /// Читает таблицу из горизонтальной полосы `size² x size`:
+    /// `size` квадратов по синему каналу, в каждом `r` по горизонтали и `g` по вертикали
+    pub fn from_strip(image: &image::RgbaImage) -> Result<Self, PostProcessError> {
+        let (width, height) = image.dimensions();
+        if height < 2 || width != height * height {
+            return Err(PostProcessError::InvalidLut { width, height });
+        }
+
+        let size = height;
+        let mut data = Vec::with_capacity((size * size * size * 4) as usize);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    data.extend(image.get_pixel(b * size + r, g).0);
+                }
+            }
+        }
+        Ok(Self { size, data })
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn texel(&self, r: u32, g: u32, b: u32) -> [u8; 4] {
+        let index = (((b * self.size + g) * self.size + r) * 4) as usize;
+        [
+            self.data[index],
+            self.data[index + 1],
+            self.data[index + 2],
+            self.data[index + 3],
+        ]
+    }
+
+    pub fn create_texture(&self, device: &wgpu::Device, queue: &wgpu::Queue) -> GpuTexture {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("color_grading_lut"),
+                size: wgpu::Extent3d {
+                    width: self.size,
+                    height: self.size,
+                    depth_or_array_layers: self.size,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D3,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &self.data,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&post_sampler_descriptor());
+
+        GpuTexture {
+            texture,
+            view,
+            sampler,
+        }
+    }
+}
+
+fn post_sampler_descriptor() -> wgpu::SamplerDescriptor<'static> {
+    wgpu::SamplerDescriptor {
+        label: Some("post_process_sampler"),
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        ..Default::default()
+    }
+}
+
+/// HDR-цель сцены и GPU-ресурсы цепочки постобработки
+pub struct PostProcessStack {
+    /// Эффекты и их порядок; изменения применяются со следующего кадра
+    pub chain: PostProcessChain,
+    composer: ShaderComposer,
+    shader: Option<wgpu::ShaderModule>,
+    width: u32,
+    height: u32,
+    scene_color: GpuTexture,
+    ping_pong: [GpuTexture; 2],
+    bloom_targets: [GpuTexture; 2],
+    lut: GpuTexture,
+    source_layout: wgpu::BindGroupLayout,
+    bloom_layout: wgpu::BindGroupLayout,
+    lut_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    uniform_buffers: Vec<wgpu::Buffer>,
+    pipelines: HashMap<(PostPass, wgpu::TextureFormat), wgpu::RenderPipeline>,
+}
+
+impl PostProcessStack {
+    /// Создаёт цели размером `width x height` и цепочку по умолчанию
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        cache: &mut PipelineCache,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        let (scene_color, ping_pong, bloom_targets) = Self::create_targets(device, width, height);
+
+        Self {
+            chain: PostProcessChain::default(),
+            composer: cache.composer().clone(),
+            shader: None,
+            width,
+            height,
+            scene_color,
+            ping_pong,
+            bloom_targets,
+            lut: ColorLut::identity(16).create_texture(device, queue),
+            source_layout: cache.bind_group_layout(device, POST_SOURCE_LAYOUT_ENTRIES),
+            bloom_layout: cache.bind_group_layout(device, POST_BLOOM_LAYOUT_ENTRIES),
+            lut_layout: cache.bind_group_layout(device, POST_LUT_LAYOUT_ENTRIES),
+            sampler: cache.sampler(device, &post_sampler_descriptor()),
+            uniform_buffers: Vec::new(),
+            pipelines: HashMap::new(),
+        }
+    }
+
+    /// Вью HDR-текстуры, в которую нужно рисовать сцену
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.scene_color.view
+    }
+
+    pub fn scene_texture(&self) -> &GpuTexture {
+        &self.scene_color
+    }
+
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        let (scene_color, ping_pong, bloom_targets) = Self::create_targets(device, width, height);
+        self.scene_color = scene_color;
+        self.ping_pong = ping_pong;
+        self.bloom_targets = bloom_targets;
+        self.width = width;
+        self.height = height;
+    }
+
+    pub fn set_lut(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, lut: &ColorLut) {
+        self.lut = lut.create_texture(device, queue);
+    }
+
+    /// Сбрасывает пайплайны, если изменился модуль `post` или его импорты
+    pub fn reload_shaders(&mut self, composer: &ShaderComposer, changed: &[String]) {
+        if changed.iter().any(|name| name == POST_SHADER) {
+            self.composer = composer.clone();
+            self.shader = None;
+            self.pipelines.clear();
+        }
+    }
+
+    /// Записывает проходы цепочки в `encoder`; результат попадает в `output`
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::TextureView,
+        output_format: wgpu::TextureFormat,
+    ) -> Result<(), PostProcessError> {
+        let draws = self.chain.plan();
+        while self.uniform_buffers.len() < draws.len() {
+            self.uniform_buffers.push(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("post_process_uniform"),
+                size: std::mem::size_of::<PostUniform>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        for (index, draw) in draws.iter().enumerate() {
+            let format = match draw.target {
+                PostSlot::Output => output_format,
+                _ => HDR_FORMAT,
+            };
+            let pipeline = self.pipeline(device, draw.pass, format)?;
+            let source = self.slot(draw.source);
+            let target = match draw.target {
+                PostSlot::Output => output,
+                slot => &self.slot(slot).view,
+            };
+
+            let size = source.texture.size();
+            let uniform = PostUniform {
+                texel_size: [1.0 / size.width as f32, 1.0 / size.height as f32],
+                direction: draw.direction,
+                params: draw.params,
+            };
+            let uniform_buffer = &self.uniform_buffers[index];
+            queue.write_buffer(uniform_buffer, 0, bytemuck::bytes_of(&uniform));
+
+            let source_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("post_process_source_bind_group"),
+                layout: &self.source_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&source.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            let extra_bind_group = match draw.pass {
+                PostPass::BloomComposite => Some((&self.bloom_layout, 0, &self.bloom_targets[0].view)),
+                PostPass::ColorGrading => Some((&self.lut_layout, 1, &self.lut.view)),
+                _ => None,
+            }
+            .map(|(layout, binding, view)| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("post_process_extra_bind_group"),
+                    layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding,
+                        resource: wgpu::BindingResource::TextureView(view),
+                    }],
+                })
+            });
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(draw.pass.entry_point()),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&pipeline);
+            render_pass.set_bind_group(0, &source_bind_group, &[]);
+            if let Some(bind_group) = &extra_bind_group {
+                render_pass.set_bind_group(1, bind_group, &[]);
+            }
+            render_pass.draw(0..3, 0..1);
+        }
+        Ok(())
+    }
+
+    fn slot(&self, slot: PostSlot) -> &GpuTexture {
+        match slot {
+            PostSlot::Scene | PostSlot::Output => &self.scene_color,
+            PostSlot::PingPong(index) => &self.ping_pong[index],
+            PostSlot::Bloom(index) => &self.bloom_targets[index],
+        }
+    }
+
+    fn pipeline(
+        &mut self,
+        device: &wgpu::Device,
+        pass: PostPass,
+        format: wgpu::TextureFormat,
+    ) -> Result<wgpu::RenderPipeline, ShaderError> {
+        if let Some(pipeline) = self.pipelines.get(&(pass, format)) {
+            return Ok(pipeline.clone());
+        }
+        if self.shader.is_none() {
+            self.shader = Some(self.composer.create_shader_module(device, POST_SHADER, &[])?);
+        }
+        let shader = self.shader.as_ref().unwrap();
+
+        let mut layouts = vec![&self.source_layout];
+        match pass {
+            PostPass::BloomComposite => layouts.push(&self.bloom_layout),
+            PostPass::ColorGrading => layouts.push(&self.lut_layout),
+            _ => {}
+        }
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(pass.entry_point()),
+            bind_group_layouts: &layouts,
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = with_error_scope(device, POST_SHADER, || {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(pass.entry_point()),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: Some(pass.entry_point()),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        })?;
+        self.pipelines.insert((pass, format), pipeline.clone());
+        Ok(pipeline)
+    }
+
+    /// HDR-цель сцены, пара промежуточных целей и пара целей bloom
+    fn create_targets(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+    ) -> (GpuTexture, [GpuTexture; 2], [GpuTexture; 2]) {
+        let (bloom_width, bloom_height) = ((width / 2).max(1), (height / 2).max(1));
+        let target = |width, height, label| {
+            GpuTexture::create_render_target(device, width, height, HDR_FORMAT, 1, label)
+        };
+        (
+            target(width, height, "hdr_scene_color"),
+            [target(width, height, "post_ping"), target(width, height, "post_pong")],
+            [
+                target(bloom_width, bloom_height, "bloom_ping"),
+                target(bloom_width, bloom_height, "bloom_pong"),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_plan() {
+        let draws = PostProcessChain::default().plan();
+        let passes: Vec<PostPass> = draws.iter().map(|draw| draw.pass).collect();
+        assert_eq!(
+            passes,
+            vec![
+                PostPass::BloomPrefilter,
+                PostPass::BloomBlur,
+                PostPass::BloomBlur,
+                PostPass::BloomComposite,
+                PostPass::Tonemap,
+                PostPass::ColorGrading,
+                PostPass::Fxaa,
+                PostPass::Vignette,
+            ]
+        );
+
+        // Каждый эффект читает результат предыдущего
+        assert_eq!(draws[3].source, PostSlot::Scene);
+        assert_eq!(draws[3].target, PostSlot::PingPong(0));
+        assert_eq!(draws[4].source, PostSlot::PingPong(0));
+        assert_eq!(draws[4].target, PostSlot::PingPong(1));
+        assert_eq!(draws[4].params[0], 0.246);
+        assert_eq!(draws[7].target, PostSlot::Output);
+    }
+
+    #[test]
+    fn test_disabled_chain_blits_scene() {
+        let mut chain = PostProcessChain::default();
+        for kind in [
+            PostEffectKind::Bloom,
+            PostEffectKind::Tonemap,
+            PostEffectKind::ColorGrading,
+            PostEffectKind::Fxaa,
+            PostEffectKind::Vignette,
+        ] {
+            assert!(chain.set_enabled(kind, false));
+        }
+
+        let expected = vec![PostDraw::new(PostPass::Blit, PostSlot::Scene, PostSlot::Output)];
+        assert_eq!(chain.plan(), expected);
+        assert_eq!(PostProcessChain::empty().plan(), expected);
+    }
+
+    #[test]
+    fn test_reorder_and_toggle() {
+        let mut chain = PostProcessChain::default();
+        assert!(chain.move_to(PostEffectKind::Vignette, 0));
+        assert_eq!(chain.toggle(PostEffectKind::Bloom), Some(false));
+        assert_eq!(PostProcessChain::empty().toggle(PostEffectKind::Fxaa), None);
+
+        let kinds: Vec<PostEffectKind> = chain.enabled_effects().map(|e| e.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PostEffectKind::Vignette,
+                PostEffectKind::Tonemap,
+                PostEffectKind::ColorGrading,
+                PostEffectKind::Fxaa,
+            ]
+        );
+        assert_eq!(chain.plan()[0].pass, PostPass::Vignette);
+    }
+
+    #[test]
+    fn test_color_lut_strip() {
+        let identity = ColorLut::identity(4);
+        assert_eq!(identity.texel(0, 0, 0), [0, 0, 0, 255]);
+        assert_eq!(identity.texel(3, 1, 2), [255, 85, 170, 255]);
+
+        let strip = image::RgbaImage::from_fn(16, 4, |x, y| {
+            image::Rgba(identity.texel(x % 4, y, x / 4))
+        });
+        assert_eq!(ColorLut::from_strip(&strip).unwrap(), identity);
+
+        let invalid = image::RgbaImage::new(8, 4);
+        assert!(matches!(
+            ColorLut::from_strip(&invalid),
+            Err(PostProcessError::InvalidLut { width: 8, height: 4 })
+        ));
+    }
+
+    #[test]
+    fn test_post_shader_bindings() {
+        let module = ShaderComposer::with_default_modules()
+            .compose(POST_SHADER)
+            .unwrap()
+            .validate()
+            .unwrap();
+
+        for pass in [
+            PostPass::Blit,
+            PostPass::Tonemap,
+            PostPass::BloomPrefilter,
+            PostPass::BloomBlur,
+            PostPass::BloomComposite,
+            PostPass::ColorGrading,
+            PostPass::Fxaa,
+            PostPass::Vignette,
+        ] {
+            assert!(
+                module.entry_points.iter().any(|entry| entry.name == pass.entry_point()),
+                "missing entry point {}",
+                pass.entry_point()
+            );
+        }
+
+        for (_, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+            let entries = match binding.group {
+                0 => POST_SOURCE_LAYOUT_ENTRIES.to_vec(),
+                1 => [POST_BLOOM_LAYOUT_ENTRIES, POST_LUT_LAYOUT_ENTRIES].concat(),
+                group => panic!("unexpected group {}", group),
+            };
+            assert!(entries.iter().any(|entry| entry.binding == binding.binding));
+        }
+    }
+}
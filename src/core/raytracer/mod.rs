@@ -2,7 +2,7 @@ use glam::Mat4;
 use gltf::camera;
 use wgpu::util::DeviceExt;
 
-use crate::{core::raytracer::sky::SkyParams, math::{angle::Angle, sphere::Sphere, unit_quad_projection_matrix}, res::{material::{GpuMaterial, Material, RayCastMaterial}, texture::{gpu_buffers::{StorageBuffer, UniformTextureBuffer}, Texture, TextureDescriptor}, vertex::{SimpleVertex, VertexUniforms, VERTICES}}, scene::{camera::{Camera, GpuCamera}, transform::Transform}};
+use crate::{core::{raytracer::sky::SkyParams, shader::{with_error_scope, ShaderComposer, ShaderError}}, math::{angle::Angle, sphere::Sphere, unit_quad_projection_matrix}, res::{asset_manager::AssetManager, material::{GpuMaterial, Material, RayCastMaterial}, texture::{gpu_buffers::{StorageBuffer, UniformTextureBuffer}, Texture, TextureDescriptor}, vertex::{SimpleVertex, VertexUniforms, VERTICES}}, scene::{camera::{Camera, GpuCamera}, transform::Transform}};
 
 pub mod sky;
 
@@ -17,6 +17,8 @@ pub struct Raytracer {
     pub parameter_bind_group: wgpu::BindGroup,
     pub scene_bind_group: wgpu::BindGroup,
     pub pipeline: wgpu::RenderPipeline,
+    pipeline_layout: wgpu::PipelineLayout,
+    target_format: wgpu::TextureFormat,
     pub latest_render_params: RenderParams,
     pub render_progress: RenderProgress,
     pub frame_number: u32,
@@ -233,10 +235,7 @@ impl Raytracer {
             (scene_bind_group_layout, scene_bind_group)
         };
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            source: wgpu::ShaderSource::Wgsl(include_str!("../../../shaders/raycast/raytracer.wgsl").into()),
-            label: Some("raytracer.wgsl"),
-        });
+        let shader = ShaderComposer::with_default_modules().create_shader_module(device, "raytracer", &[])?;
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             bind_group_layouts: &[
@@ -248,19 +247,71 @@ impl Raytracer {
             push_constant_ranges: &[],
             label: Some("raytracer layout"),
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            layout: Some(&pipeline_layout),
+        let pipeline = Self::create_pipeline(device, &pipeline_layout, &shader, surface_config.format);
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            contents: bytemuck::cast_slice(VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+            label: Some("VertexInput buffer"),
+        });
+
+        let render_progress = RenderProgress::new();
+
+        let frame_number = 1_u32;
+
+        Ok(Self {
+            vertex_uniform_bind_group,
+            frame_data_buffer,
+            image_bind_group,
+            camera_buffer,
+            sampling_parameter_buffer,
+            hw_sky_state_buffer,
+            parameter_bind_group,
+            scene_bind_group,
+            vertex_buffer,
+            pipeline,
+            pipeline_layout,
+            target_format: surface_config.format,
+            latest_render_params: render_params.clone(),
+            render_progress,
+            frame_number,
+        })
+    }
+
+    /// Пересобирает пайплайн из `raytracer` модуля реестра.
+    /// При ошибке текущий пайплайн остаётся без изменений.
+    pub fn reload_shader(
+        &mut self,
+        device: &wgpu::Device,
+        composer: &ShaderComposer,
+    ) -> Result<(), ShaderError> {
+        let shader = composer.create_shader_module(device, "raytracer", &[])?;
+        self.pipeline = with_error_scope(device, "raytracer", || {
+            Self::create_pipeline(device, &self.pipeline_layout, &shader, self.target_format)
+        })?;
+        self.render_progress.reset();
+        Ok(())
+    }
+
+    fn create_pipeline(
+        device: &wgpu::Device,
+        pipeline_layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            layout: Some(pipeline_layout),
             vertex: wgpu::VertexState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("vsMain"),
                 buffers: &[SimpleVertex::desc()],
                 compilation_options: Default::default(),
             },
             fragment: Some(wgpu::FragmentState {
-                module: &shader,
+                module: shader,
                 entry_point: Some("fsMain"),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: surface_config.format,
+                    format,
                     blend: Some(wgpu::BlendState {
                         color: wgpu::BlendComponent::REPLACE,
                         alpha: wgpu::BlendComponent::REPLACE,
@@ -290,32 +341,6 @@ impl Raytracer {
             // indicates how many array layers the attachments will have.
             multiview: None,
             cache: None,
-        });
-
-        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            contents: bytemuck::cast_slice(VERTICES),
-            usage: wgpu::BufferUsages::VERTEX,
-            label: Some("VertexInput buffer"),
-        });
-
-        let render_progress = RenderProgress::new();
-
-        let frame_number = 1_u32;
-
-        Ok(Self {
-            vertex_uniform_bind_group,
-            frame_data_buffer,
-            image_bind_group,
-            camera_buffer,
-            sampling_parameter_buffer,
-            hw_sky_state_buffer,
-            parameter_bind_group,
-            scene_bind_group,
-            vertex_buffer,
-            pipeline,
-            latest_render_params: render_params.clone(),
-            render_progress,
-            frame_number,
         })
     }
 
@@ -428,6 +453,8 @@ pub enum RenderParamsValidationError {
     FocusDistanceOutOfRange(f32),
     #[error(transparent)]
     HwSkyModelValidationError(#[from] hw_skymodel::rgb::Error),
+    #[error(transparent)]
+    Shader(#[from] ShaderError),
 }
 
 pub struct Scene {
@@ -435,6 +462,30 @@ pub struct Scene {
     pub materials: Vec<RayCastMaterial>,
 }
 
+impl Scene {
+    /// Добавляет меши сцены glTF описанными сферами: трассировщик умеет только
+    /// сферы, материал берётся из [`Material::ray_cast_material`]
+    pub fn add_gltf_scene(&mut self, scene: &crate::res::scene::Scene, assets: &AssetManager, transform: Mat4) {
+        for (node, world) in scene.nodes.iter().zip(scene.world_transforms()) {
+            let Some(model) = node.model.clone().and_then(|model| assets.models.get(model)) else {
+                continue;
+            };
+            for mesh in model.meshes.iter().filter_map(|mesh| assets.meshes.get(mesh.clone())) {
+                if mesh.bounds.is_empty() {
+                    continue;
+                }
+                let sphere = mesh.bounds.bounding_sphere().transformed(&(transform * world));
+                let material = match mesh.material.clone().and_then(|material| assets.materials.get(material)) {
+                    Some(material) => material.ray_cast_material(),
+                    None => Material::default().ray_cast_material(),
+                };
+                self.materials.push(material);
+                self.spheres.push(Sphere::new(sphere.center, sphere.radius, self.materials.len() as u32 - 1));
+            }
+        }
+    }
+}
+
 
 #[derive(Clone, Copy, PartialEq)]
 pub struct SamplingParams {
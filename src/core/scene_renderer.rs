@@ -0,0 +1,352 @@
+//! Отрисовка сцены glTF освещённым пайплайном.
+//!
+//! Раз в кадр [`SceneRenderer::prepare`] записывает мировые матрицы узлов,
+//! палитры суставов скинов ([`Skin::joint_matrices`] → [`JointPalette::update`])
+//! и смешанные морф-цели, после чего собирает список вызовов отрисовки. Меш
+//! общий для узлов, поэтому у узла с весами свой вершинный буфер
+//! ([`Mesh::create_morph_buffer`]), и смешивается он заново только при смене
+//! весов. Пайплайн выбирается по [`Mesh::skinned`]: [`PipelineType::LitSkinned`]
+//! для мешей со скелетом на узле со скином, иначе [`PipelineType::Lit`];
+//! смешивание и отсечение граней берутся из материала
+//! ([`PipelineKey::with_material`]). Прозрачные и пропускающие свет меши
+//! рисуются после непрозрачных, от дальних к ближним. Меши, чьи
+//! [`Mesh::bounds`] целиком вне пирамиды видимости камеры, пропускаются;
+//! скинированные и морфируемые меши выходят за эти границы и не отсекаются.
+//! Уровень детализации каждого меша выбирает [`SceneRenderer::lod`] по размеру
+//! на экране. Отрезки и точки рисуются пайплайнами [`PipelineType::Lines`] и
+//! [`PipelineType::Points`] цветом материала, без скиннинга и упрощённых уровней.
+//!
+//! [`Skin::joint_matrices`]: crate::res::scin::Skin::joint_matrices
+
+use std::collections::HashMap;
+
+use glam::Mat4;
+use wgpu::util::DeviceExt;
+
+use crate::math::frustum::Frustum;
+use crate::res::{
+    asset_manager::AssetManager,
+    material::{AlphaMode, Material, MATERIAL_BIND_GROUP_LAYOUT_ENTRIES},
+    mesh::Mesh,
+    scene::Scene,
+    scin::{JointPalette, SKINNED_TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES},
+    texture::gpu_texture::GpuTexture,
+    Handle, MaterialKey, MeshKey,
+};
+use crate::scene::{
+    entity::{SceneEntity, SceneEntityKind},
+    transform::TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES,
+};
+
+use super::{
+    lod::LodPolicy,
+    pipeline_cache::{PipelineCache, PipelineKey},
+    shader::ShaderError,
+    PipelineType,
+};
+
+/// Матрица узла и, если у узла есть скин, палитра его суставов
+struct NodeResources {
+    transform: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    skinned: Option<(JointPalette, wgpu::BindGroup)>,
+    /// Вершины морфируемых мешей узла
+    morphs: HashMap<MeshKey, MorphVertices>,
+}
+
+/// Вершинный буфер узла со смешанными морф-целями меша
+struct MorphVertices {
+    buffer: wgpu::Buffer,
+    /// Веса, по которым смешан буфер
+    weights: Vec<f32>,
+    /// Версия меша ([`Storage::version`](crate::res::storage::Storage::version)),
+    /// из которой собран буфер
+    version: u64,
+}
+
+/// Bind group материала; его uniform-буфер живёт, пока жива bind group
+struct MaterialResources {
+    bind_group: wgpu::BindGroup,
+    /// Версии материала и его текстур, из которых всё собрано
+    versions: [u64; 6],
+}
+
+impl NodeResources {
+    /// Смешивает морф-цели меша версии `version` по весам узла, если те
+    /// изменились; перезагруженный меш получает новый буфер. `false`, если
+    /// у меша нет морф-целей.
+    fn prepare_morph(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        (key, version): (MeshKey, u64),
+        mesh: &Mesh,
+        weights: &[f32],
+    ) -> bool {
+        let Some(morph) = &mesh.morph_targets else {
+            return false;
+        };
+        match self.morphs.get_mut(&key) {
+            Some(vertices) if vertices.version == version => {
+                if vertices.weights != weights {
+                    queue.write_buffer(&vertices.buffer, 0, &morph.blend_vertices(weights));
+                    vertices.weights = weights.to_vec();
+                }
+            }
+            _ => {
+                if let Some(buffer) = mesh.create_morph_buffer(device, weights) {
+                    self.morphs.insert(key, MorphVertices { buffer, weights: weights.to_vec(), version });
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Вызов отрисовки одного меша узла
+struct DrawItem {
+    node: usize,
+    mesh: Handle<Mesh>,
+    material: MaterialKey,
+    pipeline: wgpu::RenderPipeline,
+    skinned: bool,
+    /// Рисовать из вершинного буфера узла со смешанными морф-целями
+    morphed: bool,
+    /// Уровень детализации для [`Mesh::lod`]
+    lod: usize,
+    /// Пайплайн со смешиванием; такие вызовы идут после непрозрачных
+    blended: bool,
+    /// Квадрат расстояния от камеры до центра границ меша
+    distance: f32,
+}
+
+/// GPU-ресурсы узлов и материалов сцены glTF
+pub struct SceneRenderer {
+    /// Формат цели и число выборок; тип пайплайна и раскладка вершин
+    /// берутся из меша
+    pub target: PipelineKey,
+    /// Пороги уровней детализации мешей
+    pub lod: LodPolicy,
+    transform_layout: wgpu::BindGroupLayout,
+    skinned_layout: wgpu::BindGroupLayout,
+    material_layout: wgpu::BindGroupLayout,
+    nodes: Vec<NodeResources>,
+    materials: HashMap<MaterialKey, MaterialResources>,
+    items: Vec<DrawItem>,
+}
+
+impl SceneRenderer {
+    pub fn new(device: &wgpu::Device, cache: &mut PipelineCache, target: PipelineKey) -> Self {
+        Self {
+            target,
+            lod: LodPolicy::default(),
+            transform_layout: cache.bind_group_layout(device, TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES),
+            skinned_layout: cache.bind_group_layout(device, SKINNED_TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES),
+            material_layout: cache.bind_group_layout(device, MATERIAL_BIND_GROUP_LAYOUT_ENTRIES),
+            nodes: Vec::new(),
+            materials: HashMap::new(),
+            items: Vec::new(),
+        }
+    }
+
+    /// Обновляет матрицы и палитры суставов по текущей позе `scene` и
+    /// собирает вызовы отрисовки мешей, видимых камерой `camera_entity`;
+    /// если это не камера, рисуется всё.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        cache: &mut PipelineCache,
+        assets: &AssetManager,
+        scene: &Scene,
+        camera_entity: &SceneEntity,
+    ) -> Result<(), ShaderError> {
+        let world = scene.world_transforms();
+        let camera = match &camera_entity.kind {
+            SceneEntityKind::Camera { camera, .. } => Some(camera),
+            _ => None,
+        };
+        let frustum = camera
+            .map(|camera| Frustum::from_view_projection(&camera_entity.transform.calculate_view_projection(camera)));
+        self.items.clear();
+
+        for (index, node) in scene.nodes.iter().enumerate() {
+            let skin = node.skin.clone().and_then(|skin| assets.skins.get(skin));
+            if index == self.nodes.len() {
+                self.nodes.push(self.create_node(device));
+            }
+            let resources = &mut self.nodes[index];
+            queue.write_buffer(&resources.transform, 0, bytemuck::cast_slice(&world[index].to_cols_array()));
+            if let Some(skin) = skin {
+                let (palette, _) = resources.skinned.get_or_insert_with(|| {
+                    let palette = JointPalette::new(device);
+                    let bind_group = palette.create_bind_group(device, &self.skinned_layout, &resources.transform);
+                    (palette, bind_group)
+                });
+                palette.update(queue, &skin.joint_matrices(scene, &world, index));
+            }
+
+            let Some(model) = node.model.clone().and_then(|model| assets.models.get(model)) else {
+                continue;
+            };
+            for handle in &model.meshes {
+                let Some(mesh) = assets.meshes.get(handle.clone()) else {
+                    continue;
+                };
+                // Скинированные и морфируемые меши выходят за исходные границы
+                let deformed = mesh.skinned || mesh.morph_targets.is_some();
+                let bounds = mesh.bounds.transformed(&world[index]);
+                if !deformed && frustum.is_some_and(|frustum| !frustum.intersects_aabb(&bounds)) {
+                    continue;
+                }
+                let Some(material_handle) = mesh.material.clone() else {
+                    continue;
+                };
+                let Some(material) = self.prepare_material(device, assets, &material_handle) else {
+                    continue;
+                };
+                let morphed = !node.weights.is_empty()
+                    && self.nodes[index].prepare_morph(
+                        device,
+                        queue,
+                        (handle.key(), assets.meshes.version(handle.clone())),
+                        mesh,
+                        &node.weights,
+                    );
+
+                let triangles = mesh.topology == wgpu::PrimitiveTopology::TriangleList;
+                let skinned = triangles && mesh.skinned && self.nodes[index].skinned.is_some();
+                let kind = if skinned {
+                    PipelineType::LitSkinned
+                } else {
+                    PipelineType::Lit.for_topology(mesh.topology)
+                };
+                let key = PipelineKey { kind, ..self.target }
+                    .with_vertex_layout(mesh.layout)
+                    .with_material(material);
+                let distance = bounds.center().distance_squared(camera_entity.transform.position);
+                let lod = match camera {
+                    Some(camera) if triangles => {
+                        self.lod.select_for_camera(mesh, &world[index], camera, &camera_entity.transform)
+                    }
+                    _ => 0,
+                };
+                self.items.push(DrawItem {
+                    node: index,
+                    mesh: handle.clone(),
+                    material: material_handle.key(),
+                    pipeline: cache.pipeline(device, key)?,
+                    skinned,
+                    morphed,
+                    lod,
+                    blended: key.alpha_mode == AlphaMode::Blend,
+                    distance,
+                });
+            }
+        }
+        // Устойчивая сортировка сохраняет порядок непрозрачных
+        self.items.sort_by(|a, b| match (a.blended, b.blended) {
+            (true, true) => b.distance.total_cmp(&a.distance),
+            (a_blended, b_blended) => a_blended.cmp(&b_blended),
+        });
+        Ok(())
+    }
+
+    /// Рисует собранные в [`Self::prepare`] меши; группа 0 — камера,
+    /// группа 1 — точечные источники и окружение
+    /// ([`Environment::create_bind_group`](crate::res::texture::ibl::Environment::create_bind_group))
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        assets: &AssetManager,
+        camera_bind_group: &wgpu::BindGroup,
+        lighting_bind_group: &wgpu::BindGroup,
+    ) {
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, lighting_bind_group, &[]);
+        for item in &self.items {
+            let Some(mesh) = assets.meshes.get(item.mesh.clone()) else {
+                continue;
+            };
+            let node = &self.nodes[item.node];
+            let transform = match (&node.skinned, item.skinned) {
+                (Some((_, bind_group)), true) => bind_group,
+                _ => &node.bind_group,
+            };
+            render_pass.set_pipeline(&item.pipeline);
+            render_pass.set_bind_group(2, transform, &[]);
+            render_pass.set_bind_group(3, &self.materials[&item.material].bind_group, &[]);
+            let vertex_buffer = match node.morphs.get(&item.mesh.key()) {
+                Some(vertices) if item.morphed => &vertices.buffer,
+                _ => &mesh.vertex_buffer,
+            };
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            let (index_buffer, index_count) = mesh.lod(item.lod);
+            render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+            render_pass.draw_indexed(0..index_count, 0, 0..1);
+        }
+    }
+
+    /// Забывает ресурсы узлов и материалов, например после смены сцены
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.materials.clear();
+        self.items.clear();
+    }
+
+    fn create_node(&self, device: &wgpu::Device) -> NodeResources {
+        let transform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("node_transform_buffer"),
+            contents: bytemuck::cast_slice(&Mat4::IDENTITY.to_cols_array()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("node_transform_bind_group"),
+            layout: &self.transform_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: transform.as_entire_binding(),
+            }],
+        });
+        NodeResources {
+            transform,
+            bind_group,
+            skinned: None,
+            morphs: HashMap::new(),
+        }
+    }
+
+    /// Создаёт uniform и bind group материала при первом использовании и
+    /// после перезагрузки материала или его текстур. `None`, если материала
+    /// или его текстуры уже нет в хранилище.
+    fn prepare_material<'a>(
+        &mut self,
+        device: &wgpu::Device,
+        assets: &'a AssetManager,
+        handle: &Handle<Material>,
+    ) -> Option<&'a Material> {
+        let material = assets.materials.get(handle.clone())?;
+        let texture = |texture: &Option<Handle<GpuTexture>>| {
+            texture.clone().map_or(0, |texture| assets.textures.version(texture))
+        };
+        let versions = [
+            assets.materials.version(handle.clone()),
+            texture(&material.base_color_texture),
+            texture(&material.metallic_roughness_texture),
+            texture(&material.normal_texture),
+            texture(&material.occlusion_texture),
+            texture(&material.emissive_texture),
+        ];
+        if self.materials.get(&handle.key()).is_some_and(|cached| cached.versions == versions) {
+            return Some(material);
+        }
+        let uniform = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("material_uniform_buffer"),
+            contents: bytemuck::bytes_of(&material.uniform()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = material.create_pbr_bind_group(device, &self.material_layout, &assets.textures, &uniform)?;
+        self.materials.insert(handle.key(), MaterialResources { bind_group, versions });
+        Some(material)
+    }
+}
@@ -0,0 +1,196 @@
+//! Горячая перезагрузка шейдеров в режиме разработки.
+//!
+//! [`ShaderHotReload`] читает WGSL с диска, следит за каталогом `shaders/`
+//! и сообщает, какие модули затронуты изменениями. [`ReloadablePipeline`]
+//! пересоздаёт пайплайн из нового исходника; при ошибке компиляции
+//! остаётся предыдущая версия, а ошибка пишется в лог.
+
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::{with_error_scope, ShaderComposer, ShaderError};
+
+/// Наблюдатель за каталогом шейдеров
+pub struct ShaderHotReload {
+    /// Реестр модулей, загруженных с диска
+    pub composer: ShaderComposer,
+    root: PathBuf,
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl ShaderHotReload {
+    /// Загружает шейдеры из `root` и начинает следить за изменениями
+    ///
+    /// # Пример
+    /// ```no_run
+    /// # use diploma_thesis::core::shader::{hot_reload::ShaderHotReload, ShaderError};
+    /// # fn main() -> Result<(), ShaderError> {
+    /// let mut hot_reload = ShaderHotReload::new("shaders")?;
+    /// let changed = hot_reload.poll();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(root: impl AsRef<Path>) -> Result<Self, ShaderError> {
+        let root = root.as_ref().canonicalize().map_err(|source| ShaderError::Io {
+            path: root.as_ref().to_path_buf(),
+            source,
+        })?;
+        let composer = ShaderComposer::from_directory(&root)?;
+
+        let (sender, events) = channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            composer,
+            root,
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Обрабатывает накопившиеся события файловой системы без блокировки.
+    /// Возвращает имена модулей, которые нужно пересобрать.
+    pub fn poll(&mut self) -> Vec<String> {
+        let mut changed = BTreeSet::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    changed.extend(event.paths);
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("shader watcher error: {}", err),
+            }
+        }
+        let changed: Vec<PathBuf> = changed.into_iter().collect();
+        self.apply_changes(&changed)
+    }
+
+    /// Перечитывает изменённые файлы и возвращает затронутые модули
+    pub fn apply_changes(&mut self, paths: &[PathBuf]) -> Vec<String> {
+        let mut affected = BTreeSet::new();
+        for path in paths {
+            if path.extension().and_then(|e| e.to_str()) != Some("wgsl") {
+                continue;
+            }
+            match self.composer.reload_file(&self.root, path) {
+                Ok(Some(module)) => {
+                    log::info!("shader module '{}' changed", module);
+                    affected.extend(self.composer.dependents(&module));
+                }
+                Ok(None) => {}
+                // Причина ошибки чтения не входит в её сообщение
+                Err(err) => match std::error::Error::source(&err) {
+                    Some(source) => log::error!("{}: {}", err, source),
+                    None => log::error!("{}", err),
+                },
+            }
+        }
+        affected.into_iter().collect()
+    }
+}
+
+/// Функция, строящая пайплайн из готового модуля шейдера
+pub type PipelineBuilder = Box<dyn Fn(&wgpu::Device, &wgpu::ShaderModule) -> wgpu::RenderPipeline>;
+
+/// Render pipeline, который можно пересобрать после изменения шейдера
+pub struct ReloadablePipeline {
+    /// Имя модуля шейдера в [`ShaderComposer`]
+    pub shader: String,
+    defines: Vec<String>,
+    build: PipelineBuilder,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ReloadablePipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        composer: &ShaderComposer,
+        shader: &str,
+        defines: &[&str],
+        build: PipelineBuilder,
+    ) -> Result<Self, ShaderError> {
+        let pipeline = Self::create(device, composer, shader, defines, &build)?;
+        Ok(Self {
+            shader: shader.to_string(),
+            defines: defines.iter().map(|d| d.to_string()).collect(),
+            build,
+            pipeline,
+        })
+    }
+
+    pub fn pipeline(&self) -> &wgpu::RenderPipeline {
+        &self.pipeline
+    }
+
+    /// Пересобирает пайплайн. При ошибке текущий пайплайн не меняется.
+    pub fn reload(&mut self, device: &wgpu::Device, composer: &ShaderComposer) -> Result<(), ShaderError> {
+        let defines: Vec<&str> = self.defines.iter().map(String::as_str).collect();
+        self.pipeline = Self::create(device, composer, &self.shader, &defines, &self.build)?;
+        Ok(())
+    }
+
+    /// Пересобирает пайплайн, если его шейдер среди `changed`.
+    /// Возвращает `true`, если пайплайн был заменён.
+    pub fn reload_if_changed(
+        &mut self,
+        device: &wgpu::Device,
+        composer: &ShaderComposer,
+        changed: &[String],
+    ) -> bool {
+        if !changed.contains(&self.shader) {
+            return false;
+        }
+        match self.reload(device, composer) {
+            Ok(()) => true,
+            Err(err) => {
+                log::error!("keeping previous '{}' pipeline: {}", self.shader, err);
+                false
+            }
+        }
+    }
+
+    fn create(
+        device: &wgpu::Device,
+        composer: &ShaderComposer,
+        shader: &str,
+        defines: &[&str],
+        build: &PipelineBuilder,
+    ) -> Result<wgpu::RenderPipeline, ShaderError> {
+        let module = composer.create_shader_module(device, shader, defines)?;
+        with_error_scope(device, shader, || build(device, &module))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_changes_reports_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let shaders = Path::new(env!("CARGO_MANIFEST_DIR")).join("shaders");
+        for (_, file_name, _) in super::super::DEFAULT_MODULES {
+            let path = dir.path().join(file_name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::copy(shaders.join(file_name), &path).unwrap();
+        }
+
+        let mut hot_reload = ShaderHotReload::new(dir.path()).unwrap();
+        let camera = hot_reload.root().join("camera.wgsl");
+        let readme = hot_reload.root().join("notes.txt");
+
+        let affected = hot_reload.apply_changes(&[camera, readme]);
+        assert_eq!(affected, vec!["camera", "light_gizmo", "main", "skybox"]);
+    }
+}
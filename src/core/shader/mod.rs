@@ -0,0 +1,702 @@
+//! Система композиции WGSL-шейдеров.
+//!
+//! Поддерживает директивы препроцессора поверх обычного WGSL:
+//! - `#import module::Item` / `#import module::{A, B}` — подключение именованного модуля
+//! - `#ifdef NAME` / `#ifndef NAME` / `#else` / `#endif` — условная компиляция
+//!
+//! Каждый модуль подключается в итоговый исходник один раз, циклические
+//! импорты обнаруживаются. Для каждой строки результата сохраняется её
+//! происхождение, поэтому ошибки naga сообщаются в координатах исходного файла.
+
+pub mod hot_reload;
+
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+/// Строка таблицы [`DEFAULT_MODULES`]: имя, путь в `shaders/` и встроенный исходник
+macro_rules! default_module {
+    ($name:literal, $file:literal) => {
+        ($name, $file, include_str!(concat!("../../../shaders/", $file)))
+    };
+}
+
+/// Встроенные модули: имя для `#import`, путь относительно каталога `shaders/`
+/// и исходник, встроенный при сборке
+pub const DEFAULT_MODULES: &[(&str, &str, &str)] = &[
+    default_module!("camera", "camera.wgsl"),
+    default_module!("lighting", "light.wgsl"),
+    default_module!("main", "main.wgsl"),
+    default_module!("skybox", "skybox.wgsl"),
+    default_module!("floor", "floor.wgsl"),
+    default_module!("light_gizmo", "light_gizmo.wgsl"),
+    default_module!("tonemap", "tonemap.wgsl"),
+    default_module!("ibl", "ibl.wgsl"),
+    default_module!("material", "material.wgsl"),
+    default_module!("fullscreen", "post/fullscreen.wgsl"),
+    default_module!("post", "post/post.wgsl"),
+    default_module!("raytracer", "raycast/raytracer.wgsl"),
+];
+
+/// Исходный код именованного WGSL-модуля
+#[derive(Debug, Clone)]
+pub struct ShaderModuleSource {
+    /// Имя модуля, используемое в `#import`
+    pub name: String,
+    /// Имя файла для сообщений об ошибках
+    pub file_name: String,
+    /// WGSL-код с директивами препроцессора
+    pub source: String,
+}
+
+/// Происхождение строки собранного шейдера
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineOrigin {
+    /// Имя файла модуля
+    pub file_name: String,
+    /// Номер строки в модуле (с единицы)
+    pub line: u32,
+}
+
+/// Результат композиции: чистый WGSL и карта строк
+#[derive(Debug, Clone)]
+pub struct ComposedShader {
+    pub name: String,
+    pub source: String,
+    line_origins: Vec<LineOrigin>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ShaderError {
+    #[error("shader module '{0}' is not registered")]
+    UnknownModule(String),
+    #[error("{file_name}:{line}: imported module '{module}' is not registered")]
+    UnresolvedImport {
+        file_name: String,
+        line: u32,
+        module: String,
+    },
+    #[error("{file_name}:{line}: module '{module}' does not declare '{item}'")]
+    UnknownImportItem {
+        file_name: String,
+        line: u32,
+        module: String,
+        item: String,
+    },
+    #[error("import cycle detected: {}", .0.join(" -> "))]
+    ImportCycle(Vec<String>),
+    #[error("{file_name}:{line}: {message}")]
+    Directive {
+        file_name: String,
+        line: u32,
+        message: String,
+    },
+    #[error("{file_name}:{line}:{column}: {message}")]
+    Parse {
+        file_name: String,
+        line: u32,
+        column: u32,
+        message: String,
+    },
+    #[error("{file_name}:{line}: {message}")]
+    Validation {
+        file_name: String,
+        line: u32,
+        message: String,
+    },
+    #[error("shader '{name}' rejected by device: {message}")]
+    Device { name: String, message: String },
+    #[error("failed to read shader {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Watch(#[from] notify::Error),
+}
+
+/// Реестр WGSL-модулей и набор активных define'ов
+#[derive(Debug, Clone, Default)]
+pub struct ShaderComposer {
+    modules: HashMap<String, ShaderModuleSource>,
+    defines: HashSet<String>,
+}
+
+/// Состояние одной сборки шейдера
+struct ComposeState<'a> {
+    defines: HashSet<&'a str>,
+    included: HashSet<String>,
+    stack: Vec<String>,
+    source: String,
+    line_origins: Vec<LineOrigin>,
+}
+
+/// Открытый блок `#ifdef`/`#ifndef`
+struct Condition {
+    condition: bool,
+    parent_active: bool,
+    else_seen: bool,
+    line: u32,
+}
+
+impl Condition {
+    fn active(&self) -> bool {
+        self.parent_active && (self.condition != self.else_seen)
+    }
+}
+
+impl ShaderComposer {
+    pub fn new() -> Self {
+        Self {
+            modules: HashMap::new(),
+            defines: HashSet::new(),
+        }
+    }
+
+    /// Создаёт реестр со всеми шейдерами из каталога `shaders/`
+    pub fn with_default_modules() -> Self {
+        let mut composer = Self::new();
+        for (name, file_name, source) in DEFAULT_MODULES {
+            composer.add_module(*name, *file_name, *source);
+        }
+        composer
+    }
+
+    /// Загружает встроенные модули с диска (режим разработки)
+    ///
+    /// # Аргументы
+    /// * `root` - Каталог с шейдерами, аналог `shaders/`
+    pub fn from_directory(root: impl AsRef<Path>) -> Result<Self, ShaderError> {
+        let root = root.as_ref();
+        let mut composer = Self::new();
+        for (name, file_name, _) in DEFAULT_MODULES {
+            let path = root.join(file_name);
+            let source = std::fs::read_to_string(&path)
+                .map_err(|source| ShaderError::Io { path, source })?;
+            composer.add_module(*name, *file_name, source);
+        }
+        Ok(composer)
+    }
+
+    /// Перечитывает модуль, которому соответствует файл `path` внутри `root`.
+    /// Возвращает имя обновлённого модуля или `None`, если файл не зарегистрирован.
+    pub fn reload_file(&mut self, root: &Path, path: &Path) -> Result<Option<String>, ShaderError> {
+        let Ok(relative) = path.strip_prefix(root) else {
+            return Ok(None);
+        };
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let Some(module) = self.modules.values_mut().find(|m| m.file_name == relative) else {
+            return Ok(None);
+        };
+        module.source = std::fs::read_to_string(path).map_err(|source| ShaderError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Some(module.name.clone()))
+    }
+
+    /// Возвращает все модули, которые прямо или транзитивно импортируют `name`,
+    /// включая сам модуль
+    pub fn dependents(&self, name: &str) -> Vec<String> {
+        let mut dependents: Vec<String> = self
+            .modules
+            .keys()
+            .filter(|candidate| self.depends_on(candidate, name, &mut HashSet::new()))
+            .cloned()
+            .collect();
+        dependents.sort();
+        dependents
+    }
+
+    fn depends_on<'a>(&'a self, module: &'a str, target: &str, visited: &mut HashSet<&'a str>) -> bool {
+        if module == target {
+            return true;
+        }
+        if !visited.insert(module) {
+            return false;
+        }
+        let Some(source) = self.modules.get(module) else {
+            return false;
+        };
+        source
+            .source
+            .lines()
+            .filter_map(|line| line.trim_start().strip_prefix("#import"))
+            .filter_map(parse_import)
+            .any(|(import, _)| self.depends_on(import, target, visited))
+    }
+
+    /// Регистрирует (или заменяет) модуль под указанным именем
+    pub fn add_module(
+        &mut self,
+        name: impl Into<String>,
+        file_name: impl Into<String>,
+        source: impl Into<String>,
+    ) {
+        let name = name.into();
+        self.modules.insert(
+            name.clone(),
+            ShaderModuleSource {
+                name,
+                file_name: file_name.into(),
+                source: source.into(),
+            },
+        );
+    }
+
+    pub fn module(&self, name: &str) -> Option<&ShaderModuleSource> {
+        self.modules.get(name)
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = &ShaderModuleSource> {
+        self.modules.values()
+    }
+
+    /// Включает define для всех последующих сборок
+    pub fn define(&mut self, name: impl Into<String>) {
+        self.defines.insert(name.into());
+    }
+
+    pub fn undefine(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    pub fn compose(&self, name: &str) -> Result<ComposedShader, ShaderError> {
+        self.compose_with_defines(name, &[])
+    }
+
+    /// Собирает модуль с дополнительными define'ами поверх глобальных
+    pub fn compose_with_defines(
+        &self,
+        name: &str,
+        defines: &[&str],
+    ) -> Result<ComposedShader, ShaderError> {
+        let mut state = ComposeState {
+            defines: self
+                .defines
+                .iter()
+                .map(String::as_str)
+                .chain(defines.iter().copied())
+                .collect(),
+            included: HashSet::new(),
+            stack: Vec::new(),
+            source: String::new(),
+            line_origins: Vec::new(),
+        };
+        self.expand(name, &mut state)?;
+
+        Ok(ComposedShader {
+            name: name.to_string(),
+            source: state.source,
+            line_origins: state.line_origins,
+        })
+    }
+
+    /// Собирает, проверяет через naga и создаёт модуль шейдера на GPU.
+    /// Ошибки устройства перехватываются и возвращаются, а не приводят к панике.
+    pub fn create_shader_module(
+        &self,
+        device: &wgpu::Device,
+        name: &str,
+        defines: &[&str],
+    ) -> Result<wgpu::ShaderModule, ShaderError> {
+        let composed = self.compose_with_defines(name, defines)?;
+        composed.validate()?;
+        with_error_scope(device, name, || device.create_shader_module(composed.descriptor()))
+    }
+
+    fn expand(&self, name: &str, state: &mut ComposeState) -> Result<(), ShaderError> {
+        if let Some(position) = state.stack.iter().position(|n| n == name) {
+            let mut chain = state.stack[position..].to_vec();
+            chain.push(name.to_string());
+            return Err(ShaderError::ImportCycle(chain));
+        }
+        if state.included.contains(name) {
+            return Ok(());
+        }
+        let module = self
+            .modules
+            .get(name)
+            .ok_or_else(|| ShaderError::UnknownModule(name.to_string()))?;
+
+        state.stack.push(name.to_string());
+        let mut conditions: Vec<Condition> = Vec::new();
+
+        for (index, line) in module.source.lines().enumerate() {
+            let line_number = index as u32 + 1;
+            let active = conditions.last().is_none_or(Condition::active);
+            let directive_error = |message: String| ShaderError::Directive {
+                file_name: module.file_name.clone(),
+                line: line_number,
+                message,
+            };
+
+            let Some(directive) = line.trim_start().strip_prefix('#') else {
+                if active {
+                    state.source.push_str(line);
+                    state.source.push('\n');
+                    state.line_origins.push(LineOrigin {
+                        file_name: module.file_name.clone(),
+                        line: line_number,
+                    });
+                }
+                continue;
+            };
+
+            let (keyword, argument) = match directive.split_once(char::is_whitespace) {
+                Some((keyword, argument)) => (keyword, argument.trim()),
+                None => (directive.trim_end(), ""),
+            };
+
+            match keyword {
+                "ifdef" | "ifndef" => {
+                    if argument.is_empty() {
+                        return Err(directive_error(format!("#{} requires a name", keyword)));
+                    }
+                    let defined = state.defines.contains(argument);
+                    conditions.push(Condition {
+                        condition: if keyword == "ifdef" { defined } else { !defined },
+                        parent_active: active,
+                        else_seen: false,
+                        line: line_number,
+                    });
+                }
+                "else" => match conditions.last_mut() {
+                    Some(condition) if !condition.else_seen => condition.else_seen = true,
+                    Some(_) => return Err(directive_error("duplicate #else".into())),
+                    None => return Err(directive_error("#else without #ifdef".into())),
+                },
+                "endif" => {
+                    if conditions.pop().is_none() {
+                        return Err(directive_error("#endif without #ifdef".into()));
+                    }
+                }
+                "import" => {
+                    if !active {
+                        continue;
+                    }
+                    let (import_name, items) = parse_import(argument)
+                        .ok_or_else(|| directive_error(format!("malformed import '{}'", argument)))?;
+                    let imported = self.modules.get(import_name).ok_or_else(|| {
+                        ShaderError::UnresolvedImport {
+                            file_name: module.file_name.clone(),
+                            line: line_number,
+                            module: import_name.to_string(),
+                        }
+                    })?;
+                    if let Some(item) = items.iter().find(|item| !declares(&imported.source, item)) {
+                        return Err(ShaderError::UnknownImportItem {
+                            file_name: module.file_name.clone(),
+                            line: line_number,
+                            module: import_name.to_string(),
+                            item: item.to_string(),
+                        });
+                    }
+                    self.expand(import_name, state)?;
+                }
+                other => return Err(directive_error(format!("unknown directive #{}", other))),
+            }
+        }
+
+        if let Some(condition) = conditions.last() {
+            return Err(ShaderError::Directive {
+                file_name: module.file_name.clone(),
+                line: condition.line,
+                message: "unterminated #ifdef".into(),
+            });
+        }
+
+        state.stack.pop();
+        state.included.insert(name.to_string());
+        Ok(())
+    }
+}
+
+impl ComposedShader {
+    /// Возвращает происхождение строки собранного исходника (нумерация с единицы)
+    pub fn origin(&self, line: u32) -> Option<&LineOrigin> {
+        self.line_origins.get(line.checked_sub(1)? as usize)
+    }
+
+    /// Разбирает и валидирует собранный исходник средствами naga
+    pub fn validate(&self) -> Result<naga::Module, ShaderError> {
+        let module = naga::front::wgsl::parse_str(&self.source).map_err(|err| {
+            let location = err.location(&self.source);
+            let (file_name, line) = self.map_location(location.map(|l| l.line_number));
+            ShaderError::Parse {
+                file_name,
+                line,
+                column: location.map_or(0, |l| l.line_position),
+                message: err.message().to_string(),
+            }
+        })?;
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::default(),
+        )
+        .validate(&module)
+        .map_err(|err| {
+            let location = err.location(&self.source);
+            let (file_name, line) = self.map_location(location.map(|l| l.line_number));
+            let mut message = err.as_inner().to_string();
+            let mut source = err.as_inner().source();
+            while let Some(inner) = source {
+                message.push_str(": ");
+                message.push_str(&inner.to_string());
+                source = inner.source();
+            }
+            ShaderError::Validation {
+                file_name,
+                line,
+                message,
+            }
+        })?;
+
+        Ok(module)
+    }
+
+    pub fn descriptor(&self) -> wgpu::ShaderModuleDescriptor<'_> {
+        wgpu::ShaderModuleDescriptor {
+            label: Some(&self.name),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(&self.source)),
+        }
+    }
+
+    fn map_location(&self, line: Option<u32>) -> (String, u32) {
+        match line.and_then(|line| self.origin(line)) {
+            Some(origin) => (origin.file_name.clone(), origin.line),
+            None => (self.name.clone(), line.unwrap_or(0)),
+        }
+    }
+}
+
+/// Выполняет `create` внутри validation error scope устройства
+pub fn with_error_scope<T>(
+    device: &wgpu::Device,
+    name: &str,
+    create: impl FnOnce() -> T,
+) -> Result<T, ShaderError> {
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let value = create();
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(err) => Err(ShaderError::Device {
+            name: name.to_string(),
+            message: err.to_string(),
+        }),
+        None => Ok(value),
+    }
+}
+
+/// Разбирает аргумент `#import`: `module`, `module::Item` или `module::{A, B}`
+fn parse_import(argument: &str) -> Option<(&str, Vec<&str>)> {
+    let (module, items) = match argument.split_once("::") {
+        Some((module, items)) => (module.trim(), items.trim()),
+        None => (argument.trim(), ""),
+    };
+    if module.is_empty() {
+        return None;
+    }
+
+    let items = match items.strip_prefix('{') {
+        Some(list) => list
+            .strip_suffix('}')?
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .collect(),
+        None if items.is_empty() => Vec::new(),
+        None => vec![items],
+    };
+    Some((module, items))
+}
+
+/// Проверяет, объявлен ли в исходнике элемент верхнего уровня с данным именем
+fn declares(source: &str, item: &str) -> bool {
+    source.lines().any(|line| {
+        let mut line = line.trim_start();
+        // Пропускаем атрибуты вида `@group(0) @binding(0)`
+        while let Some(rest) = line.strip_prefix('@') {
+            let rest = rest.trim_start_matches(|c: char| c.is_alphanumeric() || c == '_');
+            line = match rest.strip_prefix('(') {
+                Some(args) => args.split_once(')').map_or("", |(_, tail)| tail),
+                None => rest,
+            }
+            .trim_start();
+        }
+
+        let declared = ["struct", "fn", "const", "override", "alias", "var"]
+            .iter()
+            .find_map(|keyword| {
+                let rest = line.strip_prefix(keyword)?;
+                if *keyword == "var" && rest.starts_with('<') {
+                    return rest.split_once('>').map(|(_, tail)| tail);
+                }
+                rest.starts_with(char::is_whitespace).then_some(rest)
+            });
+
+        declared.is_some_and(|rest| {
+            rest.trim_start()
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                == Some(item)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compose_main_with_imports() {
+        let composer = ShaderComposer::with_default_modules();
+        let composed = composer.compose("main").unwrap();
+
+        assert!(!composed.source.contains("#import"));
+        assert!(composed.source.contains("struct Camera"));
+        assert!(composed.source.contains("fn calculate_lighting"));
+        composed.validate().unwrap();
+    }
+
+    #[test]
+    fn test_default_modules_validate() {
+        let composer = ShaderComposer::with_default_modules();
+        for name in [
+            "camera", "lighting", "skybox", "floor", "light_gizmo", "tonemap", "ibl", "material", "fullscreen",
+            "post", "raytracer",
+        ] {
+            let composed = composer.compose(name).unwrap();
+            if let Err(err) = composed.validate() {
+                panic!("{} failed to validate: {}", name, err);
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_is_included_once() {
+        let mut composer = ShaderComposer::new();
+        composer.add_module("common", "common.wgsl", "const SCALE: f32 = 2.0;");
+        composer.add_module("a", "a.wgsl", "#import common::SCALE\nfn a() -> f32 { return SCALE; }");
+        composer.add_module(
+            "root",
+            "root.wgsl",
+            "#import a::a\n#import common::SCALE\nfn root() -> f32 { return a() * SCALE; }",
+        );
+
+        let composed = composer.compose("root").unwrap();
+        assert_eq!(composed.source.matches("const SCALE").count(), 1);
+        composed.validate().unwrap();
+    }
+
+    #[test]
+    fn test_import_cycle_detected() {
+        let mut composer = ShaderComposer::new();
+        composer.add_module("a", "a.wgsl", "#import b\nfn a() {}");
+        composer.add_module("b", "b.wgsl", "#import a\nfn b() {}");
+
+        match composer.compose("a") {
+            Err(ShaderError::ImportCycle(chain)) => assert_eq!(chain, vec!["a", "b", "a"]),
+            other => panic!("expected import cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_import_item() {
+        let composer = {
+            let mut composer = ShaderComposer::with_default_modules();
+            composer.add_module("broken", "broken.wgsl", "#import camera::Projection");
+            composer
+        };
+
+        match composer.compose("broken") {
+            Err(ShaderError::UnknownImportItem { item, line, .. }) => {
+                assert_eq!(item, "Projection");
+                assert_eq!(line, 1);
+            }
+            other => panic!("expected unknown import item, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ifdef_branches() {
+        let composer = ShaderComposer::with_default_modules();
+
+        let lit = composer.compose("main").unwrap();
+        assert!(lit.source.contains("calculate_lighting(normal)"));
+        assert!(!lit.source.contains("fn ambient_ibl"));
+
+        let ibl = composer.compose_with_defines("main", &["IBL"]).unwrap();
+        assert!(ibl.source.contains("fn ambient_ibl"));
+        assert!(ibl.source.contains("fn material_layers"));
+        ibl.validate().unwrap();
+
+        let unlit = composer.compose_with_defines("main", &["UNLIT"]).unwrap();
+        assert!(!unlit.source.contains("calculate_lighting(normal)"));
+        unlit.validate().unwrap();
+    }
+
+    #[test]
+    fn test_unterminated_ifdef() {
+        let mut composer = ShaderComposer::new();
+        composer.add_module("a", "a.wgsl", "fn a() {}\n#ifdef FOO\nfn b() {}");
+
+        match composer.compose("a") {
+            Err(ShaderError::Directive { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected directive error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_dependents() {
+        let composer = ShaderComposer::with_default_modules();
+        assert_eq!(composer.dependents("camera"), vec!["camera", "light_gizmo", "main", "skybox"]);
+        assert_eq!(composer.dependents("raytracer"), vec!["raytracer"]);
+    }
+
+    #[test]
+    fn test_from_directory_and_reload_file() {
+        let dir = tempfile::tempdir().unwrap();
+        for (_, file_name, _) in DEFAULT_MODULES {
+            let path = dir.path().join(file_name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, "fn placeholder() {}").unwrap();
+        }
+
+        let mut composer = ShaderComposer::from_directory(dir.path()).unwrap();
+        let raytracer = dir.path().join("raycast").join("raytracer.wgsl");
+        std::fs::write(&raytracer, "fn updated() {}").unwrap();
+
+        let reloaded = composer.reload_file(dir.path(), &raytracer).unwrap();
+        assert_eq!(reloaded.as_deref(), Some("raytracer"));
+        assert!(composer.module("raytracer").unwrap().source.contains("updated"));
+
+        let unknown = composer.reload_file(dir.path(), &dir.path().join("other.wgsl")).unwrap();
+        assert!(unknown.is_none());
+    }
+
+    #[test]
+    fn test_error_mapped_to_imported_module_line() {
+        let mut composer = ShaderComposer::new();
+        composer.add_module("util", "util.wgsl", "fn ok() {}\n\nfn broken() -> f32 { return missing; }");
+        composer.add_module("root", "root.wgsl", "#import util::ok\nfn root() { ok(); }");
+
+        let composed = composer.compose("root").unwrap();
+        match composed.validate() {
+            Err(ShaderError::Parse { file_name, line, .. }) => {
+                assert_eq!(file_name, "util.wgsl");
+                assert_eq!(line, 3);
+            }
+            other => panic!("expected parse error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
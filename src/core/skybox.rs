@@ -0,0 +1,118 @@
+//! Отрисовка неба сцены из [`AppScene::skybox`].
+//!
+//! Небо рисуется после непрозрачной геометрии: шейдер кладёт куб на дальнюю
+//! плоскость, а тест глубины `LessEqual` без записи оставляет его только там,
+//! где ничего не нарисовано.
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    res::{
+        storage::Storage,
+        texture::gpu_texture::{GpuTexture, CUBE_TEXTURE_BIND_GROUP_LAYOUT_ENTRIES},
+        TextureKey,
+    },
+    scene::AppScene,
+};
+
+use super::pipeline_cache::PipelineCache;
+
+#[rustfmt::skip]
+const CUBE_VERTICES: [[f32; 3]; 8] = [
+    [-1.0, -1.0, -1.0], [ 1.0, -1.0, -1.0], [ 1.0,  1.0, -1.0], [-1.0,  1.0, -1.0],
+    [-1.0, -1.0,  1.0], [ 1.0, -1.0,  1.0], [ 1.0,  1.0,  1.0], [-1.0,  1.0,  1.0],
+];
+
+#[rustfmt::skip]
+const CUBE_INDICES: [u16; 36] = [
+    0, 1, 2, 0, 2, 3, // зад
+    4, 6, 5, 4, 7, 6, // перед
+    0, 4, 5, 0, 5, 1, // низ
+    3, 2, 6, 3, 6, 7, // верх
+    1, 5, 6, 1, 6, 2, // право
+    0, 3, 7, 0, 7, 4, // лево
+];
+
+/// Геометрия неба и bind group текущей кубической карты сцены
+pub struct SkyboxRenderer {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    layout: wgpu::BindGroupLayout,
+    bind_group: Option<(TextureKey, wgpu::BindGroup)>,
+}
+
+impl SkyboxRenderer {
+    pub fn new(device: &wgpu::Device, cache: &mut PipelineCache) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("skybox_vertex_buffer"),
+            contents: bytemuck::cast_slice(&CUBE_VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("skybox_index_buffer"),
+            contents: bytemuck::cast_slice(&CUBE_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            layout: cache.bind_group_layout(device, CUBE_TEXTURE_BIND_GROUP_LAYOUT_ENTRIES),
+            bind_group: None,
+        }
+    }
+
+    /// Пересоздаёт bind group, если у сцены сменилась кубическая карта.
+    /// Возвращает `true`, если небо есть и его можно рисовать.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        scene: &AppScene,
+        textures: &Storage<GpuTexture>,
+    ) -> bool {
+        let Some((key, texture)) = scene.skybox.as_ref().and_then(|handle| {
+            textures.get(handle.clone()).map(|texture| (handle.key(), texture))
+        }) else {
+            self.bind_group = None;
+            return false;
+        };
+
+        if self.bind_group.as_ref().is_some_and(|(current, _)| *current == key) {
+            return true;
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("skybox_bind_group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+        });
+        self.bind_group = Some((key, bind_group));
+        true
+    }
+
+    /// Рисует небо пайплайном [`PipelineType::Skybox`](super::PipelineType::Skybox)
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        pipeline: &wgpu::RenderPipeline,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        let Some((_, bind_group)) = &self.bind_group else {
+            return;
+        };
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_bind_group(1, bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..CUBE_INDICES.len() as u32, 0, 0..1);
+    }
+}
@@ -0,0 +1,144 @@
+//! Ограничивающие объёмы для отсечения и выбора объектов.
+
+use glam::{Mat4, Vec3};
+
+/// Параллелепипед, выровненный по осям
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Aabb {
+    /// Пустой объём: объединение с ним ничего не меняет
+    pub const EMPTY: Aabb = Aabb {
+        min: Vec3::splat(f32::INFINITY),
+        max: Vec3::splat(f32::NEG_INFINITY),
+    };
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |aabb, point| aabb.including(point))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn including(self, point: Vec3) -> Self {
+        Self::new(self.min.min(point), self.max.max(point))
+    }
+
+    pub fn union(self, other: Aabb) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Описанная сфера
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere {
+            center: self.center(),
+            radius: self.size().length() * 0.5,
+        }
+    }
+
+    /// Объём, охватывающий преобразованные углы
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let corners = (0..8).map(|corner| {
+            let pick = |bit: usize, axis: usize| if corner & bit == 0 { self.min[axis] } else { self.max[axis] };
+            transform.transform_point3(Vec3::new(pick(1, 0), pick(2, 1), pick(4, 2)))
+        });
+        Self::from_points(corners)
+    }
+
+    /// Расстояние вдоль луча до входа в объём (0, если начало внутри)
+    pub fn intersect_ray(&self, origin: Vec3, direction: Vec3) -> Option<f32> {
+        let inverse = direction.recip();
+        let t1 = (self.min - origin) * inverse;
+        let t2 = (self.max - origin) * inverse;
+        let near = t1.min(t2).max_element().max(0.0);
+        let far = t1.max(t2).min_element();
+        (near <= far).then_some(near)
+    }
+}
+
+/// Ограничивающая сфера
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Сфера с центром в середине [`Aabb`] точек; не минимальная, но близкая к ней
+    pub fn from_points(points: &[Vec3]) -> Self {
+        let center = Aabb::from_points(points.iter().copied()).center();
+        let radius = points.iter().map(|point| point.distance(center)).fold(0.0, f32::max);
+        Self { center, radius }
+    }
+
+    /// Сфера после преобразования; радиус растёт по наибольшему масштабу
+    pub fn transformed(&self, transform: &Mat4) -> Self {
+        let scale = [transform.x_axis, transform.y_axis, transform.z_axis]
+            .map(|axis| axis.truncate().length())
+            .into_iter()
+            .fold(0.0, f32::max);
+        Self {
+            center: transform.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aabb() {
+        let aabb = Aabb::from_points([Vec3::new(-1.0, 0.0, 2.0), Vec3::new(1.0, 2.0, -2.0)]);
+        assert_eq!(aabb, Aabb::new(Vec3::new(-1.0, 0.0, -2.0), Vec3::new(1.0, 2.0, 2.0)));
+        assert!(Aabb::EMPTY.is_empty());
+        assert_eq!(Aabb::EMPTY.union(aabb), aabb);
+
+        let moved = aabb.transformed(&(Mat4::from_translation(Vec3::X) * Mat4::from_rotation_y(std::f32::consts::FRAC_PI_2)));
+        assert!((moved.min - Vec3::new(-1.0, 0.0, -1.0)).length() < 1e-5);
+        assert!((moved.max - Vec3::new(3.0, 2.0, 1.0)).length() < 1e-5);
+
+        assert_eq!(aabb.intersect_ray(Vec3::new(0.0, 1.0, -5.0), Vec3::Z), Some(3.0));
+        assert_eq!(aabb.intersect_ray(Vec3::new(0.0, 1.0, 0.0), Vec3::Z), Some(0.0));
+        assert_eq!(aabb.intersect_ray(Vec3::new(0.0, 5.0, -5.0), Vec3::Z), None);
+    }
+
+    #[test]
+    fn test_bounding_sphere() {
+        let sphere = BoundingSphere::from_points(&[Vec3::new(-1.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)]);
+        assert_eq!(sphere, BoundingSphere { center: Vec3::X, radius: 2.0 });
+
+        let scaled = sphere.transformed(&Mat4::from_scale(Vec3::new(1.0, 3.0, 1.0)));
+        assert_eq!(scaled.radius, 6.0);
+    }
+}
@@ -0,0 +1,67 @@
+//! Пирамида видимости камеры для отсечения объектов.
+
+use glam::{Mat4, Vec3, Vec4, Vec4Swizzles};
+
+use super::bounds::Aabb;
+
+/// Шесть плоскостей пирамиды видимости, нормали смотрят внутрь
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// Левая, правая, нижняя, верхняя, ближняя, дальняя; `xyz` — нормаль, `w` — смещение
+    pub planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Плоскости из матрицы `projection * view` с глубиной 0..1, как в wgpu
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let [x, y, z, w] = [0, 1, 2, 3].map(|row| view_projection.row(row));
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let length = plane.xyz().length();
+            if length > 0.0 { plane / length } else { plane }
+        });
+        Self { planes }
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.xyz().dot(point) + plane.w >= 0.0)
+    }
+
+    /// `false`, только если объём целиком за одной из плоскостей; объём у
+    /// угла пирамиды может попасть в видимые, хотя его не видно
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        self.planes.iter().all(|plane| {
+            // Угол объёма дальше всех по нормали плоскости
+            let normal = plane.xyz();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frustum_culling() {
+        let view = Mat4::look_at_rh(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO, Vec3::Y);
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+        let frustum = Frustum::from_view_projection(&(projection * view));
+
+        assert!(frustum.contains_point(Vec3::ZERO));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 6.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -96.0)));
+
+        let unit = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+        assert!(frustum.intersects_aabb(&unit));
+        // Сбоку за краем поля зрения (полуширина 5 на глубине 5) и за спиной камеры
+        assert!(!frustum.intersects_aabb(&Aabb::new(Vec3::new(6.0, -0.5, -0.5), Vec3::new(7.0, 0.5, 0.5))));
+        assert!(!frustum.intersects_aabb(&Aabb::new(Vec3::new(-0.5, -0.5, 6.0), Vec3::new(0.5, 0.5, 7.0))));
+        // Пересекает край
+        assert!(frustum.intersects_aabb(&Aabb::new(Vec3::new(4.5, -0.5, -0.5), Vec3::new(7.0, 0.5, 0.5))));
+        assert!(!frustum.intersects_aabb(&Aabb::EMPTY));
+    }
+}
@@ -1,5 +1,7 @@
 pub mod angle;
 pub mod sphere;
+pub mod bounds;
+pub mod frustum;
 
 pub fn unit_quad_projection_matrix() -> nalgebra_glm::Mat4 {
     let sw = 0.5_f32;
@@ -1,214 +1,677 @@
-use super::{storage::Storage, Handle, Resource};
-use std::{error::Error, fmt};
+//! Анимации glTF: каналы ключевых кадров, их выборка и проигрывание.
+//!
+//! Клип ([`Animation`]) выбирается в момент времени в [`Pose`] — значения
+//! свойств узлов, которые затем записываются в `Transform` узлов сцены.
+//! [`AnimationPlayer`] хранит несколько проигрываемых клипов и смешивает их.
 
-use gltf::Gltf;
+use super::{error::AssetError, scene::Scene, storage::Storage, AnimationKey, Handle, Resource};
+use std::collections::HashMap;
 
-#[derive(Debug, Clone)] 
+use glam::{Quat, Vec3};
+use gltf::{animation::Interpolation as GltfInterpolation, Gltf};
+
+use crate::scene::transform::Transform;
+
+/// Способ интерполяции между ключевыми кадрами
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Linear,
+    Step,
+    /// Кубический сплайн Эрмита: на каждый кадр приходятся входящая касательная,
+    /// значение и исходящая касательная
+    CubicSpline,
+}
+
+impl From<GltfInterpolation> for Interpolation {
+    fn from(value: GltfInterpolation) -> Self {
+        match value {
+            GltfInterpolation::Linear => Interpolation::Linear,
+            GltfInterpolation::Step => Interpolation::Step,
+            GltfInterpolation::CubicSpline => Interpolation::CubicSpline,
+        }
+    }
+}
+
+/// Выходные значения канала, по элементу на кадр (по три для сплайна)
+#[derive(Debug, Clone)]
 pub enum Keyframes {
-    Translation(Vec<Vec<f32>>),
-    Other,
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// Веса морф-целей: `targets` значений на кадр подряд
+    MorphWeights { targets: usize, values: Vec<f32> },
 }
 
-#[derive(Debug, Clone)] 
+/// Канал: одно свойство одного узла
+#[derive(Debug, Clone)]
+pub struct Channel {
+    /// Индекс узла glTF
+    pub target_node: usize,
+    pub interpolation: Interpolation,
+    pub timestamps: Vec<f32>,
+    pub keyframes: Keyframes,
+}
+
+/// Значение канала в момент времени
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelValue {
+    Translation(Vec3),
+    Rotation(Quat),
+    Scale(Vec3),
+    MorphWeights(Vec<f32>),
+}
+
+/// Анимационный клип
+#[derive(Debug, Clone)]
 pub struct Animation {
     pub name: String,
-    pub keyframes: Keyframes,
-    pub timestamps: Vec<f32>,
+    pub channels: Vec<Channel>,
+    /// Время последнего ключевого кадра
+    pub duration: f32,
 }
 
 impl Resource for Animation {
-    type Key = super::TextureKey;
-    
+    type Key = AnimationKey;
+
     type LoadParams = Animation;
-    
-    fn load(anim: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>>{
-        Ok(Self {
-            name: anim.name,
-            keyframes: anim.keyframes,
-            timestamps: anim.timestamps,
-        })
+
+    fn load(anim: Self::LoadParams) -> Result<Self, AssetError>{
+        Ok(anim)
     }
 }
 
-#[derive(Debug)]
-pub struct LoadAnimationError {
-    message: String,
-    source: Option<Box<dyn Error + Send + Sync>>,
+pub type AnimationStorage = Storage<Animation>;
+pub type AnimationHandle = Handle<Animation>;
+
+/// Кубический сплайн Эрмита между `v0` и `v1` с касательными, уже умноженными на длину отрезка
+fn hermite<T>(v0: T, out_tangent: T, v1: T, in_tangent: T, t: f32) -> T
+where
+    T: std::ops::Mul<f32, Output = T> + std::ops::Add<Output = T>,
+{
+    let t2 = t * t;
+    let t3 = t2 * t;
+    v0 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + out_tangent * (t3 - 2.0 * t2 + t)
+        + v1 * (-2.0 * t3 + 3.0 * t2)
+        + in_tangent * (t3 - t2)
 }
 
-impl fmt::Display for LoadAnimationError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Buffer loading error: {}", self.message)
+impl Channel {
+    pub fn from_gltf(channel: &gltf::animation::Channel, buffers: &[Vec<u8>]) -> Result<Self, AssetError> {
+        let reader = channel.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+        let timestamps: Vec<f32> = reader
+            .read_inputs()
+            .ok_or_else(|| AssetError::invalid("channel has no input accessor"))?
+            .collect();
+
+        use gltf::animation::util::ReadOutputs;
+        let keyframes = match reader
+            .read_outputs()
+            .ok_or_else(|| AssetError::invalid("channel has no output accessor"))?
+        {
+            ReadOutputs::Translations(values) => Keyframes::Translation(values.map(Vec3::from).collect()),
+            ReadOutputs::Rotations(values) => {
+                Keyframes::Rotation(values.into_f32().map(Quat::from_array).collect())
+            }
+            ReadOutputs::Scales(values) => Keyframes::Scale(values.map(Vec3::from).collect()),
+            ReadOutputs::MorphTargetWeights(values) => {
+                let values: Vec<f32> = values.into_f32().collect();
+                let frames = timestamps.len().max(1);
+                let per_frame = match channel.sampler().interpolation() {
+                    GltfInterpolation::CubicSpline => values.len() / (frames * 3),
+                    _ => values.len() / frames,
+                };
+                Keyframes::MorphWeights { targets: per_frame, values }
+            }
+        };
+
+        let channel = Self {
+            target_node: channel.target().node().index(),
+            interpolation: channel.sampler().interpolation().into(),
+            timestamps,
+            keyframes,
+        };
+        let expected = match channel.interpolation {
+            Interpolation::CubicSpline => channel.timestamps.len() * 3,
+            _ => channel.timestamps.len(),
+        };
+        if channel.timestamps.is_empty() || channel.frame_count() != expected {
+            return Err(AssetError::invalid(format!(
+                "channel of node {} has {} inputs and {} outputs",
+                channel.target_node,
+                channel.timestamps.len(),
+                channel.frame_count()
+            )));
+        }
+        Ok(channel)
     }
-}
 
-impl Error for LoadAnimationError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        self.source.as_ref().map(|e| &**e as _)
+    /// Число выходных элементов (для сплайна втрое больше числа кадров)
+    fn frame_count(&self) -> usize {
+        match &self.keyframes {
+            Keyframes::Translation(values) | Keyframes::Scale(values) => values.len(),
+            Keyframes::Rotation(values) => values.len(),
+            Keyframes::MorphWeights { targets, values } => values.len() / (*targets).max(1),
+        }
     }
-}
 
-impl LoadAnimationError {
-    pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            source: None,
+    /// Отрезок `[i, i + 1]`, содержащий `time`, и доля внутри него. До первого
+    /// и после последнего кадра значение держится.
+    fn segment(&self, time: f32) -> (usize, usize, f32, f32) {
+        let times = &self.timestamps;
+        let last = times.len() - 1;
+        if time <= times[0] {
+            return (0, 0, 0.0, 0.0);
         }
+        if time >= times[last] {
+            return (last, last, 0.0, 0.0);
+        }
+        let next = times.partition_point(|&t| t <= time);
+        let prev = next - 1;
+        let delta = times[next] - times[prev];
+        let factor = if delta > 0.0 { (time - times[prev]) / delta } else { 0.0 };
+        (prev, next, factor, delta)
     }
 
-    pub fn with_source(message: impl Into<String>, source: impl Error + Send + Sync + 'static) -> Self {
-        Self {
-            message: message.into(),
-            source: Some(Box::new(source)),
+    pub fn sample(&self, time: f32) -> ChannelValue {
+        let (prev, next, factor, delta) = self.segment(time);
+        let interpolation = self.interpolation;
+
+        // Для сплайна значение кадра k лежит в элементе 3k + 1
+        fn pick<T: Copy>(values: &[T], interpolation: Interpolation, frame: usize, offset: usize) -> T {
+            match interpolation {
+                Interpolation::CubicSpline => values[frame * 3 + offset],
+                _ => values[frame],
+            }
+        }
+        fn vector(values: &[Vec3], interpolation: Interpolation, prev: usize, next: usize, t: f32, delta: f32) -> Vec3 {
+            match interpolation {
+                Interpolation::Step => values[prev],
+                Interpolation::Linear => values[prev].lerp(values[next], t),
+                Interpolation::CubicSpline => hermite(
+                    pick(values, interpolation, prev, 1),
+                    pick(values, interpolation, prev, 2) * delta,
+                    pick(values, interpolation, next, 1),
+                    pick(values, interpolation, next, 0) * delta,
+                    t,
+                ),
+            }
+        }
+
+        match &self.keyframes {
+            Keyframes::Translation(values) => {
+                ChannelValue::Translation(vector(values, interpolation, prev, next, factor, delta))
+            }
+            Keyframes::Scale(values) => ChannelValue::Scale(vector(values, interpolation, prev, next, factor, delta)),
+            Keyframes::Rotation(values) => ChannelValue::Rotation(match interpolation {
+                Interpolation::Step => values[prev],
+                Interpolation::Linear => values[prev].slerp(values[next], factor),
+                Interpolation::CubicSpline => {
+                    let as_vec = |q: Quat| glam::Vec4::from(q);
+                    let value = hermite(
+                        as_vec(pick(values, interpolation, prev, 1)),
+                        as_vec(pick(values, interpolation, prev, 2)) * delta,
+                        as_vec(pick(values, interpolation, next, 1)),
+                        as_vec(pick(values, interpolation, next, 0)) * delta,
+                        factor,
+                    );
+                    Quat::from_vec4(value).normalize()
+                }
+            }),
+            Keyframes::MorphWeights { targets, values } => {
+                let targets = *targets;
+                let weight = |frame: usize, offset: usize, target: usize| match interpolation {
+                    Interpolation::CubicSpline => values[(frame * 3 + offset) * targets + target],
+                    _ => values[frame * targets + target],
+                };
+                ChannelValue::MorphWeights(
+                    (0..targets)
+                        .map(|target| match interpolation {
+                            Interpolation::Step => weight(prev, 0, target),
+                            Interpolation::Linear => {
+                                let (a, b) = (weight(prev, 0, target), weight(next, 0, target));
+                                a + (b - a) * factor
+                            }
+                            Interpolation::CubicSpline => hermite(
+                                weight(prev, 1, target),
+                                weight(prev, 2, target) * delta,
+                                weight(next, 1, target),
+                                weight(next, 0, target) * delta,
+                                factor,
+                            ),
+                        })
+                        .collect(),
+                )
+            }
         }
     }
 }
 
-pub type AnimationStorage = Storage<Animation>;
-pub type AnimationHandle = Handle<Animation>;
+/// Значения свойств одного узла; `None` — свойство клипом не анимируется
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodePose {
+    pub translation: Option<Vec3>,
+    pub rotation: Option<Quat>,
+    pub scale: Option<Vec3>,
+    pub weights: Option<Vec<f32>>,
+}
 
+impl NodePose {
+    /// Записывает анимируемые свойства в преобразование
+    pub fn apply(&self, transform: &mut Transform) {
+        if let Some(translation) = self.translation {
+            transform.position = translation;
+        }
+        if let Some(rotation) = self.rotation {
+            transform.rotation = rotation;
+        }
+        if let Some(scale) = self.scale {
+            transform.scale = scale;
+        }
+    }
 
-impl Animation {
+    fn set(&mut self, value: ChannelValue) {
+        match value {
+            ChannelValue::Translation(v) => self.translation = Some(v),
+            ChannelValue::Rotation(q) => self.rotation = Some(q),
+            ChannelValue::Scale(v) => self.scale = Some(v),
+            ChannelValue::MorphWeights(w) => self.weights = Some(w),
+        }
+    }
 
-    pub fn from_gltf_animation_chanel(
-        channel: gltf::animation::Channel,
-        buffers: &[Vec<u8>], 
-        name: String,
-    )-> Result<Animation, LoadAnimationError>{
-        let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
-        let timestamps = if let Some(inputs) = reader.read_inputs() {
-            match inputs {
-                gltf::accessor::Iter::Standard(times) => {
-                    let times: Vec<f32> = times.collect();
-                    println!("Time: {}", times.len());
-                    // dbg!(times);
-                    times
-                }
-                gltf::accessor::Iter::Sparse(_) => {
-                    println!("Sparse keyframes not supported");
-                    let times: Vec<f32> = Vec::new();
-                    times
-                }
-            }
-        } else {
-            println!("We got problems");
-            let times: Vec<f32> = Vec::new();
-            times
+    /// Смешивает с `other` с долей `t`; свойство, заданное только с одной
+    /// стороны, берётся как есть
+    fn blend(&mut self, other: &NodePose, t: f32) {
+        fn mix<T: Copy>(a: &mut Option<T>, b: Option<T>, f: impl Fn(T, T) -> T) {
+            *a = match (*a, b) {
+                (Some(a), Some(b)) => Some(f(a, b)),
+                (a, b) => a.or(b),
+            };
+        }
+        mix(&mut self.translation, other.translation, |a, b| a.lerp(b, t));
+        mix(&mut self.rotation, other.rotation, |a, b| a.slerp(b, t));
+        mix(&mut self.scale, other.scale, |a, b| a.lerp(b, t));
+        self.weights = match (self.weights.take(), &other.weights) {
+            (Some(a), Some(b)) => Some(a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect()),
+            (a, b) => a.or_else(|| b.clone()),
         };
-        let keyframes = if let Some(outputs) = reader.read_outputs() {
-            match outputs {
-                gltf::animation::util::ReadOutputs::Translations(translation) => {
-                    let translation_vec = translation.map(|tr| {
-                        // println!("Translation:");
-                        // dbg!(tr);
-                        let vector: Vec<f32> = tr.into();
-                        vector
-                    }).collect();
-                    Keyframes::Translation(translation_vec)
-                },
-                other => {
-                    Keyframes::Other
+    }
+}
+
+/// Поза: значения свойств по индексам узлов glTF
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Pose {
+    pub nodes: HashMap<usize, NodePose>,
+}
+
+impl Pose {
+    /// Записывает позу в узлы сцены (по [`SceneNode::gltf_index`](super::scene::SceneNode::gltf_index))
+    pub fn apply_to_scene(&self, scene: &mut Scene) {
+        for node in &mut scene.nodes {
+            if let Some(pose) = self.nodes.get(&node.gltf_index) {
+                pose.apply(&mut node.transform);
+                if let Some(weights) = &pose.weights {
+                    node.weights.clone_from(weights);
                 }
-                // gltf::animation::util::ReadOutputs::Rotations(_) => todo!(),
-                // gltf::animation::util::ReadOutputs::Scales(_) => todo!(),
-                // gltf::animation::util::ReadOutputs::MorphTargetWeights(_) => todo!(),
             }
-        } else {
-            println!("We got problems");
-            Keyframes::Other
-        };
-        Ok(Animation {
-            name: name.to_string(),
-            keyframes,
-            timestamps,
-        })
+        }
     }
-    
-    pub fn from_gltf_animation(
-        gltf_animation: gltf::Animation,
-        buffers: &[Vec<u8>], 
-    )->Result<Vec<Animation>, LoadAnimationError>{
-        let mut animation_clips = Vec::new();
-        for channel in gltf_animation.channels() {
-            animation_clips.push( match  Animation::from_gltf_animation_chanel(
-                channel, 
-                buffers,
-                gltf_animation.name().unwrap_or("Default").to_string()){
-                    Ok(anim) => anim,
-                    Err(_) => return Err(LoadAnimationError::new("Error loading animations")),
-                }
-            ) 
+
+    /// Смешивание поз: `t = 0` — `self`, `t = 1` — `other`
+    pub fn blend(&mut self, other: &Pose, t: f32) {
+        for (node, pose) in &other.nodes {
+            self.nodes.entry(*node).or_default().blend(pose, t);
         }
-        Ok(animation_clips)
     }
+}
 
+impl Animation {
+    pub fn from_gltf(animation: &gltf::Animation, buffers: &[Vec<u8>]) -> Result<Self, AssetError> {
+        let channels = animation
+            .channels()
+            .map(|channel| Channel::from_gltf(&channel, buffers))
+            .collect::<Result<Vec<_>, _>>()?;
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.timestamps.last().copied())
+            .fold(0.0, f32::max);
+        Ok(Self {
+            name: animation.name().unwrap_or("Default").to_string(),
+            channels,
+            duration,
+        })
+    }
+
+    /// Поза клипа в момент `time` (без зацикливания)
+    pub fn sample(&self, time: f32) -> Pose {
+        let mut pose = Pose::default();
+        for channel in &self.channels {
+            pose.nodes.entry(channel.target_node).or_default().set(channel.sample(time));
+        }
+        pose
+    }
 }
 
 pub fn load_gltf_animations(
     gltf: &Gltf,
-    buffers: &[Vec<u8>], 
-)-> Result<Vec<Animation>, LoadAnimationError>{
-    let mut animation_clips = Vec::new();
-    for animation in gltf.animations() {
-        let mut anims = match Animation::from_gltf_animation(animation, buffers){
-                Ok(anims) => anims,
-                Err(_) => return Err(LoadAnimationError::new("Error loading animations")),
+    buffers: &[Vec<u8>],
+)-> Result<Vec<Animation>, AssetError>{
+    gltf.animations()
+        .map(|animation| {
+            Animation::from_gltf(&animation, buffers).map_err(|err| AssetError::Animation {
+                index: animation.index(),
+                source: Box::new(err),
+            })
+        })
+        .collect()
+}
+
+/// Один проигрываемый клип
+#[derive(Debug, Clone)]
+pub struct Playback {
+    pub animation: Handle<Animation>,
+    pub time: f32,
+    /// Множитель скорости, отрицательный проигрывает назад
+    pub speed: f32,
+    pub looping: bool,
+    /// Вес при смешивании
+    pub weight: f32,
+    /// Изменение веса в секунду при плавном переходе
+    fade: f32,
+}
+
+impl Playback {
+    pub fn new(animation: Handle<Animation>) -> Self {
+        Self {
+            animation,
+            time: 0.0,
+            speed: 1.0,
+            looping: true,
+            weight: 1.0,
+            fade: 0.0,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Время внутри клипа с учётом зацикливания
+    fn local_time(&self, duration: f32) -> f32 {
+        if duration <= 0.0 {
+            0.0
+        } else if self.looping {
+            self.time.rem_euclid(duration)
+        } else {
+            self.time.clamp(0.0, duration)
+        }
+    }
+
+    /// Клип без зацикливания доиграл до конца
+    pub fn is_finished(&self, duration: f32) -> bool {
+        !self.looping && (self.time >= duration && self.speed >= 0.0 || self.time <= 0.0 && self.speed < 0.0)
+    }
+}
+
+/// Проигрыватель: несколько клипов со своими временем, скоростью и весом
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    pub playbacks: Vec<Playback>,
+    /// Общий множитель скорости
+    pub speed: f32,
+    pub paused: bool,
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        Self {
+            playbacks: Vec::new(),
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    /// Заменяет все клипы одним
+    pub fn play(&mut self, playback: Playback) {
+        self.playbacks.clear();
+        self.playbacks.push(playback);
+    }
+
+    /// Добавляет клип к смешиваемым
+    pub fn add(&mut self, playback: Playback) {
+        self.playbacks.push(playback);
+    }
+
+    /// Плавно переходит к клипу за `duration` секунд: его вес растёт от нуля,
+    /// остальные затухают и удаляются
+    pub fn crossfade(&mut self, mut playback: Playback, duration: f32) {
+        if duration <= 0.0 {
+            self.play(playback);
+            return;
+        }
+        for current in &mut self.playbacks {
+            current.fade = -current.weight / duration;
+        }
+        playback.weight = 0.0;
+        playback.fade = 1.0 / duration;
+        self.playbacks.push(playback);
+    }
+
+    /// Продвигает время на `dt` секунд
+    pub fn update(&mut self, dt: f32) {
+        if self.paused {
+            return;
+        }
+        for playback in &mut self.playbacks {
+            playback.time += dt * self.speed * playback.speed;
+            if playback.fade != 0.0 {
+                playback.weight = (playback.weight + playback.fade * dt).clamp(0.0, 1.0);
+                if playback.weight >= 1.0 {
+                    playback.fade = 0.0;
+                }
+            }
+        }
+        self.playbacks.retain(|playback| playback.fade >= 0.0 || playback.weight > 0.0);
+    }
+
+    /// Смешанная поза всех клипов; отсутствующие в хранилище пропускаются
+    pub fn sample(&self, animations: &Storage<Animation>) -> Pose {
+        let mut pose = Pose::default();
+        let mut total = 0.0;
+        for playback in &self.playbacks {
+            let Some(animation) = animations.get(playback.animation.clone()) else {
+                continue;
             };
-        animation_clips.append(&mut anims);
-    }
-    Ok(animation_clips)
-}
-
-// pub fn load_gltf_animations(
-//     gltf: &Gltf,
-//     buffers: &[Vec<u8>], 
-// )-> Result<Vec<Animation>, LoadAnimationError>{
-//     let mut animation_clips = Vec::new();
-//     for animation in gltf.animations() {
-//         for channel in animation.channels() {
-//             let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
-//             let timestamps = if let Some(inputs) = reader.read_inputs() {
-//                 match inputs {
-//                     gltf::accessor::Iter::Standard(times) => {
-//                         let times: Vec<f32> = times.collect();
-//                         times
-//                     }
-//                     gltf::accessor::Iter::Sparse(_) => {
-//                         let times: Vec<f32> = Vec::new();
-//                         times
-//                     }
-//                 }
-//             } else {
-//                 let times: Vec<f32> = Vec::new();
-//                 times
-//             };
-
-//             let keyframes = if let Some(outputs) = reader.read_outputs() {
-//                 match outputs {
-//                     gltf::animation::util::ReadOutputs::Translations(translation) => {
-//                         let translation_vec = translation.map(|tr| {
-//                             let vector: Vec<f32> = tr.into();
-//                             vector
-//                         }).collect();
-//                         Keyframes::Translation(translation_vec)
-//                     },
-//                     other => {
-//                         Keyframes::Other
-//                     }
-//                     // gltf::animation::util::ReadOutputs::Rotations(_) => todo!(),
-//                     // gltf::animation::util::ReadOutputs::Scales(_) => todo!(),
-//                     // gltf::animation::util::ReadOutputs::MorphTargetWeights(_) => todo!(),
-//                 }
-//             } else {
-//                 Keyframes::Other
-//             };
-
-//             animation_clips.push(Animation {
-//                 name: animation.name().unwrap_or("Default").to_string(),
-//                 keyframes,
-//                 timestamps,
-//             })
-//         }
-//     }
-//     Ok(animation_clips)
-// }
+            if playback.weight <= 0.0 {
+                continue;
+            }
+            let sampled = animation.sample(playback.local_time(animation.duration));
+            total += playback.weight;
+            // Накопительное среднее: доля нового клипа — его вес среди уже учтённых
+            pose.blend(&sampled, playback.weight / total);
+        }
+        pose
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(interpolation: Interpolation, keyframes: Keyframes) -> Channel {
+        Channel {
+            target_node: 0,
+            interpolation,
+            timestamps: vec![0.0, 1.0, 2.0],
+            keyframes,
+        }
+    }
+
+    #[test]
+    fn test_linear_and_step() {
+        let values = vec![Vec3::ZERO, Vec3::X, Vec3::new(3.0, 0.0, 0.0)];
+        let linear = channel(Interpolation::Linear, Keyframes::Translation(values.clone()));
+        assert_eq!(linear.sample(0.5), ChannelValue::Translation(Vec3::new(0.5, 0.0, 0.0)));
+        assert_eq!(linear.sample(1.5), ChannelValue::Translation(Vec3::new(2.0, 0.0, 0.0)));
+        // За пределами кадров значение держится
+        assert_eq!(linear.sample(-1.0), ChannelValue::Translation(Vec3::ZERO));
+        assert_eq!(linear.sample(5.0), ChannelValue::Translation(Vec3::new(3.0, 0.0, 0.0)));
+
+        let step = channel(Interpolation::Step, Keyframes::Translation(values));
+        assert_eq!(step.sample(1.99), ChannelValue::Translation(Vec3::X));
+    }
+
+    #[test]
+    fn test_rotation_slerp() {
+        let quarter = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let rotation = channel(
+            Interpolation::Linear,
+            Keyframes::Rotation(vec![Quat::IDENTITY, quarter, quarter]),
+        );
+        let ChannelValue::Rotation(half) = rotation.sample(0.5) else {
+            panic!("expected rotation");
+        };
+        assert!(half.abs_diff_eq(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4), 1e-6));
+        assert!((half.length() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cubic_spline() {
+        // Касательные нулевые: сплайн проходит через кадры со сглаживанием smoothstep
+        let zero = Vec3::ZERO;
+        let values = vec![zero, zero, zero, zero, Vec3::X, zero, zero, Vec3::X, zero];
+        let spline = channel(Interpolation::CubicSpline, Keyframes::Scale(values));
+        assert_eq!(spline.sample(1.0), ChannelValue::Scale(Vec3::X));
+        let ChannelValue::Scale(quarter) = spline.sample(0.25) else {
+            panic!("expected scale");
+        };
+        assert!((quarter.x - 0.15625).abs() < 1e-6, "{}", quarter);
+
+        let weights = Channel {
+            target_node: 0,
+            interpolation: Interpolation::Linear,
+            timestamps: vec![0.0, 1.0],
+            keyframes: Keyframes::MorphWeights { targets: 2, values: vec![0.0, 1.0, 1.0, 0.0] },
+        };
+        assert_eq!(weights.sample(0.25), ChannelValue::MorphWeights(vec![0.25, 0.75]));
+    }
+
+    #[test]
+    fn test_animation_from_gltf() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "nodes": [{ "name": "box" }],
+                "buffers": [{ "byteLength": 40 }],
+                "bufferViews": [
+                    { "buffer": 0, "byteOffset": 0, "byteLength": 8 },
+                    { "buffer": 0, "byteOffset": 8, "byteLength": 32 }
+                ],
+                "accessors": [
+                    { "bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR", "min": [0.0], "max": [2.0] },
+                    { "bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC4" }
+                ],
+                "animations": [{
+                    "name": "spin",
+                    "samplers": [{ "input": 0, "output": 1, "interpolation": "LINEAR" }],
+                    "channels": [{ "sampler": 0, "target": { "node": 0, "path": "rotation" } }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let turn = Quat::from_rotation_z(2.0 * std::f32::consts::FRAC_PI_3);
+        let data: Vec<f32> = [0.0, 2.0]
+            .into_iter()
+            .chain(Quat::IDENTITY.to_array())
+            .chain(turn.to_array())
+            .collect();
+        let buffers = vec![bytemuck::cast_slice(&data).to_vec()];
+
+        let animations = load_gltf_animations(&gltf, &buffers).unwrap();
+        assert_eq!(animations.len(), 1);
+        let spin = &animations[0];
+        assert_eq!((spin.name.as_str(), spin.duration), ("spin", 2.0));
+        let pose = spin.sample(1.0);
+        let rotation = pose.nodes[&0].rotation.unwrap();
+        assert!(rotation.abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_3), 1e-6), "{}", rotation);
+        assert_eq!(pose.nodes[&0].translation, None);
+    }
+
+    fn clip(name: &str, to: Vec3) -> Animation {
+        Animation {
+            name: name.to_string(),
+            channels: vec![Channel {
+                target_node: 0,
+                interpolation: Interpolation::Linear,
+                timestamps: vec![0.0, 1.0],
+                keyframes: Keyframes::Translation(vec![Vec3::ZERO, to]),
+            }],
+            duration: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_player_loop_speed_and_blend() {
+        let mut animations = Storage::new();
+        let walk = animations.load(clip("walk", Vec3::X)).unwrap();
+        let jump = animations.load(clip("jump", Vec3::Y)).unwrap();
+        let translation = |player: &AnimationPlayer| player.sample(&animations).nodes[&0].translation.unwrap();
+
+        let mut player = AnimationPlayer::new();
+        player.play(Playback::new(walk.clone()).with_speed(0.5));
+        player.update(3.0);
+        // 1.5 с клипа длиной 1 с по кругу — середина
+        assert!(translation(&player).abs_diff_eq(Vec3::new(0.5, 0.0, 0.0), 1e-6));
+
+        let mut once = AnimationPlayer::default();
+        once.play(Playback::new(walk.clone()).with_looping(false));
+        once.update(5.0);
+        assert_eq!(translation(&once), Vec3::X);
+        assert!(once.playbacks[0].is_finished(1.0));
+
+        // Переход за 1 с: на полпути оба клипа весят одинаково
+        let mut player = AnimationPlayer::new();
+        player.play(Playback::new(walk).with_speed(0.0).with_looping(false));
+        player.playbacks[0].time = 1.0;
+        player.crossfade(Playback::new(jump).with_speed(0.0).with_looping(false), 1.0);
+        player.playbacks[1].time = 1.0;
+        player.update(0.5);
+        assert!(translation(&player).abs_diff_eq(Vec3::new(0.5, 0.5, 0.0), 1e-6));
+        player.update(0.6);
+        assert_eq!(player.playbacks.len(), 1);
+        assert_eq!(translation(&player), Vec3::Y);
+    }
+
+    #[test]
+    fn test_pose_applies_to_scene() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "scenes": [{ "nodes": [0] }],
+                "nodes": [{ "name": "root", "children": [1] }, { "name": "child", "scale": [2.0, 2.0, 2.0] }]
+            }"#,
+        )
+        .unwrap();
+        let mut scene = Scene::from_gltf(&gltf.scenes().next().unwrap(), &[], &[], &[], &[]).unwrap();
+        let mut pose = Pose::default();
+        pose.nodes.insert(1, NodePose { translation: Some(Vec3::Z), ..Default::default() });
+        pose.apply_to_scene(&mut scene);
+
+        let child = &scene.nodes[scene.find_node("child").unwrap()];
+        assert_eq!(child.transform.position, Vec3::Z);
+        // Не анимируемые свойства не меняются
+        assert_eq!(child.transform.scale, Vec3::splat(2.0));
+    }
+}
@@ -1,11 +1,21 @@
-use core::fmt;
-use std::{error::Error, path::Path};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
 
 use gltf::{Gltf};
 
-use crate::res::texture::{gpu_texture::GpuTexture, load_gltf_texture_source_data};
+use crate::res::texture::{
+    cubemap::equirectangular_to_cube_faces,
+    compressed::{CompressedImage, ImageContainer},
+    gpu_texture::{default_sampler_descriptor, GpuTexture},
+    ibl::{brdf_lut, prefilter_specular, Environment, IblSettings, Sh9},
+    sampler::{max_supported_anisotropy, sampler_descriptor_from_gltf, SamplerCache, SamplerKey},
+    TextureKind,
+};
 
-use super::{buffer::{load_gltf_buffers, to_vec}, camera::Camera, material::Material, mesh::Mesh, model::Model, scene::Scene, storage::Storage,  Handle};
+use super::{animation::Animation, buffer::{load_gltf_buffers, to_vec, BufferData}, image::gltf_texture_image, camera::Camera, error::AssetError, loader::{PreparedGltf, PreparedImage}, manifest::{relative_path, scan_directory, AssetKind, DirectoryFilter, LoadedAsset, Manifest, SceneDescription}, material::{DefaultTextures, Material}, mesh::{Mesh, MeshImportOptions}, model::Model, scene::{NodeLight, Scene}, scin::Skin, storage::Storage, Handle, Resource};
 
 
 #[derive(Debug, Clone)] 
@@ -16,29 +26,181 @@ pub struct AssetManager {
     pub textures: Storage<GpuTexture>,
     pub materials: Storage<Material>,
     pub cameras: Storage<Camera>,
+    pub skins: Storage<Skin>,
+    pub animations: Storage<Animation>,
+    /// Общие сэмплеры текстур, одинаковые настройки дают один сэмплер
+    pub samplers: SamplerCache,
+    /// Строить ли mip-уровни загружаемых текстур
+    pub generate_mipmaps: bool,
+    /// Предел анизотропной фильтрации; [`AssetManager::for_adapter`] берёт
+    /// его из [`max_supported_anisotropy`], [`AssetManager::new`] ставит 1
+    pub max_anisotropy: u16,
+    /// Нормали, копия геометрии на CPU и уровни детализации импортируемых мешей
+    pub mesh_import: MeshImportOptions,
+    default_textures: Option<DefaultTextures>,
+    files: HashMap<PathBuf, CachedFile>,
 }
 
-#[derive(Debug)]
-pub struct LoadAssetError {
-    message: String,
+/// Ресурсы, зарегистрированные при импорте файла glTF. Модели, материалы
+/// и камеры идут в порядке индексов файла.
+#[derive(Debug, Clone)]
+pub struct GltfImport {
+    pub scenes: Vec<Handle<Scene>>,
+    /// Сцена `scene` из файла, иначе первая
+    pub default_scene: Option<Handle<Scene>>,
+    pub models: Vec<Handle<Model>>,
+    /// Материалы файла; последним идёт материал по умолчанию, если он понадобился
+    pub materials: Vec<Handle<Material>>,
+    /// Текстуры, которыми владеет импорт; внешние могут быть общими с другими файлами
+    pub textures: Vec<Handle<GpuTexture>>,
+    pub cameras: Vec<Handle<Camera>>,
+    pub skins: Vec<Handle<Skin>>,
+    pub animations: Vec<Handle<Animation>>,
+    pub lights: Vec<NodeLight>,
 }
 
-impl fmt::Display for LoadAssetError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Asset loading error: {}", self.message)  // Исправлено сообщение
+/// Регистрирует ресурс в хранилище. С путём ресурс потом находится через [`Storage::find`]; с `previous`
+/// подменяет прежний ресурс, сохраняя его handle. Новый или подменённый ресурс записывается в `journal`.
+fn register<T: Resource>(
+    storage: &mut Storage<T>,
+    params: T::LoadParams,
+    path: Option<String>,
+    previous: Option<Handle<T>>,
+    journal: &mut Journal<T>,
+) -> Result<Handle<T>, AssetError> {
+    let handle = match (previous.filter(|handle| storage.contains(Handle::new(handle.key()))), path) {
+        (Some(handle), _) => {
+            if let Some(replaced) = storage.replace(Handle::new(handle.key()), T::load(params)?) {
+                journal.replaced.push((handle.key(), replaced));
+            }
+            return Ok(handle);
+        }
+        (None, Some(path)) => storage.load_with_path(path, params)?,
+        (None, None) => storage.load(params)?,
+    };
+    journal.retained.push(handle.key());
+    Ok(handle)
+}
+
+/// Изменения одного хранилища за время импорта
+struct Journal<T: Resource> {
+    /// Добавленные ресурсы и владения общими ресурсами
+    retained: Vec<T::Key>,
+    /// Прежние версии ресурсов, подменённых при перезагрузке
+    replaced: Vec<(T::Key, T)>,
+}
+
+impl<T: Resource> Default for Journal<T> {
+    fn default() -> Self {
+        Self { retained: Vec::new(), replaced: Vec::new() }
     }
 }
 
-impl Error for LoadAssetError {}
+impl<T: Resource> Journal<T> {
+    fn undo(self, storage: &mut Storage<T>) {
+        for key in self.retained.into_iter().rev() {
+            storage.release(Handle::new(key));
+        }
+        // С конца, чтобы дважды подменённый ресурс получил самую первую версию
+        for (key, resource) in self.replaced.into_iter().rev() {
+            storage.replace(Handle::new(key), resource);
+        }
+    }
+}
 
-impl LoadAssetError {
-    pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
+/// Что импорт glTF успел зарегистрировать или подменить; при ошибке новое
+/// освобождается, подменённое возвращается, и хранилища остаются как до импорта
+#[derive(Default)]
+struct ImportJournal {
+    scenes: Journal<Scene>,
+    models: Journal<Model>,
+    meshes: Journal<Mesh>,
+    materials: Journal<Material>,
+    textures: Journal<GpuTexture>,
+    cameras: Journal<Camera>,
+    skins: Journal<Skin>,
+    animations: Journal<Animation>,
+}
+
+impl ImportJournal {
+    fn undo(self, assets: &mut AssetManager) {
+        self.scenes.undo(&mut assets.scenes);
+        self.models.undo(&mut assets.models);
+        self.meshes.undo(&mut assets.meshes);
+        self.materials.undo(&mut assets.materials);
+        self.textures.undo(&mut assets.textures);
+        self.cameras.undo(&mut assets.cameras);
+        self.skins.undo(&mut assets.skins);
+        self.animations.undo(&mut assets.animations);
+    }
+}
+
+/// Первые байты файла для определения его типа
+fn read_header(path: &Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(16);
+    std::fs::File::open(path)?.take(16).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// Освобождает ресурсы `previous`, которых нет среди `current`
+fn release_missing<T: Resource>(storage: &mut Storage<T>, previous: &[Handle<T>], current: &[Handle<T>]) {
+    for handle in previous {
+        if !current.iter().any(|current| current.key() == handle.key()) {
+            storage.release(Handle::new(handle.key()));
         }
     }
 }
 
+/// Общие данные импорта одного glTF-файла
+struct GltfImportContext<'a> {
+    gltf: &'a Gltf,
+    base_path: Option<&'a Path>,
+    buffers: &'a [BufferData],
+    device: &'a wgpu::Device,
+    queue: &'a wgpu::Queue,
+    /// Изображения, прочитанные заранее; без них читаются при загрузке текстуры
+    images: &'a [PreparedImage],
+    /// Путь файла, от него строятся пути ресурсов (`file.gltf#mesh0`)
+    source: Option<&'a str>,
+    /// Прежний импорт при перезагрузке: его ресурсы подменяются по индексам
+    previous: Option<&'a GltfImport>,
+}
+
+impl GltfImportContext<'_> {
+    fn path(&self, kind: &str, index: usize) -> Option<String> {
+        self.source.map(|source| format!("{}#{}{}", source, kind, index))
+    }
+
+    fn previous<T: Resource>(&self, list: fn(&GltfImport) -> &[Handle<T>], index: usize) -> Option<Handle<T>> {
+        self.previous.and_then(|import| list(import).get(index).map(|handle| Handle::new(handle.key())))
+    }
+}
+
+/// Материалы файла и текстуры, загруженные для них
+type GltfMaterials = (Vec<Handle<Material>>, Vec<Handle<GpuTexture>>);
+
+/// Файл, загруженный через [`AssetManager::load_gltf_file`] или
+/// [`AssetManager::load_texture_file`]
+#[derive(Debug, Clone)]
+struct CachedFile {
+    asset: CachedAsset,
+    /// Файлы, изменение которых требует перезагрузки
+    dependencies: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum CachedAsset {
+    Gltf {
+        import: GltfImport,
+        /// Сколько раз файл загружен и ещё не выгружен
+        users: usize,
+    },
+    /// Временем жизни текстуры управляет счётчик ссылок хранилища
+    Texture(Handle<GpuTexture>),
+}
+
 impl AssetManager {
     pub fn new() -> Self {
         Self {
@@ -48,146 +210,966 @@ impl AssetManager {
             textures: Storage::new(),
             materials: Storage::new(),
             cameras: Storage::new(),
+            skins: Storage::new(),
+            animations: Storage::new(),
+            samplers: SamplerCache::new(),
+            generate_mipmaps: true,
+            max_anisotropy: 1,
+            mesh_import: MeshImportOptions::default(),
+            default_textures: None,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Менеджер с анизотропной фильтрацией до предела адаптера
+    pub fn for_adapter(adapter: &wgpu::Adapter) -> Self {
+        Self {
+            max_anisotropy: max_supported_anisotropy(adapter),
+            ..Self::new()
         }
     }
     
+    /// Загружает файлы каталога, подходящие под фильтр, определяя их тип
+    /// (см. [`AssetKind::detect`]). Ошибки отдельных файлов попадают в
+    /// [`Manifest::errors`], остальные файлы загружаются. Файлы glTF
+    /// загружаются первыми; их буферы и изображения в манифест не попадают.
     pub fn load_from_directory(
         &mut self,
         base_path: &str,
+        filter: &DirectoryFilter,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Manifest, AssetError> {
+        let root = Path::new(base_path);
+        let root = root.canonicalize().map_err(|err| AssetError::io(root, err))?;
+        let mut manifest = Manifest { root: root.clone(), ..Default::default() };
+        let mut files = Vec::new();
+        let mut descriptions = Vec::new();
+
+        let scanned = scan_directory(&root, filter).map_err(|err| AssetError::io(&root, err))?;
+        for (path, err) in scanned.skipped {
+            let relative = relative_path(&root, &path).unwrap_or_else(|| path.display().to_string());
+            manifest.errors.insert(relative, AssetError::io(path, err));
+        }
+        for (relative, path) in scanned.files {
+            let kind = match read_header(&path) {
+                Ok(header) => AssetKind::detect(&path, &header),
+                Err(err) => {
+                    manifest.errors.insert(relative, AssetError::io(path, err));
+                    continue;
+                }
+            };
+            match kind {
+                // Описания сцены загружаются последними, их файлы могут уже быть в манифесте
+                Some(AssetKind::SceneDescription) => descriptions.push((relative, path)),
+                Some(kind) => files.push((relative, path, kind)),
+                None => {}
+            }
+        }
+
+        // Текстуры, на которые ссылается загруженный glTF, уже загружены им
+        files.sort_by_key(|(_, _, kind)| *kind != AssetKind::Gltf);
+        let mut dependencies = Vec::new();
+        for (relative, path, kind) in files {
+            let path = path.canonicalize().unwrap_or(path);
+            if dependencies.contains(&path) {
+                continue;
+            }
+            self.load_manifest_entry(&mut manifest, relative, &path, kind, device, queue);
+            if let Some(cached) = self.files.get(&path) {
+                dependencies.extend(cached.dependencies.iter().filter(|dependency| **dependency != path).cloned());
+            }
+        }
+
+        for (relative, path) in descriptions {
+            let description = match std::fs::read(&path) {
+                Ok(data) => SceneDescription::from_slice(&data).map_err(|err| err.in_file(&path)),
+                Err(err) => Err(AssetError::io(&path, err)),
+            };
+            let description = match description {
+                Ok(description) => description,
+                Err(err) => {
+                    manifest.errors.insert(relative, err);
+                    continue;
+                }
+            };
+
+            let directory = path.parent().unwrap_or(&root);
+            let mut members = Vec::new();
+            for asset in &description.assets {
+                let asset_path = directory.join(asset);
+                let asset_path = asset_path.canonicalize().unwrap_or(asset_path);
+                let member = relative_path(&root, &asset_path).unwrap_or_else(|| asset_path.display().to_string());
+                let loaded = manifest.assets.contains_key(&member)
+                    || manifest.errors.contains_key(&member)
+                    || dependencies.contains(&asset_path);
+                if !loaded {
+                    let kind = read_header(&asset_path).map(|header| AssetKind::detect(&asset_path, &header));
+                    match kind {
+                        Ok(Some(kind)) if kind != AssetKind::SceneDescription => {
+                            self.load_manifest_entry(&mut manifest, member.clone(), &asset_path, kind, device, queue)
+                        }
+                        Ok(_) => {
+                            let err = AssetError::invalid("unknown asset type").in_file(&asset_path);
+                            manifest.errors.insert(member.clone(), err);
+                        }
+                        Err(err) => {
+                            manifest.errors.insert(member.clone(), AssetError::io(&asset_path, err));
+                        }
+                    }
+                }
+                members.push(member);
+            }
+            manifest.assets.insert(relative, LoadedAsset::SceneDescription(members));
+        }
+        Ok(manifest)
+    }
+
+    /// Загружает один файл каталога и записывает результат в манифест
+    fn load_manifest_entry(
+        &mut self,
+        manifest: &mut Manifest,
+        relative: String,
+        path: &Path,
+        kind: AssetKind,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
     ) {
-        
+        let loaded = match kind {
+            AssetKind::Gltf => self.load_gltf_file(path, device, queue).map(LoadedAsset::Gltf),
+            AssetKind::Texture => self.load_texture_file(path, device, queue).map(LoadedAsset::Texture),
+            AssetKind::Environment => image::open(path)
+                .map_err(AssetError::from)
+                .and_then(|image| self.load_environment(device, queue, &image, &IblSettings::default()))
+                .map(LoadedAsset::Environment)
+                .map_err(|err| err.in_file(path)),
+            AssetKind::Shader => std::fs::read_to_string(path)
+                .map(|source| LoadedAsset::Shader {
+                    // Без расширения в любом регистре: `shaders/Light.WGSL` → `shaders/Light`
+                    name: Path::new(&relative).with_extension("").to_string_lossy().into_owned(),
+                    source,
+                })
+                .map_err(|err| AssetError::io(path, err)),
+            AssetKind::SceneDescription => {
+                Err(AssetError::invalid("nested scene descriptions are not supported").in_file(path))
+            }
+        };
+        match loaded {
+            Ok(asset) => {
+                manifest.assets.insert(relative, asset);
+            }
+            Err(err) => {
+                manifest.errors.insert(relative, err);
+            }
+        }
     }
 
-    pub fn load_gltf_meshes<'a>(
+    /// Загружает цветовую текстуру из файла (PNG/JPEG или KTX2/DDS); повторная
+    /// загрузка того же файла возвращает ту же текстуру
+    pub fn load_texture_file(
         &mut self,
-        gltf: &'a Gltf,
-        base_path: &str,
+        path: impl AsRef<Path>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Handle<GpuTexture>, AssetError> {
+        let path = path.as_ref();
+        let path = path.canonicalize().map_err(|err| AssetError::io(path, err))?;
+        let key = path.to_string_lossy().into_owned();
+        if let Some(handle) = self.textures.find(&key) {
+            self.textures.retain(handle.clone());
+            return Ok(handle);
+        }
+
+        let texture = self.read_texture_file(&path, device, queue)?;
+        let handle = self.textures.load_with_path(key, texture)?;
+        let cached = CachedFile {
+            asset: CachedAsset::Texture(handle.clone()),
+            dependencies: vec![path.clone()],
+        };
+        self.files.insert(path, cached);
+        Ok(handle)
+    }
+
+    /// Перечитывает файл, загруженный [`Self::load_texture_file`], и подменяет
+    /// текстуру под тем же handle'ом. При ошибке остаётся прежняя версия.
+    pub fn reload_texture_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Handle<GpuTexture>, AssetError> {
+        let path = path.as_ref();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let handle = match self.files.get(&path) {
+            Some(CachedFile { asset: CachedAsset::Texture(handle), .. }) if self.textures.contains(handle.clone()) => {
+                handle.clone()
+            }
+            Some(CachedFile { asset: CachedAsset::Texture(_), .. }) => {
+                // Все владельцы уже освободили текстуру
+                self.files.remove(&path);
+                return Err(AssetError::NotLoaded(path));
+            }
+            _ => return Err(AssetError::NotLoaded(path)),
+        };
+        let texture = self.read_texture_file(&path, device, queue)?;
+        self.textures.replace(handle.clone(), texture);
+        Ok(handle)
+    }
+
+    /// Читает цветовую текстуру из файла со сэмплером по умолчанию
+    fn read_texture_file(
+        &mut self,
+        path: &Path,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        )-> Result<Vec<Handle<Mesh>>,  Box<dyn std::error::Error>> {
-        let base_path: Option<&Path> = Some(Path::new(&base_path));
-        let buffers = match load_gltf_buffers(gltf, base_path) {
-            Ok(buffers) => buffers,
-            Err(_) => return Err(Box::new(LoadAssetError::new("Load buffers error"))),
+    ) -> Result<GpuTexture, AssetError> {
+        let data = std::fs::read(path).map_err(|err| AssetError::io(path, err))?;
+        let label = path.file_name().and_then(|name| name.to_str());
+        let sampler = self.samplers.get_or_create(device, &default_sampler_descriptor());
+        let texture = match ImageContainer::detect(&data) {
+            ImageContainer::Other => GpuTexture::from_image_with_sampler(
+                device,
+                queue,
+                &image::load_from_memory(&data).map_err(|err| AssetError::from(err).in_file(path))?,
+                TextureKind::Color,
+                sampler,
+                self.generate_mipmaps,
+                label,
+            )?,
+            ImageContainer::Ktx2 | ImageContainer::Dds => CompressedImage::from_bytes(&data, TextureKind::Color)
+                .and_then(|image| GpuTexture::from_compressed(device, queue, &image, sampler, label))
+                .map_err(|err| AssetError::from(err).in_file(path))?,
         };
+        Ok(texture)
+    }
+
+    /// Загружает небо из равнопромежуточной панорамы и предрассчитывает
+    /// для него освещение (SH9, зеркальная карта, BRDF LUT)
+    pub fn load_environment(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::DynamicImage,
+        settings: &IblSettings,
+    ) -> Result<Environment, AssetError> {
+        settings.validate()?;
+        let skybox = GpuTexture::from_equirectangular(device, queue, image, settings.skybox_size, "skybox");
 
+        let source_size = settings.specular_size;
+        let faces = equirectangular_to_cube_faces(&image.to_rgb32f(), source_size);
+        let irradiance = Sh9::from_cube_faces(&faces, source_size);
+        let mip_count = settings.specular_mip_count.clamp(1, source_size.ilog2() + 1);
+        let mips = prefilter_specular(&faces, source_size, mip_count, settings.specular_sample_count);
+        let specular = GpuTexture::from_hdr_cube_mips(device, queue, source_size, &mips, "environment_specular");
 
-        let mut material_handles: Vec<Handle<Material>> = Vec::new();
-        for material in gltf.materials() { 
-            let pbr = material.pbr_metallic_roughness();
-            
-            let image_data = match load_gltf_texture_source_data(base_path, pbr, &buffers){
-                Ok(data) => {data},
-                Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture source error")))},
+        let lut = brdf_lut(settings.brdf_lut_size, settings.brdf_sample_count);
+        let lut = GpuTexture::from_rg_f32(device, queue, settings.brdf_lut_size, &lut, "brdf_lut");
+
+        Ok(Environment {
+            skybox: self.textures.load(skybox)?,
+            specular: self.textures.load(specular)?,
+            brdf_lut: self.textures.load(lut)?,
+            irradiance,
+            specular_mip_count: mip_count,
+            intensity: settings.intensity,
+        })
+    }
+
+    /// Текстуры-заглушки для материалов, создаются при первом запросе
+    pub fn default_textures(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<DefaultTextures, AssetError> {
+        if let Some(defaults) = &self.default_textures {
+            return Ok(defaults.clone());
+        }
+        let defaults = DefaultTextures::new(device, queue, &mut self.textures)?;
+        self.default_textures = Some(defaults.clone());
+        Ok(defaults)
+    }
+
+    /// Загружает текстуру glTF (PNG/JPEG или KTX2/DDS) с сэмплером из файла
+    fn load_gltf_texture(
+        &mut self,
+        context: &GltfImportContext<'_>,
+        texture: &gltf::Texture<'_>,
+        kind: TextureKind,
+        journal: &mut Journal<GpuTexture>,
+    ) -> Result<Handle<GpuTexture>, AssetError> {
+        let mut image = gltf_texture_image(context.gltf, texture);
+        let mut loaded = None;
+        if image.index() != texture.source().index() {
+            // Basis Universal без транскодера и формат, который адаптер не
+            // поддерживает, а CPU не распаковывает, не загрузить: берём обычный источник
+            let features = context.device.features();
+            let loadable = match context.images.get(image.index()) {
+                Some(prepared) => prepared.is_loadable_on(features),
+                None => {
+                    let prepared = PreparedImage::load(context.base_path, context.buffers, image.clone())?;
+                    let loadable = prepared.is_loadable_on(features);
+                    loaded = Some(prepared);
+                    loadable
+                }
             };
+            if !loadable {
+                image = texture.source();
+                loaded = None;
+            }
+        }
+        let image_index = image.index();
+        let sampler = sampler_descriptor_from_gltf(&texture.sampler(), self.max_anisotropy);
 
-            match  GpuTexture::from_bytes(device, queue, &image_data, "lable") {
-                Ok(texture) => {
-                    match self.textures.load(texture){
-                        Ok(t_h) => {
-                            match self.materials.load(Material::from_gltf_texture(&material, t_h)){
-                                Ok(h_m) => {material_handles.push(h_m)},
-                                Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture source error")))},
-                            }
-                        },
-                        Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture source error")))},
-                    };
-                },
-                Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture error")))},
-            }
-        }
-       
-        let mut mesh_handles = Vec::new();
+        // Внешние изображения общие для всех файлов: ключ из пути, назначения и сэмплера
+        let cache_path = match (image.source(), context.base_path) {
+            (gltf::image::Source::Uri { uri, .. }, Some(base_path)) => {
+                let path = base_path.join(uri);
+                let path = path.canonicalize().unwrap_or(path);
+                let mut hasher = DefaultHasher::new();
+                SamplerKey::from_descriptor(&sampler).hash(&mut hasher);
+                Some(format!("{}#{:?}#{:x}", path.display(), kind, hasher.finish()))
+            }
+            _ => None,
+        };
+        // При перезагрузке общая текстура читается заново и подменяется у всех владельцев
+        let cached = cache_path.as_deref().and_then(|path| self.textures.find(path));
+        if let (Some(handle), None) = (&cached, context.previous) {
+            self.textures.retain(handle.clone());
+            journal.retained.push(handle.key());
+            return Ok(handle.clone());
+        }
+
+        let prepared = match (context.images.get(image_index), &mut loaded) {
+            (Some(prepared), _) => prepared,
+            (None, Some(prepared)) => prepared,
+            (None, loaded) => loaded.insert(PreparedImage::load(context.base_path, context.buffers, image)?),
+        };
+
+        let sampler = self.samplers.get_or_create(context.device, &sampler);
+        let (device, queue) = (context.device, context.queue);
+
+        let texture = match prepared {
+            PreparedImage::Decoded(image) => GpuTexture::from_image_with_sampler(
+                device,
+                queue,
+                image,
+                kind,
+                sampler,
+                self.generate_mipmaps,
+                texture.name(),
+            )?,
+            PreparedImage::Compressed(image_data) => CompressedImage::from_bytes(&image_data.data, kind)
+                .and_then(|image| GpuTexture::from_compressed(device, queue, &image, sampler, texture.name()))
+                .map_err(|err| AssetError::Image {
+                    index: image_index,
+                    source: Box::new(err.into()),
+                })?,
+        };
+
+        let handle = match (cached, cache_path) {
+            (Some(handle), _) => {
+                if let Some(replaced) = self.textures.replace(handle.clone(), texture) {
+                    journal.replaced.push((handle.key(), replaced));
+                }
+                self.textures.retain(handle.clone());
+                handle
+            }
+            (None, Some(path)) => self.textures.load_with_path(path, texture)?,
+            (None, None) => self.textures.load(texture)?,
+        };
+        journal.retained.push(handle.key());
+        Ok(handle)
+    }
+
+    /// Загружает материалы glTF; текстура с одним назначением загружается один раз.
+    /// Вместе с материалами возвращает текстуры, которыми владеет импорт.
+    fn load_gltf_materials(
+        &mut self,
+        context: &GltfImportContext<'_>,
+        defaults: &DefaultTextures,
+        journal: &mut ImportJournal,
+    ) -> Result<GltfMaterials, AssetError> {
+        let mut loaded_textures: HashMap<(usize, TextureKind), Handle<GpuTexture>> = HashMap::new();
+        let mut material_handles = Vec::new();
+        for material in context.gltf.materials() {
+            let index = material.index().unwrap_or_default();
+            let material = Material::from_gltf(&material, defaults, |texture, kind| {
+                if let Some(handle) = loaded_textures.get(&(texture.index(), kind)) {
+                    return Ok(handle.clone());
+                }
+                let handle = self.load_gltf_texture(context, &texture, kind, &mut journal.textures)?;
+                loaded_textures.insert((texture.index(), kind), handle.clone());
+                Ok(handle)
+            })
+            .map_err(|err| AssetError::Material { index, source: Box::new(err) })?;
+            let path = context.path("material", index);
+            let previous = context.previous(|import| &import.materials, index);
+            material_handles.push(register(&mut self.materials, material, path, previous, &mut journal.materials)?);
+        }
+        Ok((material_handles, loaded_textures.into_values().collect()))
+    }
+
+    /// Импортирует файл glTF целиком: материалы, по модели на каждый меш,
+    /// камеры и все сцены с деревом узлов. Повторный вызов создаёт копии
+    /// ресурсов, кроме внешних текстур; для кэша по пути см. [`Self::load_gltf_file`].
+    pub fn load_gltf(
+        &mut self,
+        gltf: &Gltf,
+        base_path: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<GltfImport, AssetError> {
+        let base_path = Path::new(base_path);
+        let buffers = load_gltf_buffers(gltf, Some(base_path))?;
+        self.import_gltf(&GltfImportContext {
+            gltf,
+            base_path: Some(base_path),
+            buffers: &buffers,
+            images: &[],
+            device,
+            queue,
+            source: None,
+            previous: None,
+        })
+    }
+
+    /// Загружает файл glTF/GLB один раз: повторная загрузка того же пути
+    /// возвращает уже зарегистрированные ресурсы. Каждой загрузке должен
+    /// соответствовать вызов [`Self::unload`].
+    pub fn load_gltf_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<GltfImport, AssetError> {
+        let path = path.as_ref();
+        let path = path.canonicalize().map_err(|err| AssetError::io(path, err))?;
+        if let Some(CachedFile { asset: CachedAsset::Gltf { import, users }, .. }) = self.files.get_mut(&path) {
+            *users += 1;
+            return Ok(import.clone());
+        }
+        let prepared = PreparedGltf::read(&path)?;
+        self.load_prepared_gltf(prepared, device, queue)
+    }
+
+    /// Загружает на GPU файл, прочитанный заранее (например, в
+    /// [`AssetLoader`](super::loader::AssetLoader)). Кэшируется так же, как
+    /// [`Self::load_gltf_file`].
+    pub fn load_prepared_gltf(
+        &mut self,
+        prepared: PreparedGltf,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<GltfImport, AssetError> {
+        if let Some(CachedFile { asset: CachedAsset::Gltf { import, users }, .. }) = self.files.get_mut(&prepared.path) {
+            *users += 1;
+            return Ok(import.clone());
+        }
+        let source = prepared.path.to_string_lossy();
+        let import = self
+            .import_gltf(&GltfImportContext {
+                gltf: &prepared.gltf,
+                base_path: prepared.path.parent(),
+                buffers: &prepared.buffers,
+                images: &prepared.images,
+                device,
+                queue,
+                source: Some(&source),
+                previous: None,
+            })
+            .map_err(|err| err.in_file(&prepared.path))?;
+        let cached = CachedFile {
+            asset: CachedAsset::Gltf { import: import.clone(), users: 1 },
+            dependencies: prepared.dependencies(),
+        };
+        self.files.insert(prepared.path.clone(), cached);
+        Ok(import)
+    }
+
+    /// Выгружает файл, загруженный [`Self::load_gltf_file`]. Ресурсы удаляются,
+    /// когда выгружена последняя загрузка; `false`, если файл не загружен.
+    pub fn unload(&mut self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let Some(CachedFile { asset: CachedAsset::Gltf { import, users }, .. }) = self.files.get_mut(&path) else {
+            return false;
+        };
+        *users -= 1;
+        if *users == 0 {
+            let import = import.clone();
+            self.files.remove(&path);
+            self.release_gltf(&import);
+        }
+        true
+    }
+
+    /// Перечитывает файл, загруженный [`Self::load_gltf_file`]. Ресурсы
+    /// подменяются по индексам файла, так что прежние handle'ы сцен, моделей,
+    /// мешей, материалов, камер, скелетов и анимаций остаются в силе.
+    /// Внешние текстуры подменяются у всех файлов, встроенные создаются заново.
+    /// Если файл не читается или не импортируется, все подменённые ресурсы
+    /// возвращаются и остаётся прежняя версия целиком.
+    pub fn reload_gltf_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<GltfImport, AssetError> {
+        let path = path.as_ref();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let previous = match self.files.get(&path) {
+            Some(CachedFile { asset: CachedAsset::Gltf { import, .. }, .. }) => import.clone(),
+            _ => return Err(AssetError::NotLoaded(path)),
+        };
+        let prepared = PreparedGltf::read(&path)?;
+        let previous_meshes: Vec<Handle<Mesh>> = previous
+            .models
+            .iter()
+            .filter_map(|model| self.models.get(model.clone()))
+            .flat_map(|model| model.meshes.clone())
+            .collect();
+
+        let source = prepared.path.to_string_lossy();
+        let import = self
+            .import_gltf(&GltfImportContext {
+                gltf: &prepared.gltf,
+                base_path: prepared.path.parent(),
+                buffers: &prepared.buffers,
+                images: &prepared.images,
+                device,
+                queue,
+                source: Some(&source),
+                previous: Some(&previous),
+            })
+            .map_err(|err| err.in_file(&path))?;
+
+        // Ресурсы, которых больше нет в файле, удаляются
+        let meshes: Vec<Handle<Mesh>> = import
+            .models
+            .iter()
+            .filter_map(|model| self.models.get(model.clone()))
+            .flat_map(|model| model.meshes.clone())
+            .collect();
+        release_missing(&mut self.meshes, &previous_meshes, &meshes);
+        release_missing(&mut self.scenes, &previous.scenes, &import.scenes);
+        release_missing(&mut self.models, &previous.models, &import.models);
+        release_missing(&mut self.materials, &previous.materials, &import.materials);
+        release_missing(&mut self.cameras, &previous.cameras, &import.cameras);
+        release_missing(&mut self.skins, &previous.skins, &import.skins);
+        release_missing(&mut self.animations, &previous.animations, &import.animations);
+        for texture in &previous.textures {
+            self.textures.release(texture.clone());
+        }
+
+        if let Some(cached) = self.files.get_mut(&path) {
+            if let CachedAsset::Gltf { import: cached_import, .. } = &mut cached.asset {
+                *cached_import = import.clone();
+            }
+            cached.dependencies = prepared.dependencies();
+        }
+        Ok(import)
+    }
+
+    /// Перечитывает файл, загруженный [`Self::load_gltf_file`] или
+    /// [`Self::load_texture_file`], см. [`Self::reload_gltf_file`] и
+    /// [`Self::reload_texture_file`]
+    pub fn reload_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<(), AssetError> {
+        let path = path.as_ref();
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        match self.files.get(&path).map(|cached| &cached.asset) {
+            Some(CachedAsset::Texture(_)) => self.reload_texture_file(&path, device, queue).map(drop),
+            _ => self.reload_gltf_file(&path, device, queue).map(drop),
+        }
+    }
+
+    /// Загруженные через [`Self::load_gltf_file`] и [`Self::load_texture_file`]
+    /// файлы, которые зависят от `path`
+    pub fn files_using(&self, path: &Path) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, cached)| cached.dependencies.iter().any(|dependency| dependency == path))
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
+
+    /// Все файлы, от которых зависят загруженные glTF и текстуры
+    pub fn watched_paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.values().flat_map(|cached| cached.dependencies.iter().map(PathBuf::as_path))
+    }
+
+    /// Освобождает ресурсы импорта; общие текстуры остаются, пока ими владеют
+    /// другие импорты
+    pub fn release_gltf(&mut self, import: &GltfImport) {
+        for scene in &import.scenes {
+            self.scenes.release(scene.clone());
+        }
+        for model in &import.models {
+            if let Some(model) = self.models.release(model.clone()) {
+                for mesh in model.meshes {
+                    self.meshes.release(mesh);
+                }
+            }
+        }
+        for material in &import.materials {
+            self.materials.release(material.clone());
+        }
+        for texture in &import.textures {
+            self.textures.release(texture.clone());
+        }
+        for camera in &import.cameras {
+            self.cameras.release(camera.clone());
+        }
+        for skin in &import.skins {
+            self.skins.release(skin.clone());
+        }
+        for animation in &import.animations {
+            self.animations.release(animation.clone());
+        }
+    }
+
+    /// Импортирует файл; при ошибке освобождает всё, что успел зарегистрировать,
+    /// и возвращает ресурсы, подменённые при перезагрузке
+    fn import_gltf(&mut self, context: &GltfImportContext<'_>) -> Result<GltfImport, AssetError> {
+        let mut journal = ImportJournal::default();
+        let import = self.import_gltf_resources(context, &mut journal);
+        if import.is_err() {
+            journal.undo(self);
+        }
+        import
+    }
+
+    fn import_gltf_resources(
+        &mut self,
+        context: &GltfImportContext<'_>,
+        journal: &mut ImportJournal,
+    ) -> Result<GltfImport, AssetError> {
+        let (gltf, device, queue) = (context.gltf, context.device, context.queue);
+        let defaults = self.default_textures(device, queue)?;
+        let (mut materials, textures) = self.load_gltf_materials(context, &defaults, journal)?;
+
+        let buffer_data = to_vec(context.buffers.to_vec());
+        let mut default_material: Option<Handle<Material>> = None;
+        let mut models = Vec::new();
         for mesh in gltf.meshes() {
+            let previous_model = context.previous(|import| &import.models, mesh.index());
+            let previous_meshes = previous_model
+                .clone()
+                .and_then(|model| self.models.get(model))
+                .map(|model| model.meshes.clone())
+                .unwrap_or_default();
+            let mut meshes = Vec::new();
             for primitive in mesh.primitives() {
-                let mesh = Mesh::from_gltf_primitive(
+                // Примитив без материала получает материал glTF по умолчанию
+                let material = match primitive.material().index() {
+                    Some(index) => materials.get(index).cloned(),
+                    None => match &default_material {
+                        Some(handle) => Some(handle.clone()),
+                        None => {
+                            let material = Material::default_with_textures(&defaults);
+                            let path = context.path("default_material", 0);
+                            let previous = context.previous(|import| &import.materials, gltf.materials().len());
+                            let handle = register(&mut self.materials, material, path, previous, &mut journal.materials)?;
+                            default_material = Some(handle.clone());
+                            Some(handle)
+                        }
+                    },
+                };
+                let primitive_error = |err| AssetError::Primitive {
+                    mesh: mesh.index(),
+                    primitive: primitive.index(),
+                    source: Box::new(err),
+                };
+                let loaded = Mesh::from_gltf_primitive(
                     &primitive,
-                    &to_vec(buffers.clone()),
-                    material_handles.get(primitive.material().index().unwrap_or(0)).cloned(),
+                    &buffer_data,
+                    material,
+                    mesh.weights(),
+                    &self.mesh_import,
                     device.clone(),
-                )?;
-                match self.meshes.load(mesh) {
-                    Ok(m) => {mesh_handles.push(m);},
-                    Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture error")))},
-                }
+                )
+                .map_err(primitive_error)?;
+                let path = context.path(&format!("mesh{}/primitive", mesh.index()), primitive.index());
+                let previous = previous_meshes.get(primitive.index()).cloned();
+                meshes.push(register(&mut self.meshes, loaded, path, previous, &mut journal.meshes)?);
             }
+            let model = Model { meshes, animations: None };
+            let path = context.path("mesh", mesh.index());
+            models.push(register(&mut self.models, model, path, previous_model, &mut journal.models)?);
         }
 
-        Ok(mesh_handles)
+        let cameras = gltf
+            .cameras()
+            .map(|camera| {
+                let path = context.path("camera", camera.index());
+                let previous = context.previous(|import| &import.cameras, camera.index());
+                register(&mut self.cameras, Camera::from_gltf(&camera), path, previous, &mut journal.cameras)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut skins = Vec::new();
+        for skin in gltf.skins() {
+            let loaded = Skin::from_gltf(&skin, &buffer_data)
+                .map_err(|err| AssetError::Skin { index: skin.index(), source: Box::new(err) })?;
+            let path = context.path("skin", skin.index());
+            let previous = context.previous(|import| &import.skins, skin.index());
+            skins.push(register(&mut self.skins, loaded, path, previous, &mut journal.skins)?);
+        }
+        let mut animations = Vec::new();
+        for animation in gltf.animations() {
+            let loaded = Animation::from_gltf(&animation, &buffer_data).map_err(|err| AssetError::Animation {
+                index: animation.index(),
+                source: Box::new(err),
+            })?;
+            let path = context.path("animation", animation.index());
+            let previous = context.previous(|import| &import.animations, animation.index());
+            animations.push(register(&mut self.animations, loaded, path, previous, &mut journal.animations)?);
+        }
+        let lights = NodeLight::from_gltf(gltf);
+
+        let mut scenes = Vec::new();
+        for scene in gltf.scenes() {
+            let path = context.path("scene", scene.index());
+            let previous = context.previous(|import| &import.scenes, scene.index());
+            let scene = Scene::from_gltf(&scene, &models, &cameras, &skins, &lights)?;
+            scenes.push(register(&mut self.scenes, scene, path, previous, &mut journal.scenes)?);
+        }
+        let default_scene = gltf
+            .default_scene()
+            .and_then(|scene| scenes.get(scene.index()).cloned())
+            .or_else(|| scenes.first().cloned());
+        materials.extend(default_material);
+
+        Ok(GltfImport {
+            scenes,
+            default_scene,
+            models,
+            materials,
+            textures,
+            cameras,
+            skins,
+            animations,
+            lights,
+        })
     }
 
-    // pub fn load_gltf_data<'a>(
-    //     mut self,
-    //     gltf: &'a Gltf,
-    //     file_name: &str,
-    //     device: &wgpu::Device,
-    //     queue: &wgpu::Queue,
-    //     )-> Result<Vec<Handle<Scene>>,  Box<dyn std::error::Error>> {
-    //     let base_path: Option<&Path> = Some(Path::new(&file_name));
-    //     let (buffers, images) = load_gltf_file_data(gltf, base_path).unwrap();
-
-
-    //     let mut material_handles: Vec<Handle<Material>> = Vec::new();
-    //     for material in gltf.materials() { 
-    //         let pbr = material.pbr_metallic_roughness();
-            
-    //         let image_data = match load_gltf_texture_source_data(base_path, pbr, &buffers){
-    //             Ok(data) => {data},
-    //             Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture source error")))},
-    //         };
-
-    //         match  GpuTexture::from_bytes(device, queue, &image_data, "lable") {
-    //             Ok(texture) => {
-    //                 match self.textures.load(texture){
-    //                     Ok(t_h) => {
-    //                         match self.materials.load(Material::from_gltf_texture(&material, t_h)){
-    //                             Ok(h) => {material_handles.push(h)},
-    //                             Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture source error")))},
-    //                         }
-    //                     },
-    //                     Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture source error")))},
-    //                 };
-    //             },
-    //             Err(_) => {return Err(Box::new(LoadAssetError::new("Load texture error")))},
-    //         }
-    //     }
-
-    //     let scene_handles = Vec::new();
-    //     for scene in gltf.scenes() {
-    //         let scene = load_scene_meshes(scene, buffers.clone(), material_handles.clone());
-    //         for nodes in scene {
-    //             for node in nodes {
-    //                 for mesh in node {  
-    //                     self.meshes.load(mesh);
-    //                 }
-    //             }
-    //         }
-    //     }
-
-    //     Ok(scene_handles)
-    // }
-
-
- 
-    
-    // fn cached_load<T: Resource>(
-    //     &mut self,
-    //     cache: &mut HashMap<String, Handle<T>>,
-    //     path: &str,
-    //     params: T::LoadParams,
-    // ) -> Result<Handle<T>, Box<dyn std::error::Error>> {
-    //     if let Some(handle) = cache.get(path) {
-    //         return Ok(*handle);
-    //     }
-    //     let handle = T::load(params)?;
-    //     cache.insert(path.to_string(), handle);
-    //     Ok(handle)
-    // }
+    /// Загружает все примитивы файла плоским списком мешей, см. [`Self::load_gltf`]
+    pub fn load_gltf_meshes(
+        &mut self,
+        gltf: &Gltf,
+        base_path: &str,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        )-> Result<Vec<Handle<Mesh>>, AssetError> {
+        let import = self.load_gltf(gltf, base_path, device, queue)?;
+        let mut mesh_handles = Vec::new();
+        for model in import.models {
+            if let Some(model) = self.models.get(model) {
+                mesh_handles.extend(model.meshes.iter().cloned());
+            }
+        }
+        Ok(mesh_handles)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-}
\ No newline at end of file
+    use crate::res::{camera::Projection, hot_reload::reload_changed, test::test_device};
+
+    fn camera(name: &str) -> Camera {
+        Camera {
+            name: name.to_string(),
+            projection: Projection::Orthographic { xmag: 1.0, ymag: 1.0, znear: 0.1, zfar: 10.0 },
+        }
+    }
+
+    #[test]
+    fn test_import_journal_undo() {
+        let mut assets = AssetManager::new();
+        let kept = assets.cameras.load_with_path("kept", camera("kept")).unwrap();
+        let mut journal = ImportJournal::default();
+
+        let added = register(&mut assets.cameras, camera("added"), Some("added".into()), None, &mut journal.cameras).unwrap();
+        assets.cameras.retain(kept.clone());
+        journal.cameras.retained.push(kept.key());
+        // Дважды подменённый ресурс возвращается к самой первой версии
+        register(&mut assets.cameras, camera("first"), None, Some(kept.clone()), &mut journal.cameras).unwrap();
+        register(&mut assets.cameras, camera("second"), None, Some(kept.clone()), &mut journal.cameras).unwrap();
+        assert_eq!(assets.cameras.len(), 2);
+        assert_eq!(assets.cameras.ref_count(kept.clone()), 2);
+
+        journal.undo(&mut assets);
+        assert!(!assets.cameras.contains(added));
+        assert!(assets.cameras.find("added").is_none());
+        assert_eq!(assets.cameras.len(), 1);
+        assert_eq!(assets.cameras.ref_count(kept.clone()), 1);
+        assert_eq!(assets.cameras.get(kept).unwrap().name, "kept");
+    }
+
+    /// Пишет `name.gltf` с одним треугольником и текстурой `base.png`;
+    /// третий индекс `last_index` вне диапазона ломает импорт меша
+    fn write_triangle(dir: &Path, name: &str, last_index: u32) -> PathBuf {
+        let positions = [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        let mut bin = bytemuck::cast_slice::<_, u8>(&positions).to_vec();
+        bin.extend_from_slice(bytemuck::cast_slice(&[0u32, 1, last_index]));
+        std::fs::write(dir.join(format!("{}.bin", name)), bin).unwrap();
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255])).save(dir.join("base.png")).unwrap();
+
+        let gltf = format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "buffers": [{{"uri": "{name}.bin", "byteLength": 48}}],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+                    {{"buffer": 0, "byteOffset": 36, "byteLength": 12}}
+                ],
+                "accessors": [
+                    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]}},
+                    {{"bufferView": 1, "componentType": 5125, "count": 3, "type": "SCALAR"}}
+                ],
+                "images": [{{"uri": "base.png"}}],
+                "textures": [{{"source": 0}}],
+                "materials": [{{"pbrMetallicRoughness": {{"baseColorTexture": {{"index": 0}}}}}}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1, "material": 0}}]}}],
+                "nodes": [{{"mesh": 0}}],
+                "scenes": [{{"nodes": [0]}}]
+            }}"#
+        );
+        let path = dir.join(format!("{}.gltf", name));
+        std::fs::write(&path, gltf).unwrap();
+        path
+    }
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn test_failed_import_releases_resources() {
+        let (device, queue) = test_device();
+        let dir = tempfile::tempdir().unwrap();
+        let good = write_triangle(dir.path(), "good", 2);
+        let broken = write_triangle(dir.path(), "broken", 5);
+
+        let mut assets = AssetManager::new();
+        assets.default_textures(&device, &queue).unwrap();
+        let default_textures = assets.textures.len();
+        assert!(assets.load_gltf_file(&broken, &device, &queue).is_err());
+        assert_eq!(assets.textures.len(), default_textures);
+        assert!(assets.materials.is_empty());
+        assert!(assets.meshes.is_empty());
+
+        // Общая текстура другого файла теряет только владение неудачного импорта
+        let import = assets.load_gltf_file(&good, &device, &queue).unwrap();
+        let texture = import.textures[0].clone();
+        assert!(assets.load_gltf_file(&broken, &device, &queue).is_err());
+        assert_eq!(assets.textures.ref_count(texture), 1);
+        assert_eq!(assets.materials.len(), 1);
+        assert_eq!(assets.meshes.len(), 1);
+    }
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn test_load_from_directory() {
+        let (device, queue) = test_device();
+        let dir = tempfile::tempdir().unwrap();
+        write_triangle(dir.path(), "triangle", 2);
+        image::RgbaImage::new(1, 1).save(dir.path().join("albedo.png")).unwrap();
+        std::fs::create_dir(dir.path().join("shaders")).unwrap();
+        std::fs::write(dir.path().join("shaders/Light.WGSL"), "fn light() {}").unwrap();
+        std::fs::write(dir.path().join("broken.scene.json"), "{}").unwrap();
+
+        let mut assets = AssetManager::new();
+        let manifest = assets
+            .load_from_directory(dir.path().to_str().unwrap(), &DirectoryFilter::new(), &device, &queue)
+            .unwrap();
+        let errors: Vec<_> = manifest.errors.keys().map(String::as_str).collect();
+        assert_eq!(errors, ["broken.scene.json"]);
+        assert!(matches!(manifest.errors["broken.scene.json"].root_cause(), AssetError::InvalidData(_)));
+        let loaded: Vec<_> = manifest.assets.keys().map(String::as_str).collect();
+        // base.png и triangle.bin принадлежат triangle.gltf
+        assert_eq!(loaded, ["albedo.png", "shaders/Light.WGSL", "triangle.gltf"]);
+        assert!(matches!(
+            manifest.get("shaders/Light.WGSL"),
+            Some(LoadedAsset::Shader { name, .. }) if name == "shaders/Light"
+        ));
+        assert_eq!(assets.textures.ref_count(manifest.gltf("triangle.gltf").unwrap().textures[0].clone()), 1);
+    }
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn test_reload_gltf_file() {
+        let (device, queue) = test_device();
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_triangle(dir.path(), "triangle", 2);
+
+        let mut assets = AssetManager::new();
+        let import = assets.load_gltf_file(&path, &device, &queue).unwrap();
+        let mesh = |assets: &AssetManager| assets.models.get(import.models[0].clone()).unwrap().meshes[0].key();
+        let first_mesh = mesh(&assets);
+        let vertex_buffer = |assets: &AssetManager| assets.meshes.get(Handle::new(first_mesh)).unwrap().vertex_buffer.clone();
+        let first_buffer = vertex_buffer(&assets);
+        let texture = |assets: &AssetManager| assets.textures.get(import.textures[0].clone()).unwrap().texture.clone();
+        let first_texture = texture(&assets);
+        let textures = assets.textures.len();
+
+        // Сломанный файл не трогает прежнюю версию
+        write_triangle(dir.path(), "triangle", 5);
+        assert!(assets.reload_gltf_file(&path, &device, &queue).is_err());
+        assert_eq!(mesh(&assets), first_mesh);
+        assert_eq!(vertex_buffer(&assets), first_buffer);
+        // Текстура и материал подменяются раньше меша и должны вернуться
+        assert_eq!(texture(&assets), first_texture);
+        assert_eq!(assets.textures.len(), textures);
+        assert_eq!(assets.textures.ref_count(import.textures[0].clone()), 1);
+        assert!(assets.materials.contains(import.materials[0].clone()));
+
+        // Исправленный подменяется под теми же handle'ами и без лишних текстур,
+        // а версия материала сообщает рендереру о перезагрузке
+        write_triangle(dir.path(), "triangle", 2);
+        let material_version = assets.materials.version(import.materials[0].clone());
+        let reloaded = assets.reload_gltf_file(&path, &device, &queue).unwrap();
+        assert!(assets.materials.version(import.materials[0].clone()) > material_version);
+        assert_eq!(reloaded.scenes[0].key(), import.scenes[0].key());
+        assert_eq!(reloaded.materials[0].key(), import.materials[0].key());
+        assert_eq!(mesh(&assets), first_mesh);
+        assert_ne!(vertex_buffer(&assets), first_buffer);
+        assert_eq!(assets.textures.len(), textures);
+    }
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn test_reload_texture_file() {
+        let (device, queue) = test_device();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("albedo.png");
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255])).save(&path).unwrap();
+
+        let mut assets = AssetManager::new();
+        let handle = assets.load_texture_file(&path, &device, &queue).unwrap();
+        let path = path.canonicalize().unwrap();
+        assert!(assets.watched_paths().any(|watched| watched == path));
+        let texture = |assets: &AssetManager| assets.textures.get(handle.clone()).unwrap().texture.clone();
+        let first_texture = texture(&assets);
+
+        // Сломанный файл не трогает прежнюю версию
+        std::fs::write(&path, b"not an image").unwrap();
+        assert!(reload_changed(&mut assets, &[path.clone()], &device, &queue).is_empty());
+        assert_eq!(texture(&assets), first_texture);
+
+        // Исправленный подменяется под тем же handle'ом, а версия сообщает
+        // кэшу материалов о перезагрузке
+        image::RgbaImage::from_pixel(4, 4, image::Rgba([0, 255, 0, 255])).save(&path).unwrap();
+        let version = assets.textures.version(handle.clone());
+        assert_eq!(reload_changed(&mut assets, &[path.clone()], &device, &queue), [path.clone()]);
+        assert!(assets.textures.version(handle.clone()) > version);
+        assert_ne!(texture(&assets), first_texture);
+        assert_eq!(assets.textures.get(handle.clone()).unwrap().texture.width(), 4);
+        assert_eq!(assets.textures.len(), 1);
+
+        // Освобождённая текстура больше не перезагружается
+        assets.textures.release(handle);
+        assert!(reload_changed(&mut assets, &[path], &device, &queue).is_empty());
+    }
+}
@@ -1,46 +1,14 @@
-use std::{error::Error, fmt, path::Path};
+use std::path::Path;
 use gltf::{buffer::Source, Gltf};
 
+use super::error::AssetError;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct BufferData {
     pub data: Vec<u8>,
     pub uri: Option<String>,
 }
 
-#[derive(Debug)]
-pub struct LoadBufferDataError {
-    message: String,
-    source: Option<Box<dyn Error + Send + Sync>>,
-}
-
-impl fmt::Display for LoadBufferDataError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Buffer loading error: {}", self.message)
-    }
-}
-
-impl Error for LoadBufferDataError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        self.source.as_ref().map(|e| &**e as _)
-    }
-}
-
-impl LoadBufferDataError {
-    pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            source: None,
-        }
-    }
-
-    pub fn with_source(message: impl Into<String>, source: impl Error + Send + Sync + 'static) -> Self {
-        Self {
-            message: message.into(),
-            source: Some(Box::new(source)),
-        }
-    }
-}
-
 /// Загружает все буферы из GLTF файла
 ///
 /// # Аргументы
@@ -55,7 +23,7 @@ impl LoadBufferDataError {
 pub fn load_gltf_buffers(
     gltf: &Gltf,
     base_path: Option<&Path>,
-) -> Result<Vec<BufferData>, LoadBufferDataError> {
+) -> Result<Vec<BufferData>, AssetError> {
     gltf.buffers()
         .enumerate()
         .map(|(i, buffer)| {
@@ -65,32 +33,14 @@ pub fn load_gltf_buffers(
             };
             
             let data = match buffer.source() {
-                Source::Uri(uri) => {
-                    let path = base_path.map(|p| p.join(uri))
-                        .ok_or_else(|| {
-                            LoadBufferDataError::new(format!(
-                                "Buffer [{}]: Base path required for external URI '{}'",
-                                i, uri
-                            ))
-                        })?;
-                    
-                    std::fs::read(&path).map_err(|e| {
-                        LoadBufferDataError::with_source(
-                            format!("Buffer [{}]: Failed to read '{}'", i, path.display()),
-                            e
-                        )
-                    })?
-                },
-                Source::Bin => {
-                    gltf.blob.clone().ok_or_else(|| {
-                        LoadBufferDataError::new(format!(
-                            "Buffer [{}]: Missing binary data for embedded buffer",
-                            i
-                        ))
-                    })?
-                }
-            };
-            
+                Source::Uri(uri) => base_path
+                    .map(|p| p.join(uri))
+                    .ok_or_else(|| AssetError::MissingBasePath { uri: uri.to_string() })
+                    .and_then(|path| std::fs::read(&path).map_err(|e| AssetError::io(path, e))),
+                Source::Bin => gltf.blob.clone().ok_or(AssetError::MissingBlob),
+            }
+            .map_err(|err| AssetError::Buffer { index: i, source: Box::new(err) })?;
+
             Ok(BufferData { data, uri })
         })
         .collect()
@@ -166,7 +116,7 @@ mod tests {
         
         assert!(matches!(result, Err(_)));
         if let Err(e) = result {
-            assert!(e.to_string().contains("Failed to read"));
+            assert!(e.to_string().contains("failed to read"));
         }
     }
 
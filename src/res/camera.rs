@@ -1,12 +1,32 @@
-use glam::{Quat, Vec3};
+use glam::Mat4;
 
-use super::{CameraKey, Resource};
+use super::{error::AssetError, CameraKey, Resource};
 
-#[derive(Debug, Clone)] 
+/// Проекция камеры glTF
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective {
+        /// Вертикальный угол обзора в радианах
+        yfov: f32,
+        /// Соотношение сторон из файла; `None` — брать от окна
+        aspect_ratio: Option<f32>,
+        znear: f32,
+        /// `None` — бесконечная дальняя плоскость
+        zfar: Option<f32>,
+    },
+    Orthographic {
+        xmag: f32,
+        ymag: f32,
+        znear: f32,
+        zfar: f32,
+    },
+}
+
+/// Камера из файла. Положение задаётся узлом сцены, который на неё ссылается.
+#[derive(Debug, Clone)]
 pub struct Camera {
     pub name: String,
-    pub rotation: Quat,
-    pub scale: Vec3,
+    pub projection: Projection,
 }
 
 impl Resource for Camera {
@@ -14,7 +34,47 @@ impl Resource for Camera {
     type LoadParams = Camera;
     
 
-    fn load(camera: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>> {
+    fn load(camera: Self::LoadParams) -> Result<Self, AssetError> {
         Ok(camera) 
     }
-}
\ No newline at end of file
+}
+
+impl Camera {
+    pub fn from_gltf(camera: &gltf::Camera) -> Self {
+        let projection = match camera.projection() {
+            gltf::camera::Projection::Perspective(p) => Projection::Perspective {
+                yfov: p.yfov(),
+                aspect_ratio: p.aspect_ratio(),
+                znear: p.znear(),
+                zfar: p.zfar(),
+            },
+            gltf::camera::Projection::Orthographic(o) => Projection::Orthographic {
+                xmag: o.xmag(),
+                ymag: o.ymag(),
+                znear: o.znear(),
+                zfar: o.zfar(),
+            },
+        };
+        Self {
+            name: camera.name().unwrap_or_default().to_string(),
+            projection,
+        }
+    }
+
+    /// Матрица проекции (глубина 0..1, как в wgpu); `aspect` используется,
+    /// если в файле соотношение сторон не задано
+    pub fn projection_matrix(&self, aspect: f32) -> Mat4 {
+        match self.projection {
+            Projection::Perspective { yfov, aspect_ratio, znear, zfar } => {
+                let aspect = aspect_ratio.unwrap_or(aspect);
+                match zfar {
+                    Some(zfar) => Mat4::perspective_rh(yfov, aspect, znear, zfar),
+                    None => Mat4::perspective_infinite_rh(yfov, aspect, znear),
+                }
+            }
+            Projection::Orthographic { xmag, ymag, znear, zfar } => {
+                Mat4::orthographic_rh(-xmag, xmag, -ymag, ymag, znear, zfar)
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Ошибки загрузки ресурсов.
+
+use std::path::{Path, PathBuf};
+
+use super::texture::compressed::CompressedTextureError;
+
+/// Ошибка загрузки ассета.
+///
+/// Варианты с `source: Box<AssetError>` указывают место ошибки: файл
+/// ([`AssetError::File`]) и элемент glTF (меш, примитив, материал, изображение).
+/// Сообщение варианта не повторяет причину, она доступна через
+/// [`Error::source`](std::error::Error::source); всю цепочку собирает
+/// [`AssetError::report`].
+#[derive(thiserror::Error, Debug)]
+pub enum AssetError {
+    #[error("failed to read {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse glTF {}", path.display())]
+    Gltf {
+        path: PathBuf,
+        #[source]
+        source: Box<gltf::Error>,
+    },
+    #[error("external resource '{uri}' needs a base path")]
+    MissingBasePath { uri: String },
+    #[error("embedded buffer has no GLB binary chunk")]
+    MissingBlob,
+    #[error("unsupported image mime type: {0}")]
+    UnsupportedImage(String),
+    #[error("failed to decode image")]
+    Decode(#[from] image::ImageError),
+    #[error(transparent)]
+    Compressed(#[from] CompressedTextureError),
+    /// Данные файла противоречат спецификации или друг другу
+    #[error("{0}")]
+    InvalidData(String),
+    #[error("buffer #{index}")]
+    Buffer {
+        index: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("image #{index}")]
+    Image {
+        index: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("texture #{index}")]
+    Texture {
+        index: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("material #{index}")]
+    Material {
+        index: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("mesh #{mesh}, primitive #{primitive}")]
+    Primitive {
+        mesh: usize,
+        primitive: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("skin #{index}")]
+    Skin {
+        index: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("animation #{index}")]
+    Animation {
+        index: usize,
+        #[source]
+        source: Box<AssetError>,
+    },
+    #[error("node #{node} references missing {kind} #{index}")]
+    MissingReference { node: usize, kind: &'static str, index: usize },
+    #[error("node #{0} is reachable twice, the node graph is not a tree")]
+    NodeCycle(usize),
+    #[error("{} is not loaded", .0.display())]
+    NotLoaded(PathBuf),
+    #[error("{}", path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: Box<AssetError>,
+    },
+}
+
+impl AssetError {
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        Self::Io { path: path.into(), source }
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::InvalidData(message.into())
+    }
+
+    /// Добавляет путь файла, если его ещё нет в ошибке
+    pub fn in_file(self, path: &Path) -> Self {
+        match self {
+            Self::Io { .. } | Self::Gltf { .. } | Self::NotLoaded(_) | Self::File { .. } => self,
+            source => Self::File {
+                path: path.to_path_buf(),
+                source: Box::new(source),
+            },
+        }
+    }
+
+    /// Сообщение вместе со всеми причинами через `: `
+    pub fn report(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(error) = source {
+            message.push_str(": ");
+            message.push_str(&error.to_string());
+            source = error.source();
+        }
+        message
+    }
+
+    /// Ошибка без обёрток, указывающих место
+    pub fn root_cause(&self) -> &AssetError {
+        match self {
+            Self::Buffer { source, .. }
+            | Self::Image { source, .. }
+            | Self::Texture { source, .. }
+            | Self::Material { source, .. }
+            | Self::Primitive { source, .. }
+            | Self::Skin { source, .. }
+            | Self::Animation { source, .. }
+            | Self::File { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_context() {
+        let error = AssetError::Primitive {
+            mesh: 2,
+            primitive: 1,
+            source: Box::new(AssetError::invalid("no positions")),
+        }
+        .in_file(Path::new("models/robot.glb"));
+
+        assert_eq!(error.to_string(), "models/robot.glb");
+        assert_eq!(error.report(), "models/robot.glb: mesh #2, primitive #1: no positions");
+        assert!(matches!(error.root_cause(), AssetError::InvalidData(message) if message == "no positions"));
+        assert!(std::error::Error::source(&error).is_some());
+
+        // Путь не добавляется дважды, причина не повторяется в сообщении
+        let io = AssetError::io("a.bin", std::io::ErrorKind::NotFound.into()).in_file(Path::new("b.gltf"));
+        assert!(matches!(io, AssetError::Io { .. }));
+        assert_eq!(io.report(), format!("failed to read a.bin: {}", std::io::Error::from(std::io::ErrorKind::NotFound)));
+    }
+}
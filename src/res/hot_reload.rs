@@ -0,0 +1,107 @@
+//! Горячая перезагрузка ассетов.
+//!
+//! [`AssetHotReload`] следит за каталогами файлов, загруженных через
+//! [`AssetManager::load_gltf_file`] и [`AssetManager::load_texture_file`].
+//! glTF перечитывается, когда меняется он сам или его внешние буферы и
+//! текстуры, отдельная текстура — когда меняется её файл. Handle'ы при этом
+//! не меняются, поэтому сцены подхватывают новую версию сами; при ошибке
+//! остаётся прежняя.
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::asset_manager::AssetManager;
+
+/// Наблюдатель за исходными файлами ассетов
+pub struct AssetHotReload {
+    watcher: RecommendedWatcher,
+    /// Каталоги, за которыми уже идёт наблюдение
+    watched: HashSet<PathBuf>,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl AssetHotReload {
+    pub fn new() -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(sender)?;
+        Ok(Self {
+            watcher,
+            watched: HashSet::new(),
+            events,
+        })
+    }
+
+    /// Обрабатывает накопившиеся события без блокировки и перезагружает
+    /// затронутые файлы. Возвращает успешно перезагруженные.
+    pub fn poll(&mut self, assets: &mut AssetManager, device: &wgpu::Device, queue: &wgpu::Queue) -> Vec<PathBuf> {
+        self.watch_loaded(assets);
+
+        let mut changed = BTreeSet::new();
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                    changed.extend(event.paths);
+                }
+                Ok(_) => {}
+                Err(err) => log::warn!("asset watcher error: {}", err),
+            }
+        }
+        let changed: Vec<PathBuf> = changed.into_iter().collect();
+        reload_changed(assets, &changed, device, queue)
+    }
+
+    /// Начинает следить за каталогами недавно загруженных файлов
+    fn watch_loaded(&mut self, assets: &AssetManager) {
+        let directories: Vec<PathBuf> = assets
+            .watched_paths()
+            .filter_map(Path::parent)
+            .filter(|directory| !self.watched.contains(*directory))
+            .map(Path::to_path_buf)
+            .collect();
+        for directory in directories {
+            match self.watcher.watch(&directory, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.watched.insert(directory);
+                }
+                Err(err) => {
+                    log::warn!("cannot watch {}: {}", directory.display(), err);
+                    // Не повторять попытку каждый кадр
+                    self.watched.insert(directory);
+                }
+            }
+        }
+    }
+}
+
+/// Перезагружает файлы, зависящие от изменённых путей; ошибки пишутся в лог
+pub fn reload_changed(
+    assets: &mut AssetManager,
+    changed: &[PathBuf],
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> Vec<PathBuf> {
+    let files: BTreeSet<PathBuf> = changed
+        .iter()
+        .flat_map(|path| {
+            let path = path.canonicalize().unwrap_or_else(|_| path.clone());
+            assets.files_using(&path)
+        })
+        .collect();
+
+    let mut reloaded = Vec::new();
+    for file in files {
+        match assets.reload_file(&file, device, queue) {
+            Ok(_) => {
+                log::info!("asset '{}' reloaded", file.display());
+                reloaded.push(file);
+            }
+            Err(err) => log::error!("keeping previous '{}': {}", file.display(), err.report()),
+        }
+    }
+    reloaded
+}
@@ -1,47 +1,18 @@
-use std::{error::Error, fmt, path::Path};
+use std::path::Path;
 use gltf::{image::{Source, Format}, Gltf, Image};
 
-use super::buffer::BufferData;
+use super::{buffer::BufferData, error::AssetError, texture::compressed::ImageContainer};
+
+/// Расширение glTF, подменяющее источник текстуры на KTX2-изображение
+pub const KHR_TEXTURE_BASISU: &str = "KHR_texture_basisu";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImageData {
     pub data: Vec<u8>,
     pub uri: Option<String>,
     pub format: Format,
-}
-
-#[derive(Debug)]
-pub struct LoadImageDataError {
-    message: String,
-    source: Option<Box<dyn Error + Send + Sync>>,
-}
-
-impl fmt::Display for LoadImageDataError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Image loading error: {}", self.message)
-    }
-}
-
-impl Error for LoadImageDataError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        self.source.as_ref().map(|e| &**e as _)
-    }
-}
-
-impl LoadImageDataError {
-    pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            source: None,
-        }
-    }
-
-    pub fn with_source(message: impl Into<String>, source: impl Error + Send + Sync + 'static) -> Self {
-        Self {
-            message: message.into(),
-            source: Some(Box::new(source)),
-        }
-    }
+    /// Контейнер по сигнатуре данных: KTX2/DDS грузятся без декодирования в RGBA8
+    pub container: ImageContainer,
 }
 
 /// Загружает данные изображения из GLTF
@@ -49,23 +20,31 @@ pub fn load_gltf_image_data(
     base_path: Option<&Path>,
     buffers: &[BufferData],
     image: Image,
-) -> Result<ImageData, LoadImageDataError> {
+) -> Result<ImageData, AssetError> {
+    let index = image.index();
+    read_gltf_image_data(base_path, buffers, image).map_err(|err| AssetError::Image {
+        index,
+        source: Box::new(err),
+    })
+}
+
+fn read_gltf_image_data(
+    base_path: Option<&Path>,
+    buffers: &[BufferData],
+    image: Image,
+) -> Result<ImageData, AssetError> {
     let (data, format, uri) = match image.source() {
         Source::Uri { uri, mime_type } => {
-            let path = base_path.ok_or_else(|| {
-                LoadImageDataError::new("Base path required for external images")
-            })?.join(uri);
-            
-            let data = std::fs::read(&path).map_err(|e| {
-                LoadImageDataError::with_source(
-                    format!("Failed to read image at {}", path.display()),
-                    e
-                )
-            })?;
+            let path = base_path
+                .ok_or_else(|| AssetError::MissingBasePath { uri: uri.to_string() })?
+                .join(uri);
+
+            let data = std::fs::read(&path).map_err(|e| AssetError::io(path, e))?;
             
             let format = match mime_type.as_deref() {
                 Some("image/png") => Format::R8G8B8A8,
                 Some("image/jpeg") => Format::R8G8B8,
+                Some("image/ktx2") | Some("image/vnd-ms.dds") => Format::R8G8B8A8,
                 _ => Format::R8G8B8,
                 // _ => {println!("BLEEEEEEEEEEEEEEEH Unsupported image mime type");
                 //     return Err(LoadImageDataError::new(format!(
@@ -82,8 +61,8 @@ pub fn load_gltf_image_data(
             let end = start + view.length();
             
             if end > buffer.len() {
-                return Err(LoadImageDataError::new(format!(
-                    "Image view exceeds buffer bounds ({} > {})",
+                return Err(AssetError::invalid(format!(
+                    "image view exceeds buffer bounds ({} > {})",
                     end, buffer.len()
                 )));
             }
@@ -91,9 +70,8 @@ pub fn load_gltf_image_data(
             let format = match mime_type {
                 "image/png" => Format::R8G8B8A8,
                 "image/jpeg" => Format::R8G8B8,
-                _ => return Err(LoadImageDataError::new(format!(
-                    "Unsupported image mime type: {}", mime_type
-                ))),
+                "image/ktx2" | "image/vnd-ms.dds" => Format::R8G8B8A8,
+                _ => return Err(AssetError::UnsupportedImage(mime_type.to_string())),
             };
             
             (buffer[start..end].to_vec(), format, None)
@@ -101,18 +79,32 @@ pub fn load_gltf_image_data(
     };
     
     Ok(ImageData {
+        container: ImageContainer::detect(&data),
         data,
         format,
         uri
     })
 }
 
+/// Изображение текстуры с учётом `KHR_texture_basisu`: если расширение
+/// задаёт KTX2-источник, используется он, иначе обычный `source`. Загрузчик
+/// возвращается к `source`, если KTX2 не загрузить на адаптере
+/// (см. [`PreparedImage::is_loadable_on`](super::loader::PreparedImage::is_loadable_on))
+pub fn gltf_texture_image<'a>(document: &'a gltf::Document, texture: &gltf::Texture<'a>) -> Image<'a> {
+    texture
+        .extension_value(KHR_TEXTURE_BASISU)
+        .and_then(|extension| extension.get("source"))
+        .and_then(|source| source.as_u64())
+        .and_then(|index| document.images().nth(index as usize))
+        .unwrap_or_else(|| texture.source())
+}
+
 /// Загружает все изображения из GLTF документа
 pub fn load_gltf_images(
     gltf: &Gltf,
     base_path: Option<&Path>,
     buffers: &[BufferData],
-) -> Result<Vec<ImageData>, LoadImageDataError> {
+) -> Result<Vec<ImageData>, AssetError> {
     gltf.images()
         .map(|image| load_gltf_image_data(base_path, buffers, image))
         .collect()
@@ -140,6 +132,29 @@ mod tests {
         assert_eq!(images[0].format, Format::R8G8B8A8);
     }
 
+    #[test]
+    fn test_texture_basisu_source() {
+        let gltf = Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "extensionsUsed": ["KHR_texture_basisu"],
+                "images": [
+                    { "uri": "fallback.png" },
+                    { "uri": "texture.ktx2", "mimeType": "image/ktx2" }
+                ],
+                "textures": [
+                    { "source": 0, "extensions": { "KHR_texture_basisu": { "source": 1 } } },
+                    { "source": 0 }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let textures: Vec<_> = gltf.textures().collect();
+
+        assert_eq!(gltf_texture_image(&gltf, &textures[0]).index(), 1);
+        assert_eq!(gltf_texture_image(&gltf, &textures[1]).index(), 0);
+    }
+
     #[test]
     fn test_unsupported_mime_type() {
         let dir = tempfile::tempdir().unwrap();
@@ -160,7 +175,7 @@ mod tests {
         
         assert!(matches!(result, Err(_)));
         if let Err(e) = result {
-            assert!(e.to_string().contains("Unsupported image mime type"));
+            assert!(e.to_string().contains("unsupported image mime type"));
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,412 @@
+//! Фоновая загрузка файлов glTF.
+//!
+//! Рабочие потоки разбирают файл, читают буферы и декодируют изображения
+//! ([`PreparedGltf`]); загрузка на GPU идёт в главном потоке в
+//! [`AssetLoader::update`], поэтому большой файл не останавливает окно.
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use gltf::Gltf;
+
+use super::{
+    asset_manager::{AssetManager, GltfImport},
+    buffer::{load_gltf_buffers, BufferData},
+    error::AssetError,
+    image::{load_gltf_image_data, ImageData},
+    texture::{
+        compressed::{ktx2_needs_transcoder, CompressedImage, ImageContainer},
+        TextureKind,
+    },
+};
+
+/// Изображение glTF, прочитанное заранее
+#[derive(Debug, Clone)]
+pub enum PreparedImage {
+    /// PNG/JPEG, уже разжатые в пиксели
+    Decoded(image::DynamicImage),
+    /// KTX2/DDS: разбираются при создании текстуры, формат зависит от назначения
+    Compressed(ImageData),
+}
+
+impl PreparedImage {
+    /// Читает изображение из файла или буфера и декодирует его
+    pub fn load(base_path: Option<&Path>, buffers: &[BufferData], image: gltf::Image) -> Result<Self, AssetError> {
+        let index = image.index();
+        let data = load_gltf_image_data(base_path, buffers, image)?;
+        match data.container {
+            ImageContainer::Other => image::load_from_memory(&data.data)
+                .map(Self::Decoded)
+                .map_err(|err| AssetError::Image { index, source: Box::new(err.into()) }),
+            ImageContainer::Ktx2 | ImageContainer::Dds => Ok(Self::Compressed(data)),
+        }
+    }
+
+    /// KTX2 в кодировке Basis Universal, который [`CompressedImage`] не разбирает
+    ///
+    /// [`CompressedImage`]: super::texture::compressed::CompressedImage
+    pub fn needs_transcoder(&self) -> bool {
+        match self {
+            Self::Compressed(data) => data.container == ImageContainer::Ktx2 && ktx2_needs_transcoder(&data.data),
+            Self::Decoded(_) => false,
+        }
+    }
+
+    /// Создаётся ли из изображения текстура на устройстве с `features`; не
+    /// создаётся из KTX2 без транскодера и из формата, который адаптер не
+    /// поддерживает, а CPU не распаковывает
+    pub fn is_loadable_on(&self, features: wgpu::Features) -> bool {
+        match self {
+            Self::Compressed(data) => {
+                !self.needs_transcoder()
+                    && CompressedImage::from_bytes(&data.data, TextureKind::Data)
+                        .is_ok_and(|image| image.is_loadable_on(features))
+            }
+            Self::Decoded(_) => true,
+        }
+    }
+}
+
+/// Файл glTF, готовый к загрузке на GPU через [`AssetManager::load_prepared_gltf`]
+#[derive(Debug)]
+pub struct PreparedGltf {
+    /// Канонический путь файла
+    pub path: PathBuf,
+    pub gltf: Gltf,
+    pub buffers: Vec<BufferData>,
+    /// Изображения в порядке индексов файла
+    pub images: Vec<PreparedImage>,
+}
+
+impl PreparedGltf {
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        Self::read_with_progress(path, |_| {})
+    }
+
+    /// Читает файл, сообщая долю выполненной работы от 0 до 1
+    pub fn read_with_progress(
+        path: impl AsRef<Path>,
+        mut progress: impl FnMut(f32),
+    ) -> Result<Self, AssetError> {
+        let path = path.as_ref();
+        let path = path.canonicalize().map_err(|err| AssetError::io(path, err))?;
+        let gltf = Gltf::open(&path).map_err(|err| AssetError::Gltf {
+            path: path.clone(),
+            source: Box::new(err),
+        })?;
+        progress(0.1);
+
+        let base_path = path.parent();
+        let buffers = load_gltf_buffers(&gltf, base_path).map_err(|err| err.in_file(&path))?;
+        progress(0.3);
+
+        let count = gltf.images().len();
+        let mut images = Vec::with_capacity(count);
+        for image in gltf.images() {
+            let prepared = PreparedImage::load(base_path, &buffers, image).map_err(|err| err.in_file(&path))?;
+            images.push(prepared);
+            progress(0.3 + 0.7 * images.len() as f32 / count as f32);
+        }
+        progress(1.0);
+
+        Ok(Self { path, gltf, buffers, images })
+    }
+
+    /// Файл и внешние буферы и изображения, от которых он зависит
+    pub fn dependencies(&self) -> Vec<PathBuf> {
+        let base_path = self.path.parent().unwrap_or(Path::new(""));
+        let buffers = self.gltf.buffers().filter_map(|buffer| match buffer.source() {
+            gltf::buffer::Source::Uri(uri) => Some(uri),
+            gltf::buffer::Source::Bin => None,
+        });
+        let images = self.gltf.images().filter_map(|image| match image.source() {
+            gltf::image::Source::Uri { uri, .. } => Some(uri),
+            gltf::image::Source::View { .. } => None,
+        });
+
+        let mut paths = vec![self.path.clone()];
+        for uri in buffers.chain(images).filter(|uri| !uri.starts_with("data:")) {
+            let path = base_path.join(uri);
+            let path = path.canonicalize().unwrap_or(path);
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+}
+
+/// Номер запроса фоновой загрузки
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoadId(usize);
+
+/// Состояние запроса
+#[derive(Debug)]
+pub enum LoadState {
+    /// Файл читается или ждёт загрузки на GPU; `progress` от 0 до 1
+    Loading { progress: f32 },
+    Loaded(GltfImport),
+    Failed(AssetError),
+}
+
+/// Сводка по всем запросам
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadProgress {
+    pub total: usize,
+    pub loaded: usize,
+    pub failed: usize,
+    /// Средняя доля выполненной работы, 1 — все запросы завершены
+    pub fraction: f32,
+}
+
+impl LoadProgress {
+    pub fn is_done(&self) -> bool {
+        self.loaded + self.failed == self.total
+    }
+}
+
+struct Job {
+    id: LoadId,
+    path: PathBuf,
+}
+
+enum Event {
+    Progress(LoadId, f32),
+    Prepared(LoadId, Box<Result<PreparedGltf, AssetError>>),
+}
+
+/// Загрузчик с пулом рабочих потоков
+pub struct AssetLoader {
+    jobs: Option<Sender<Job>>,
+    events: Receiver<Event>,
+    workers: Vec<JoinHandle<()>>,
+    states: Vec<LoadState>,
+    /// Файлы, прочитанные потоками и ждущие загрузки на GPU
+    ready: VecDeque<(LoadId, PreparedGltf)>,
+    /// Сколько файлов загружать на GPU за один вызов [`AssetLoader::update`]
+    pub uploads_per_update: usize,
+}
+
+impl Default for AssetLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AssetLoader {
+    /// Загрузчик с потоком на ядро, но не больше четырёх
+    pub fn new() -> Self {
+        let workers = std::thread::available_parallelism().map_or(2, |count| count.get().min(4));
+        Self::with_workers(workers)
+    }
+
+    pub fn with_workers(count: usize) -> Self {
+        let (jobs, job_receiver) = channel::<Job>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let (event_sender, events) = channel();
+
+        let workers = (0..count.max(1))
+            .map(|index| {
+                let jobs = Arc::clone(&job_receiver);
+                let events = event_sender.clone();
+                std::thread::Builder::new()
+                    .name(format!("asset-loader-{}", index))
+                    .spawn(move || worker(jobs, events))
+                    .expect("failed to spawn asset loader thread")
+            })
+            .collect();
+
+        Self {
+            jobs: Some(jobs),
+            events,
+            workers,
+            states: Vec::new(),
+            ready: VecDeque::new(),
+            uploads_per_update: 1,
+        }
+    }
+
+    /// Ставит файл в очередь и сразу возвращает номер запроса
+    pub fn load_gltf(&mut self, path: impl Into<PathBuf>) -> LoadId {
+        let id = LoadId(self.states.len());
+        self.states.push(LoadState::Loading { progress: 0.0 });
+        let sent = self
+            .jobs
+            .as_ref()
+            .is_some_and(|jobs| jobs.send(Job { id, path: path.into() }).is_ok());
+        if !sent {
+            self.states[id.0] = LoadState::Failed(AssetError::invalid("asset loader is stopped"));
+        }
+        id
+    }
+
+    pub fn state(&self, id: LoadId) -> Option<&LoadState> {
+        self.states.get(id.0)
+    }
+
+    /// Импорт, если файл уже загружен
+    pub fn get(&self, id: LoadId) -> Option<&GltfImport> {
+        match self.states.get(id.0) {
+            Some(LoadState::Loaded(import)) => Some(import),
+            _ => None,
+        }
+    }
+
+    pub fn progress(&self) -> LoadProgress {
+        let mut summary = LoadProgress { total: self.states.len(), loaded: 0, failed: 0, fraction: 1.0 };
+        if self.states.is_empty() {
+            return summary;
+        }
+        let mut sum = 0.0;
+        for state in &self.states {
+            sum += match state {
+                LoadState::Loading { progress } => *progress,
+                LoadState::Loaded(_) => {
+                    summary.loaded += 1;
+                    1.0
+                }
+                LoadState::Failed(_) => {
+                    summary.failed += 1;
+                    1.0
+                }
+            };
+        }
+        summary.fraction = sum / self.states.len() as f32;
+        summary
+    }
+
+    /// Принимает результаты потоков и загружает готовые файлы на GPU.
+    /// Вызывается в главном потоке, обычно раз в кадр.
+    pub fn update(&mut self, assets: &mut AssetManager, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.receive();
+        for _ in 0..self.uploads_per_update {
+            let Some((id, prepared)) = self.ready.pop_front() else {
+                break;
+            };
+            self.states[id.0] = match assets.load_prepared_gltf(prepared, device, queue) {
+                Ok(import) => LoadState::Loaded(import),
+                Err(err) => LoadState::Failed(err),
+            };
+        }
+    }
+
+    /// Разбирает сообщения потоков без блокировки
+    fn receive(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            match event {
+                Event::Progress(id, value) => {
+                    if let LoadState::Loading { progress } = &mut self.states[id.0] {
+                        // Загрузка на GPU — последняя часть работы
+                        *progress = value * 0.9;
+                    }
+                }
+                Event::Prepared(id, result) => match *result {
+                    Ok(prepared) => self.ready.push_back((id, prepared)),
+                    Err(err) => self.states[id.0] = LoadState::Failed(err),
+                },
+            }
+        }
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        // Закрытый канал завершает потоки после текущего файла
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn worker(jobs: Arc<Mutex<Receiver<Job>>>, events: Sender<Event>) {
+    loop {
+        let job = match jobs.lock() {
+            Ok(jobs) => jobs.recv(),
+            Err(_) => return,
+        };
+        let Ok(Job { id, path }) = job else {
+            return;
+        };
+        let result = PreparedGltf::read_with_progress(&path, |progress| {
+            let _ = events.send(Event::Progress(id, progress));
+        });
+        if events.send(Event::Prepared(id, Box::new(result))).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    /// Ждёт, пока потоки обработают все запросы
+    fn wait(loader: &mut AssetLoader) {
+        let start = Instant::now();
+        while loader.ready.len() + loader.progress().failed < loader.states.len() {
+            assert!(start.elapsed() < Duration::from_secs(30), "asset loader timed out");
+            loader.receive();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_background_prepare() {
+        let mut loader = AssetLoader::with_workers(2);
+        let cube = loader.load_gltf("examples/assets/cube_model/scene.gltf");
+        let missing = loader.load_gltf("examples/assets/missing.gltf");
+        assert!(matches!(loader.state(cube), Some(LoadState::Loading { .. })));
+
+        wait(&mut loader);
+        assert!(matches!(loader.state(missing), Some(LoadState::Failed(AssetError::Io { .. }))));
+        let progress = loader.progress();
+        assert_eq!((progress.total, progress.loaded, progress.failed), (2, 0, 1));
+        assert!(!progress.is_done());
+
+        // Файл прочитан и ждёт GPU: изображения уже декодированы
+        let (id, prepared) = &loader.ready[0];
+        assert_eq!(*id, cube);
+        assert_eq!(prepared.images.len(), prepared.gltf.images().len());
+        assert!(matches!(prepared.images.first(), Some(PreparedImage::Decoded(_))));
+        assert!(matches!(loader.state(cube), Some(LoadState::Loading { progress }) if *progress < 1.0));
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let gltf = Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "buffers": [
+                    { "byteLength": 4, "uri": "mesh.bin" },
+                    { "byteLength": 4, "uri": "data:application/octet-stream;base64,AAAAAA==" }
+                ],
+                "images": [{ "uri": "textures/albedo.png" }, { "uri": "mesh.bin" }]
+            }"#,
+        )
+        .unwrap();
+        let prepared = PreparedGltf {
+            path: PathBuf::from("/missing/scene.gltf"),
+            gltf,
+            buffers: Vec::new(),
+            images: Vec::new(),
+        };
+        assert_eq!(
+            prepared.dependencies(),
+            [
+                PathBuf::from("/missing/scene.gltf"),
+                PathBuf::from("/missing/mesh.bin"),
+                PathBuf::from("/missing/textures/albedo.png"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Загрузка каталога ассетов.
+//!
+//! Тип файла определяется по сигнатуре, а если её нет — по расширению.
+//! Результат загрузки — [`Manifest`]: относительный путь файла и handle'ы
+//! того, что из него создано.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use glob::{MatchOptions, Pattern, PatternError};
+
+use super::{
+    asset_manager::GltfImport,
+    error::AssetError,
+    texture::{gpu_texture::GpuTexture, ibl::Environment},
+    Handle,
+};
+use crate::core::shader::ShaderComposer;
+
+/// Расширение файлов описания сцены
+pub const SCENE_DESCRIPTION_EXTENSION: &str = ".scene.json";
+
+/// Тип файла ассета
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    /// `.gltf` или `.glb`
+    Gltf,
+    /// PNG, JPEG, KTX2, DDS
+    Texture,
+    /// HDR-панорама (Radiance), из неё строится окружение
+    Environment,
+    /// Модуль WGSL
+    Shader,
+    /// JSON со списком файлов сцены, см. [`SceneDescription`]
+    SceneDescription,
+}
+
+impl AssetKind {
+    /// Определяет тип по первым байтам файла, затем по имени
+    pub fn detect(path: &Path, header: &[u8]) -> Option<Self> {
+        const PNG: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        const JPEG: &[u8] = &[0xff, 0xd8, 0xff];
+        const KTX2: &[u8] = &[0xab, b'K', b'T', b'X', b' ', b'2', b'0', 0xbb];
+        const DDS: &[u8] = b"DDS ";
+
+        if header.starts_with(b"glTF") {
+            return Some(Self::Gltf);
+        }
+        if [PNG, JPEG, KTX2, DDS].iter().any(|magic| header.starts_with(magic)) {
+            return Some(Self::Texture);
+        }
+        if header.starts_with(b"#?RADIANCE") || header.starts_with(b"#?RGBE") {
+            return Some(Self::Environment);
+        }
+
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(SCENE_DESCRIPTION_EXTENSION) {
+            return Some(Self::SceneDescription);
+        }
+        match name.rsplit_once('.')?.1 {
+            "gltf" | "glb" => Some(Self::Gltf),
+            "png" | "jpg" | "jpeg" | "ktx2" | "dds" => Some(Self::Texture),
+            "hdr" => Some(Self::Environment),
+            "wgsl" => Some(Self::Shader),
+            _ => None,
+        }
+    }
+}
+
+/// Описание сцены: файлы, которые нужно загрузить вместе.
+///
+/// ```json
+/// { "assets": ["models/robot.glb", "sky.hdr"] }
+/// ```
+///
+/// Пути указываются относительно файла описания.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SceneDescription {
+    pub assets: Vec<String>,
+}
+
+impl SceneDescription {
+    pub fn from_slice(data: &[u8]) -> Result<Self, AssetError> {
+        let value: gltf::json::Value =
+            gltf::json::deserialize::from_slice(data).map_err(|err| AssetError::invalid(err.to_string()))?;
+        let assets = value
+            .get("assets")
+            .and_then(|assets| assets.as_array())
+            .ok_or_else(|| AssetError::invalid("scene description has no \"assets\" list"))?;
+        let assets = assets
+            .iter()
+            .map(|asset| {
+                asset
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| AssetError::invalid("asset path must be a string"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { assets })
+    }
+}
+
+/// Что создано из одного файла
+#[derive(Debug, Clone)]
+pub enum LoadedAsset {
+    Gltf(GltfImport),
+    Texture(Handle<GpuTexture>),
+    Environment(Environment),
+    /// Исходник модуля; регистрируется через [`Manifest::register_shaders`]
+    Shader { name: String, source: String },
+    /// Относительные пути файлов сцены в манифесте
+    SceneDescription(Vec<String>),
+}
+
+/// Результат [`AssetManager::load_from_directory`](super::asset_manager::AssetManager::load_from_directory)
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// Канонический путь каталога
+    pub root: PathBuf,
+    /// Загруженные файлы по путям относительно `root` (через `/`)
+    pub assets: BTreeMap<String, LoadedAsset>,
+    /// Файлы, которые не удалось загрузить, с причиной
+    pub errors: BTreeMap<String, AssetError>,
+}
+
+impl Manifest {
+    pub fn get(&self, path: &str) -> Option<&LoadedAsset> {
+        self.assets.get(path)
+    }
+
+    pub fn gltf(&self, path: &str) -> Option<&GltfImport> {
+        match self.assets.get(path) {
+            Some(LoadedAsset::Gltf(import)) => Some(import),
+            _ => None,
+        }
+    }
+
+    pub fn texture(&self, path: &str) -> Option<Handle<GpuTexture>> {
+        match self.assets.get(path) {
+            Some(LoadedAsset::Texture(handle)) => Some(handle.clone()),
+            _ => None,
+        }
+    }
+
+    /// Добавляет найденные шейдеры в реестр модулей
+    pub fn register_shaders(&self, composer: &mut ShaderComposer) {
+        for (path, asset) in &self.assets {
+            if let LoadedAsset::Shader { name, source } = asset {
+                composer.add_module(name.clone(), path.clone(), source.clone());
+            }
+        }
+    }
+}
+
+/// Какие файлы каталога загружать
+#[derive(Debug, Clone)]
+pub struct DirectoryFilter {
+    /// Обходить ли подкаталоги
+    pub recursive: bool,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl Default for DirectoryFilter {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            include: Vec::new(),
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl DirectoryFilter {
+    /// Все файлы во всех подкаталогах
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    /// Загружать только пути, подходящие хотя бы под один шаблон (`**/*.glb`)
+    pub fn include(mut self, pattern: &str) -> Result<Self, PatternError> {
+        self.include.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Пропускать пути, подходящие под шаблон
+    pub fn exclude(mut self, pattern: &str) -> Result<Self, PatternError> {
+        self.exclude.push(Pattern::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Проверяет путь относительно корня каталога
+    pub fn matches(&self, relative: &str) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let matches = |pattern: &Pattern| pattern.matches_with(relative, options);
+        (self.include.is_empty() || self.include.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Результат [`scan_directory`]
+#[derive(Debug, Default)]
+pub struct DirectoryScan {
+    /// Подходящие файлы: путь относительно корня и полный путь
+    pub files: Vec<(String, PathBuf)>,
+    /// Подкаталоги и записи, которые не удалось прочитать; обход их пропускает
+    pub skipped: Vec<(PathBuf, std::io::Error)>,
+}
+
+/// Файлы каталога, подходящие под фильтр, с путями относительно `root`.
+/// Ссылки на файлы читаются, ссылки на каталоги не обходятся. Ошибка
+/// возвращается, только если не читается сам `root`.
+pub fn scan_directory(root: &Path, filter: &DirectoryFilter) -> std::io::Result<DirectoryScan> {
+    let mut scan = DirectoryScan::default();
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let read_dir = match std::fs::read_dir(&directory) {
+            Ok(read_dir) => read_dir,
+            Err(err) if directory == root => return Err(err),
+            Err(err) => {
+                scan.skipped.push((directory, err));
+                continue;
+            }
+        };
+        for entry in read_dir {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    scan.skipped.push((directory.clone(), err));
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(err) => {
+                    scan.skipped.push((path, err));
+                    continue;
+                }
+            };
+            if file_type.is_dir() {
+                if filter.recursive {
+                    directories.push(path);
+                }
+                continue;
+            }
+            // Ссылка на каталог-предка зациклила бы обход
+            if path.is_dir() {
+                continue;
+            }
+            let Some(relative) = relative_path(root, &path) else {
+                continue;
+            };
+            if filter.matches(&relative) {
+                scan.files.push((relative, path));
+            }
+        }
+    }
+    scan.files.sort();
+    Ok(scan)
+}
+
+/// Путь относительно `root` с разделителем `/`
+pub fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_kind() {
+        let detect = |name: &str, header: &[u8]| AssetKind::detect(Path::new(name), header);
+        assert_eq!(detect("model.bin", b"glTF\x02\0\0\0"), Some(AssetKind::Gltf));
+        assert_eq!(detect("model.gltf", b"{"), Some(AssetKind::Gltf));
+        // Сигнатура важнее расширения
+        assert_eq!(detect("albedo.png", &[0xff, 0xd8, 0xff, 0xe0]), Some(AssetKind::Texture));
+        assert_eq!(detect("sky", b"#?RADIANCE\n"), Some(AssetKind::Environment));
+        assert_eq!(detect("shaders/main.WGSL", b"struct"), Some(AssetKind::Shader));
+        assert_eq!(detect("level.scene.json", b"{"), Some(AssetKind::SceneDescription));
+        assert_eq!(detect("notes.json", b"{"), None);
+        assert_eq!(detect("license.txt", b"MIT"), None);
+    }
+
+    #[test]
+    fn test_scan_with_filters() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["a.glb", "textures/b.png", "textures/raw/c.png", "shaders/d.wgsl"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        let scan = |filter: DirectoryFilter| -> Vec<String> {
+            scan_directory(dir.path(), &filter).unwrap().files.into_iter().map(|(relative, _)| relative).collect()
+        };
+
+        assert_eq!(scan(DirectoryFilter::new()).len(), 4);
+        assert_eq!(scan(DirectoryFilter::new().with_recursive(false)), ["a.glb"]);
+        let textures = DirectoryFilter::new().include("**/*.png").unwrap().exclude("**/raw/*").unwrap();
+        assert_eq!(scan(textures), ["textures/b.png"]);
+        // Без `**` шаблон не заходит в подкаталоги
+        assert!(scan(DirectoryFilter::new().include("*.png").unwrap()).is_empty());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_scan_skips_directory_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("models")).unwrap();
+        std::fs::write(dir.path().join("models/a.glb"), b"").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("models/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("models/a.glb"), dir.path().join("b.glb")).unwrap();
+
+        let scanned: Vec<String> = scan_directory(dir.path(), &DirectoryFilter::new())
+            .unwrap()
+            .files
+            .into_iter()
+            .map(|(relative, _)| relative)
+            .collect();
+        assert_eq!(scanned, ["b.glb", "models/a.glb"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_scan_skips_unreadable_directories() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::write(locked.join("a.glb"), b"").unwrap();
+        std::fs::write(dir.path().join("b.glb"), b"").unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Суперпользователь читает каталог и без прав
+        let readable = std::fs::read_dir(&locked).is_ok();
+
+        let scan = scan_directory(dir.path(), &DirectoryFilter::new());
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let scan = scan.unwrap();
+
+        let files: Vec<&str> = scan.files.iter().map(|(relative, _)| relative.as_str()).collect();
+        if readable {
+            assert_eq!(files, ["b.glb", "locked/a.glb"]);
+            assert!(scan.skipped.is_empty());
+        } else {
+            assert_eq!(files, ["b.glb"]);
+            assert_eq!(scan.skipped.len(), 1);
+            assert_eq!(scan.skipped[0].0, locked);
+        }
+    }
+
+    #[test]
+    fn test_scene_description() {
+        let description = SceneDescription::from_slice(br#"{ "assets": ["robot.glb", "sky.hdr"] }"#).unwrap();
+        assert_eq!(description.assets, ["robot.glb", "sky.hdr"]);
+        assert!(SceneDescription::from_slice(br#"{ "assets": [1] }"#).is_err());
+        assert!(SceneDescription::from_slice(b"{}").is_err());
+    }
+}
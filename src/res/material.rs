@@ -1,9 +1,238 @@
-use std::{error::Error, fmt};
 
 use wgpu::{Device, BindGroupLayout, BindGroupDescriptor, BindGroupEntry, BindingResource};
-use crate::res::texture::{gpu_texture::{get_texture_bind_group_layout, GpuTexture, GpuTextureHandle}, Texture, TextureDescriptor};
+use crate::res::texture::{gpu_texture::{GpuTexture, GpuTextureHandle}, Texture, TextureDescriptor, TextureKind};
 
-use super::{storage::Storage, Handle};
+use super::{error::AssetError, storage::Storage, Handle};
+
+/// Режим прозрачности материала (glTF `alphaMode`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AlphaMode {
+    /// Альфа игнорируется
+    #[default]
+    Opaque,
+    /// Пиксели с альфой ниже `alpha_cutoff` отбрасываются
+    Mask,
+    /// Смешивание с фоном
+    Blend,
+}
+
+impl From<gltf::material::AlphaMode> for AlphaMode {
+    fn from(mode: gltf::material::AlphaMode) -> Self {
+        match mode {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        }
+    }
+}
+
+/// Текстура материала PBR; номер совпадает с битом в
+/// [`MaterialUniform::uv_sets`] и константой `SLOT_*` в `shaders/material.wgsl`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextureSlot {
+    BaseColor,
+    MetallicRoughness,
+    Normal,
+    Occlusion,
+    Emissive,
+}
+
+impl TextureSlot {
+    pub const ALL: [TextureSlot; 5] = [
+        TextureSlot::BaseColor,
+        TextureSlot::MetallicRoughness,
+        TextureSlot::Normal,
+        TextureSlot::Occlusion,
+        TextureSlot::Emissive,
+    ];
+}
+
+/// Преобразование UV из `KHR_texture_transform`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextureTransform {
+    pub offset: [f32; 2],
+    /// Поворот в радианах против часовой стрелки
+    pub rotation: f32,
+    pub scale: [f32; 2],
+    /// Переопределённый набор UV (`texCoord`)
+    pub tex_coord: Option<u32>,
+}
+
+impl Default for TextureTransform {
+    fn default() -> Self {
+        Self {
+            offset: [0.0; 2],
+            rotation: 0.0,
+            scale: [1.0; 2],
+            tex_coord: None,
+        }
+    }
+}
+
+impl TextureTransform {
+    fn from_json(value: &gltf::json::Value) -> Self {
+        let default = Self::default();
+        Self {
+            offset: json_vec(value, "offset").unwrap_or(default.offset),
+            rotation: json_f32(value, "rotation").unwrap_or(default.rotation),
+            scale: json_vec(value, "scale").unwrap_or(default.scale),
+            tex_coord: value.get("texCoord").and_then(|v| v.as_u64()).map(|v| v as u32),
+        }
+    }
+
+    /// Матрица `T * R * S` из спецификации расширения (UV как `vec3(u, v, 1)`)
+    pub fn matrix(&self) -> glam::Mat3 {
+        let (sin, cos) = self.rotation.sin_cos();
+        let translation = glam::Mat3::from_translation(glam::Vec2::from(self.offset));
+        let rotation = glam::Mat3::from_cols(
+            glam::Vec3::new(cos, -sin, 0.0),
+            glam::Vec3::new(sin, cos, 0.0),
+            glam::Vec3::Z,
+        );
+        let scale = glam::Mat3::from_scale(glam::Vec2::from(self.scale));
+        translation * rotation * scale
+    }
+
+    pub fn apply(&self, uv: [f32; 2]) -> [f32; 2] {
+        self.matrix().transform_point2(glam::Vec2::from(uv)).into()
+    }
+}
+
+/// Параметры расширений материалов KHR. Текстуры расширений не загружаются,
+/// учитываются только множители.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaterialExtensions {
+    /// `KHR_materials_transmission`: доля пропущенного света
+    pub transmission: f32,
+    /// `KHR_materials_ior`
+    pub ior: f32,
+    /// `KHR_materials_clearcoat`
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+    /// `KHR_materials_specular`
+    pub specular: f32,
+    pub specular_color: [f32; 3],
+    /// `KHR_materials_sheen`
+    pub sheen_color: [f32; 3],
+    pub sheen_roughness: f32,
+    /// `KHR_materials_emissive_strength`
+    pub emissive_strength: f32,
+    /// `KHR_texture_transform` каждой текстуры по [`TextureSlot`]; у текстур
+    /// без расширения преобразование тождественное
+    pub texture_transforms: [TextureTransform; TextureSlot::ALL.len()],
+}
+
+impl Default for MaterialExtensions {
+    fn default() -> Self {
+        Self {
+            transmission: 0.0,
+            ior: 1.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.0,
+            specular: 1.0,
+            specular_color: [1.0; 3],
+            sheen_color: [0.0; 3],
+            sheen_roughness: 0.0,
+            emissive_strength: 1.0,
+            texture_transforms: [TextureTransform::default(); TextureSlot::ALL.len()],
+        }
+    }
+}
+
+fn json_f32(value: &gltf::json::Value, key: &str) -> Option<f32> {
+    value.get(key)?.as_f64().map(|v| v as f32)
+}
+
+fn json_vec<const N: usize>(value: &gltf::json::Value, key: &str) -> Option<[f32; N]> {
+    let array = value.get(key)?.as_array()?;
+    if array.len() != N {
+        return None;
+    }
+    let mut result = [0.0; N];
+    for (slot, item) in result.iter_mut().zip(array) {
+        *slot = item.as_f64()? as f32;
+    }
+    Some(result)
+}
+
+impl MaterialExtensions {
+    pub fn from_gltf(material: &gltf::Material) -> Self {
+        let mut ext = Self::default();
+        let extension = |name: &str| material.extension_value(name);
+
+        if let Some(value) = extension("KHR_materials_transmission") {
+            ext.transmission = json_f32(value, "transmissionFactor").unwrap_or(0.0);
+        }
+        if let Some(value) = extension("KHR_materials_ior") {
+            ext.ior = json_f32(value, "ior").unwrap_or(1.5);
+        }
+        if let Some(value) = extension("KHR_materials_clearcoat") {
+            ext.clearcoat = json_f32(value, "clearcoatFactor").unwrap_or(0.0);
+            ext.clearcoat_roughness = json_f32(value, "clearcoatRoughnessFactor").unwrap_or(0.0);
+        }
+        if let Some(value) = extension("KHR_materials_specular") {
+            ext.specular = json_f32(value, "specularFactor").unwrap_or(1.0);
+            ext.specular_color = json_vec(value, "specularColorFactor").unwrap_or([1.0; 3]);
+        }
+        if let Some(value) = extension("KHR_materials_sheen") {
+            ext.sheen_color = json_vec(value, "sheenColorFactor").unwrap_or([0.0; 3]);
+            ext.sheen_roughness = json_f32(value, "sheenRoughnessFactor").unwrap_or(0.0);
+        }
+        if let Some(value) = extension("KHR_materials_emissive_strength") {
+            ext.emissive_strength = json_f32(value, "emissiveStrength").unwrap_or(1.0);
+        }
+
+        const TRANSFORM: &str = "KHR_texture_transform";
+        let pbr = material.pbr_metallic_roughness();
+        let transforms = [
+            pbr.base_color_texture().and_then(|info| info.extension_value(TRANSFORM).cloned()),
+            pbr.metallic_roughness_texture().and_then(|info| info.extension_value(TRANSFORM).cloned()),
+            material.normal_texture().and_then(|info| info.extension_value(TRANSFORM).cloned()),
+            material.occlusion_texture().and_then(|info| info.extension_value(TRANSFORM).cloned()),
+            material.emissive_texture().and_then(|info| info.extension_value(TRANSFORM).cloned()),
+        ];
+        for (transform, value) in ext.texture_transforms.iter_mut().zip(transforms) {
+            if let Some(value) = value {
+                *transform = TextureTransform::from_json(&value);
+            }
+        }
+        ext
+    }
+
+    /// F0 диэлектрика с учётом IOR и `KHR_materials_specular`
+    pub fn dielectric_f0(&self) -> [f32; 3] {
+        let f0 = ((self.ior - 1.0) / (self.ior + 1.0)).powi(2);
+        self.specular_color.map(|c| (f0 * c).min(1.0) * self.specular)
+    }
+}
+
+/// Текстуры 1x1, подставляемые вместо отсутствующих текстур материала
+#[derive(Debug, Clone)]
+pub struct DefaultTextures {
+    /// Белая: нейтральна для base color, metallic-roughness, occlusion и emissive
+    pub white: GpuTextureHandle,
+    /// Плоская нормаль `(0.5, 0.5, 1.0)`
+    pub normal: GpuTextureHandle,
+}
+
+impl DefaultTextures {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        textures: &mut Storage<GpuTexture>,
+    ) -> Result<Self, AssetError> {
+        let mut create = |pixel: [u8; 4], kind: TextureKind, label: &str| {
+            let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba(pixel)));
+            let sampler = device.create_sampler(&crate::res::texture::gpu_texture::default_sampler_descriptor());
+            let texture = GpuTexture::from_image_with_sampler(device, queue, &image, kind, sampler, false, Some(label))?;
+            textures.load(texture)
+        };
+        Ok(Self {
+            white: create([255, 255, 255, 255], TextureKind::Data, "default_white_texture")?,
+            normal: create([128, 128, 255, 255], TextureKind::Normal, "default_normal_texture")?,
+        })
+    }
+}
 
 /// Материал для рендеринга, содержащий параметры PBR и текстуры
 #[derive(Debug, Clone)]
@@ -18,16 +247,93 @@ pub struct Material {
     pub metallic: f32,
     /// Шероховатость (0.0 - 1.0)
     pub roughness: f32,
+    /// Текстура металличности (канал B) и шероховатости (канал G)
+    pub metallic_roughness_texture: Option<GpuTextureHandle>,
+    /// Карта нормалей в касательном пространстве
+    pub normal_texture: Option<GpuTextureHandle>,
+    /// Множитель XY нормали из карты
+    pub normal_scale: f32,
+    /// Карта затенения (канал R)
+    pub occlusion_texture: Option<GpuTextureHandle>,
+    /// Сила затенения (0.0 - 1.0)
+    pub occlusion_strength: f32,
+    /// Излучаемый цвет (линейный RGB)
+    pub emissive: [f32; 3],
+    pub emissive_texture: Option<GpuTextureHandle>,
+    pub alpha_mode: AlphaMode,
+    /// Порог отсечения для [`AlphaMode::Mask`]
+    pub alpha_cutoff: f32,
+    /// Отключает отсечение задних граней
+    pub double_sided: bool,
+    /// Набор UV (`texCoord`) каждой текстуры по [`TextureSlot`]; шейдер
+    /// различает TEXCOORD_0 и TEXCOORD_1
+    pub tex_coords: [u32; TextureSlot::ALL.len()],
+    /// Расширения KHR_materials_* и KHR_texture_transform
+    pub extensions: MaterialExtensions,
     /// Bind group для шейдера
     pub bind_group: Option<wgpu::BindGroup>,
 }
 
+impl Default for Material {
+    /// Материал со значениями по умолчанию из спецификации glTF
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            base_color: [1.0; 4],
+            base_color_texture: None,
+            metallic: 1.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive: [0.0; 3],
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+            tex_coords: [0; TextureSlot::ALL.len()],
+            extensions: MaterialExtensions::default(),
+            bind_group: None,
+        }
+    }
+}
+
+/// Параметры материала для uniform-буфера шейдера (`Material` в `shaders/material.wgsl`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MaterialUniform {
+    pub base_color: [f32; 4],
+    /// Излучение, уже умноженное на `emissive_strength`
+    pub emissive: [f32; 3],
+    pub normal_scale: f32,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub occlusion_strength: f32,
+    /// Порог отсечения; 0 для режимов без отсечения
+    pub alpha_cutoff: f32,
+    pub sheen_color: [f32; 3],
+    pub sheen_roughness: f32,
+    /// F0 диэлектрика с учётом IOR и specular
+    pub specular_f0: [f32; 3],
+    pub transmission: f32,
+    pub clearcoat: f32,
+    pub clearcoat_roughness: f32,
+    pub ior: f32,
+    /// Бит [`TextureSlot`] установлен, если текстура читается по TEXCOORD_1
+    pub uv_sets: u32,
+    /// Строки матриц преобразования UV по [`TextureSlot`], по две на
+    /// текстуру (третья всегда `0, 0, 1`)
+    pub uv_transforms: [[f32; 4]; 2 * TextureSlot::ALL.len()],
+}
+
 impl super::Resource for Material {
     type Key = super::MaterialKey;
     type LoadParams = Material;
     
     /// Загружает материал из параметров
-    fn load(params: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>> {
+    fn load(params: Self::LoadParams) -> Result<Self, AssetError> {
         Ok(params) // Просто возвращаем готовый материал
     }
 }
@@ -41,13 +347,15 @@ impl Material {
     ///
     /// # Аргументы
     /// * `device` - GPU устройство
-    /// * `layout` - Layout bind group
+    /// * `layout` - Layout bind group из кэша ([`get_texture_bind_group_layout`])
     /// * `texture_view` - Вью текстуры (если есть)
     /// * `sampler` - Сэмплер (если есть)
+    ///
+    /// [`get_texture_bind_group_layout`]: super::texture::gpu_texture::get_texture_bind_group_layout
     pub fn create_bind_group(
         &mut self,
         device: &Device,
-        layout: Option<&BindGroupLayout>,
+        layout: &BindGroupLayout,
         texture_view: Option<&wgpu::TextureView>,
         sampler: Option<&wgpu::Sampler>,
     ) {
@@ -66,22 +374,12 @@ impl Material {
             ]);
         }
 
-        match layout {
-            Some(layout) => {if !entries.is_empty() {
-                self.bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
-                    label: Some(&format!("{}_bind_group", self.name)),
-                    layout,
-                    entries: &entries,
-                }));
-            }},
-            None => {
-                let layout = get_texture_bind_group_layout(device);
-                self.bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
-                    label: Some(&format!("{}_bind_group", self.name)),
-                    layout: &layout,
-                    entries: &entries,
-                }));
-            },
+        if !entries.is_empty() {
+            self.bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
+                label: Some(&format!("{}_bind_group", self.name)),
+                layout,
+                entries: &entries,
+            }));
         }
     }
 
@@ -97,47 +395,239 @@ impl Material {
             base_color_texture: Some(texture),
             metallic: pbr.metallic_factor(),
             roughness: pbr.roughness_factor(),
-            bind_group: None,
+            ..Default::default()
         }
     }
- 
-    /// Создаёт материал из GLTF материала, выбирая текстуру из списка
-    pub fn from_gltf_material(
+
+    /// Создаёт материал со всеми входами glTF PBR. `load_texture` загружает
+    /// текстуру glTF с нужным назначением; отсутствующие текстуры заменяются
+    /// на `defaults`, поэтому у полученного материала все текстуры заданы.
+    pub fn from_gltf(
         material: &gltf::Material,
-        textures: &[Handle<GpuTexture>],
-    ) -> Self {
+        defaults: &DefaultTextures,
+        mut load_texture: impl FnMut(gltf::Texture<'_>, TextureKind) -> Result<GpuTextureHandle, AssetError>,
+    ) -> Result<Self, AssetError> {
         let pbr = material.pbr_metallic_roughness();
-        Self {
+        let mut texture_or = |texture: Option<gltf::Texture<'_>>, kind: TextureKind, default: &GpuTextureHandle| {
+            let Some(texture) = texture else {
+                return Ok(Some(default.clone()));
+            };
+            let index = texture.index();
+            load_texture(texture, kind)
+                .map(Some)
+                .map_err(|err| AssetError::Texture { index, source: Box::new(err) })
+        };
+
+        let normal = material.normal_texture();
+        let occlusion = material.occlusion_texture();
+        let extensions = MaterialExtensions::from_gltf(material);
+        let mut tex_coords = [
+            pbr.base_color_texture().map_or(0, |info| info.tex_coord()),
+            pbr.metallic_roughness_texture().map_or(0, |info| info.tex_coord()),
+            normal.as_ref().map_or(0, |normal| normal.tex_coord()),
+            occlusion.as_ref().map_or(0, |occlusion| occlusion.tex_coord()),
+            material.emissive_texture().map_or(0, |info| info.tex_coord()),
+        ];
+        // `texCoord` из KHR_texture_transform заменяет набор из textureInfo
+        for (tex_coord, transform) in tex_coords.iter_mut().zip(&extensions.texture_transforms) {
+            *tex_coord = transform.tex_coord.unwrap_or(*tex_coord);
+        }
+        Ok(Self {
             name: material.name().unwrap_or_default().to_string(),
             base_color: pbr.base_color_factor(),
-            base_color_texture: pbr.base_color_texture()
-                .and_then(|tex| textures.get(tex.texture().index()))
-                .cloned(),
+            base_color_texture: texture_or(
+                pbr.base_color_texture().map(|info| info.texture()),
+                TextureKind::Color,
+                &defaults.white,
+            )?,
             metallic: pbr.metallic_factor(),
             roughness: pbr.roughness_factor(),
+            metallic_roughness_texture: texture_or(
+                pbr.metallic_roughness_texture().map(|info| info.texture()),
+                TextureKind::Data,
+                &defaults.white,
+            )?,
+            normal_scale: normal.as_ref().map_or(1.0, |normal| normal.scale()),
+            normal_texture: texture_or(normal.map(|normal| normal.texture()), TextureKind::Normal, &defaults.normal)?,
+            occlusion_strength: occlusion.as_ref().map_or(1.0, |occlusion| occlusion.strength()),
+            occlusion_texture: texture_or(
+                occlusion.map(|occlusion| occlusion.texture()),
+                TextureKind::Data,
+                &defaults.white,
+            )?,
+            emissive: material.emissive_factor(),
+            emissive_texture: texture_or(
+                material.emissive_texture().map(|info| info.texture()),
+                TextureKind::Color,
+                &defaults.white,
+            )?,
+            alpha_mode: material.alpha_mode().into(),
+            alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            double_sided: material.double_sided(),
+            tex_coords,
+            extensions,
             bind_group: None,
+        })
+    }
+
+    /// Материал по умолчанию с текстурами-заглушками
+    pub fn default_with_textures(defaults: &DefaultTextures) -> Self {
+        Self {
+            name: "default".to_string(),
+            base_color_texture: Some(defaults.white.clone()),
+            metallic_roughness_texture: Some(defaults.white.clone()),
+            normal_texture: Some(defaults.normal.clone()),
+            occlusion_texture: Some(defaults.white.clone()),
+            emissive_texture: Some(defaults.white.clone()),
+            ..Default::default()
         }
     }
-}
 
+    /// Режим, в котором материал рисуется растеризатором: пропускание света
+    /// приближается прозрачностью (`material_alpha`) и требует смешивания
+    pub fn render_alpha_mode(&self) -> AlphaMode {
+        if self.extensions.transmission > 0.0 {
+            AlphaMode::Blend
+        } else {
+            self.alpha_mode
+        }
+    }
 
-#[derive(Debug)]
-pub struct LoadMaterialError {
-    message: String,
-}
+    pub fn uniform(&self) -> MaterialUniform {
+        let ext = &self.extensions;
+        let mut uv_transforms = [[0.0; 4]; 2 * TextureSlot::ALL.len()];
+        for (rows, transform) in uv_transforms.chunks_exact_mut(2).zip(&ext.texture_transforms) {
+            let uv = transform.matrix();
+            rows[0] = [uv.x_axis.x, uv.y_axis.x, uv.z_axis.x, 0.0];
+            rows[1] = [uv.x_axis.y, uv.y_axis.y, uv.z_axis.y, 0.0];
+        }
+        MaterialUniform {
+            base_color: self.base_color,
+            emissive: self.emissive.map(|c| c * ext.emissive_strength),
+            normal_scale: self.normal_scale,
+            metallic: self.metallic,
+            roughness: self.roughness,
+            occlusion_strength: self.occlusion_strength,
+            alpha_cutoff: match self.alpha_mode {
+                AlphaMode::Mask => self.alpha_cutoff,
+                AlphaMode::Opaque | AlphaMode::Blend => 0.0,
+            },
+            sheen_color: ext.sheen_color,
+            sheen_roughness: ext.sheen_roughness,
+            specular_f0: ext.dielectric_f0(),
+            transmission: ext.transmission,
+            clearcoat: ext.clearcoat,
+            clearcoat_roughness: ext.clearcoat_roughness,
+            ior: ext.ior,
+            uv_sets: self.uv_sets(),
+            uv_transforms,
+        }
+    }
 
-impl fmt::Display for LoadMaterialError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Material loading error: {}", self.message)  // Исправлено сообщение
+    /// Маска [`MaterialUniform::uv_sets`]; наборы после второго не загружаются
+    /// и читаются как TEXCOORD_0
+    pub fn uv_sets(&self) -> u32 {
+        TextureSlot::ALL
+            .iter()
+            .filter(|slot| self.tex_coords[**slot as usize] == 1)
+            .fold(0, |mask, slot| mask | 1 << *slot as u32)
     }
-}
 
-impl Error for LoadMaterialError {}
+    /// Ближайший материал трассировщика лучей: излучающий, стекло,
+    /// металл или ламбертовский, по убыванию приоритета
+    pub fn ray_cast_material(&self) -> RayCastMaterial {
+        let ext = &self.extensions;
+        let emissive = glam::Vec3::from(self.emissive) * ext.emissive_strength;
+        let albedo = glam::Vec3::new(self.base_color[0], self.base_color[1], self.base_color[2]);
+
+        if emissive.max_element() > 0.0 {
+            RayCastMaterial::Emissive {
+                emit: Texture::new_from_color(emissive),
+            }
+        } else if ext.transmission >= 0.5 {
+            RayCastMaterial::Dielectric {
+                refraction_index: ext.ior,
+            }
+        } else if self.metallic >= 0.5 {
+            RayCastMaterial::Metal {
+                albedo: Texture::new_from_color(albedo),
+                fuzz: self.roughness * self.roughness,
+            }
+        } else {
+            RayCastMaterial::Lambertian {
+                albedo: Texture::new_from_color(albedo),
+            }
+        }
+    }
 
-impl LoadMaterialError {
-    pub fn new(message: impl Into<String>) -> Self {
+    /// Bind group группы 3 для [`PipelineType::Lit`](crate::core::PipelineType::Lit):
+    /// текстуры PBR, сэмплер базового цвета и uniform материала. `None`, если
+    /// какой-то текстуры нет; [`Material::from_gltf`] подставляет вместо
+    /// отсутствующих [`DefaultTextures`]
+    pub fn create_pbr_bind_group(
+        &self,
+        device: &Device,
+        layout: &BindGroupLayout,
+        textures: &Storage<GpuTexture>,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> Option<wgpu::BindGroup> {
+        let texture = |handle: &Option<GpuTextureHandle>| textures.get(handle.clone()?);
+        let base_color = texture(&self.base_color_texture)?;
+        let metallic_roughness = texture(&self.metallic_roughness_texture)?;
+        let normal = texture(&self.normal_texture)?;
+        let occlusion = texture(&self.occlusion_texture)?;
+        let emissive = texture(&self.emissive_texture)?;
+        Some(device.create_bind_group(&BindGroupDescriptor {
+            label: Some(&format!("{}_pbr_bind_group", self.name)),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&base_color.view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&base_color.sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::TextureView(&metallic_roughness.view),
+                },
+                BindGroupEntry {
+                    binding: 4,
+                    resource: BindingResource::TextureView(&normal.view),
+                },
+                BindGroupEntry {
+                    binding: 5,
+                    resource: BindingResource::TextureView(&occlusion.view),
+                },
+                BindGroupEntry {
+                    binding: 6,
+                    resource: BindingResource::TextureView(&emissive.view),
+                },
+            ],
+        }))
+    }
+ 
+    /// Создаёт материал из GLTF материала, выбирая текстуру из списка
+    pub fn from_gltf_material(
+        material: &gltf::Material,
+        textures: &[Handle<GpuTexture>],
+    ) -> Self {
+        let pbr = material.pbr_metallic_roughness();
         Self {
-            message: message.into(),
+            name: material.name().unwrap_or_default().to_string(),
+            base_color: pbr.base_color_factor(),
+            base_color_texture: pbr.base_color_texture()
+                .and_then(|tex| textures.get(tex.texture().index()))
+                .cloned(),
+            metallic: pbr.metallic_factor(),
+            roughness: pbr.roughness_factor(),
+            ..Default::default()
         }
     }
 }
@@ -225,3 +715,260 @@ pub enum RayCastMaterial {
     Checkerboard { even: Texture, odd: Texture },
     Emissive { emit: Texture },
 }
+
+
+/// Записи layout'а материала: текстура базового цвета (binding 0),
+/// сэмплер (binding 1), [`MaterialUniform`] (binding 2), metallic-roughness (3),
+/// нормали (4), затенение (5) и излучение (6) с тем же сэмплером
+pub const MATERIAL_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 2,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 3,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 4,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 5,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 6,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use slotmap::SlotMap;
+
+    use super::*;
+    use crate::res::TextureKey;
+
+    fn gltf_materials() -> gltf::Gltf {
+        gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "images": [{ "uri": "a.png" }, { "uri": "b.png" }],
+                "textures": [{ "source": 0 }, { "source": 1 }],
+                "materials": [
+                    {
+                        "name": "full",
+                        "pbrMetallicRoughness": {
+                            "baseColorTexture": { "index": 0 },
+                            "metallicRoughnessTexture": { "index": 1 },
+                            "metallicFactor": 0.25
+                        },
+                        "normalTexture": { "index": 1, "scale": 0.5 },
+                        "occlusionTexture": { "index": 1, "strength": 0.75, "texCoord": 1 },
+                        "emissiveFactor": [1.0, 0.5, 0.0],
+                        "alphaMode": "MASK",
+                        "alphaCutoff": 0.3,
+                        "doubleSided": true
+                    },
+                    {
+                        "name": "plain",
+                        "pbrMetallicRoughness": { "baseColorFactor": [0.8, 0.8, 0.8, 1.0] }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_gltf_material() {
+        let mut keys: SlotMap<TextureKey, ()> = SlotMap::with_key();
+        let defaults = DefaultTextures {
+            white: Handle::new(keys.insert(())),
+            normal: Handle::new(keys.insert(())),
+        };
+        let loaded: Vec<GpuTextureHandle> = (0..2).map(|_| Handle::new(keys.insert(()))).collect();
+        let mut requests = Vec::new();
+
+        let gltf = gltf_materials();
+        let materials: Vec<Material> = gltf
+            .materials()
+            .map(|material| {
+                Material::from_gltf(&material, &defaults, |texture, kind| {
+                    requests.push((texture.index(), kind));
+                    Ok(loaded[texture.index()].clone())
+                })
+                .unwrap()
+            })
+            .collect();
+
+        let full = &materials[0];
+        assert_eq!(full.base_color_texture.as_ref().map(|h| h.key()), Some(loaded[0].key()));
+        assert_eq!(full.normal_texture.as_ref().map(|h| h.key()), Some(loaded[1].key()));
+        assert_eq!(full.emissive_texture.as_ref().map(|h| h.key()), Some(defaults.white.key()));
+        assert_eq!((full.normal_scale, full.occlusion_strength, full.metallic), (0.5, 0.75, 0.25));
+        assert_eq!(full.emissive, [1.0, 0.5, 0.0]);
+        assert_eq!((full.alpha_mode, full.alpha_cutoff, full.double_sided), (AlphaMode::Mask, 0.3, true));
+        assert_eq!(full.uniform().alpha_cutoff, 0.3);
+        // Запечённое затенение читается по второму набору UV
+        assert_eq!(full.tex_coords, [0, 0, 0, 1, 0]);
+        assert_eq!(full.uniform().uv_sets, 1 << TextureSlot::Occlusion as u32);
+        assert_eq!(
+            requests,
+            vec![
+                (0, TextureKind::Color),
+                (1, TextureKind::Data),
+                (1, TextureKind::Normal),
+                (1, TextureKind::Data),
+            ]
+        );
+
+        // Материал без текстур получает заглушки вместо ошибки
+        let plain = &materials[1];
+        assert_eq!(plain.base_color_texture.as_ref().map(|h| h.key()), Some(defaults.white.key()));
+        assert_eq!(plain.normal_texture.as_ref().map(|h| h.key()), Some(defaults.normal.key()));
+        assert_eq!(plain.alpha_mode, AlphaMode::Opaque);
+        assert_eq!(plain.uniform().alpha_cutoff, 0.0);
+        assert_eq!(plain.uniform().uv_sets, 0);
+        assert_eq!(plain.extensions, MaterialExtensions::default());
+    }
+
+    #[test]
+    fn test_material_extensions() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "images": [{ "uri": "a.png" }],
+                "textures": [{ "source": 0 }],
+                "materials": [{
+                    "pbrMetallicRoughness": {
+                        "baseColorTexture": {
+                            "index": 0,
+                            "extensions": {
+                                "KHR_texture_transform": { "offset": [0.5, 0.0], "scale": [2.0, 2.0], "texCoord": 1 }
+                            }
+                        },
+                        "metallicFactor": 0.0
+                    },
+                    "normalTexture": { "index": 0 },
+                    "emissiveFactor": [1.0, 1.0, 1.0],
+                    "extensions": {
+                        "KHR_materials_transmission": { "transmissionFactor": 1.0 },
+                        "KHR_materials_ior": { "ior": 1.33 },
+                        "KHR_materials_clearcoat": { "clearcoatFactor": 0.8, "clearcoatRoughnessFactor": 0.1 },
+                        "KHR_materials_specular": { "specularFactor": 0.5, "specularColorFactor": [1.0, 0.5, 0.5] },
+                        "KHR_materials_sheen": { "sheenColorFactor": [0.2, 0.3, 0.4], "sheenRoughnessFactor": 0.6 },
+                        "KHR_materials_emissive_strength": { "emissiveStrength": 5.0 }
+                    }
+                }]
+            }"#,
+        )
+        .unwrap();
+        let gltf_material = gltf.materials().next().unwrap();
+        let ext = MaterialExtensions::from_gltf(&gltf_material);
+
+        assert_eq!((ext.transmission, ext.ior), (1.0, 1.33));
+        assert_eq!((ext.clearcoat, ext.clearcoat_roughness), (0.8, 0.1));
+        assert_eq!((ext.specular, ext.specular_color), (0.5, [1.0, 0.5, 0.5]));
+        assert_eq!((ext.sheen_color, ext.sheen_roughness), ([0.2, 0.3, 0.4], 0.6));
+        assert_eq!(ext.emissive_strength, 5.0);
+        let base_color = ext.texture_transforms[TextureSlot::BaseColor as usize];
+        assert_eq!(base_color.tex_coord, Some(1));
+        assert_eq!(base_color.apply([0.25, 0.5]), [1.0, 1.0]);
+        // Карта нормалей без расширения не сдвигается
+        assert_eq!(ext.texture_transforms[TextureSlot::Normal as usize], TextureTransform::default());
+
+        let mut keys: SlotMap<TextureKey, ()> = SlotMap::with_key();
+        let defaults = DefaultTextures {
+            white: Handle::new(keys.insert(())),
+            normal: Handle::new(keys.insert(())),
+        };
+        let imported = Material::from_gltf(&gltf_material, &defaults, |_, _| Ok(defaults.white.clone())).unwrap();
+        // texCoord расширения переключает базовый цвет на TEXCOORD_1
+        assert_eq!(imported.tex_coords, [1, 0, 0, 0, 0]);
+        let uniform = imported.uniform();
+        assert_eq!(uniform.uv_sets, 1 << TextureSlot::BaseColor as u32);
+        assert_eq!(uniform.uv_transforms[0], [2.0, 0.0, 0.5, 0.0]);
+        assert_eq!(uniform.uv_transforms[2 * TextureSlot::Normal as usize], [1.0, 0.0, 0.0, 0.0]);
+
+        // Для IOR 1.5 F0 = 0.04
+        assert!((MaterialExtensions::default().dielectric_f0()[0] - 0.04).abs() < 1e-6);
+
+        let mut material = Material {
+            emissive: [1.0; 3],
+            extensions: ext,
+            ..Default::default()
+        };
+        assert_eq!(material.uniform().emissive, [5.0; 3]);
+        assert_eq!(material.render_alpha_mode(), AlphaMode::Blend);
+        assert!(matches!(material.ray_cast_material(), RayCastMaterial::Emissive { .. }));
+        material.emissive = [0.0; 3];
+        assert!(matches!(
+            material.ray_cast_material(),
+            RayCastMaterial::Dielectric { refraction_index } if refraction_index == 1.33
+        ));
+        material.extensions.transmission = 0.0;
+        assert_eq!(material.render_alpha_mode(), AlphaMode::Opaque);
+        assert!(matches!(material.ray_cast_material(), RayCastMaterial::Metal { .. }));
+        material.metallic = 0.0;
+        assert!(matches!(material.ray_cast_material(), RayCastMaterial::Lambertian { .. }));
+    }
+
+    #[test]
+    fn test_texture_transform_rotation() {
+        // Поворот на 90°: ось U переходит в -V
+        let transform = TextureTransform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            ..Default::default()
+        };
+        let [u, v] = transform.apply([1.0, 0.0]);
+        assert!(u.abs() < 1e-6 && (v + 1.0).abs() < 1e-6, "{} {}", u, v);
+    }
+}
@@ -0,0 +1,366 @@
+//! Атрибуты вершин примитива glTF на CPU.
+//!
+//! Обязательна только позиция. Недостающее достраивается: нормали (плоские
+//! или сглаженные), касательные по MikkTSpace, нулевые UV; без индексов
+//! вершины берутся по порядку, полосы и веера переводятся в списки.
+
+use glam::Vec3;
+use gltf::{mesh::Mode, Primitive};
+
+use crate::res::{
+    error::AssetError,
+    vertex::{normalize_weights, VertexAttribute, VertexLayout},
+};
+
+/// Нормаль вырожденного треугольника и вершин без треугольников
+pub(super) const DEFAULT_NORMAL: [f32; 3] = [0.0, 0.0, 1.0];
+
+/// Как строить нормали, если в примитиве их нет
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NormalGeneration {
+    /// Нормаль грани, вершины треугольников разделяются (требование glTF)
+    #[default]
+    Flat,
+    /// Среднее нормалей соседних граней, взвешенное по площади
+    Smooth,
+}
+
+/// Атрибуты вершин по отдельным массивам одинаковой длины
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexStreams {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tex_coords0: Option<Vec<[f32; 2]>>,
+    pub tex_coords1: Option<Vec<[f32; 2]>>,
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub colors: Option<Vec<[f32; 4]>>,
+    /// Суставы и веса есть только вместе
+    pub joints: Option<Vec<[u16; 4]>>,
+    pub weights: Option<Vec<[f32; 4]>>,
+}
+
+impl VertexStreams {
+    /// Читает атрибуты примитива; у всех должно быть столько же элементов, сколько позиций
+    pub fn from_gltf_primitive(primitive: &Primitive, buffers: &[Vec<u8>]) -> Result<Self, AssetError> {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+        let positions: Vec<[f32; 3]> = reader
+            .read_positions()
+            .ok_or_else(|| AssetError::invalid("no positions in primitive"))?
+            .collect();
+
+        let count = positions.len();
+        fn check<T>(values: Vec<T>, count: usize, attribute: &str) -> Result<Vec<T>, AssetError> {
+            if values.len() == count {
+                Ok(values)
+            } else {
+                Err(AssetError::invalid(format!(
+                    "{} has {} values for {} positions",
+                    attribute,
+                    values.len(),
+                    count
+                )))
+            }
+        }
+        let (joints, weights) = match (reader.read_joints(0), reader.read_weights(0)) {
+            (Some(joints), Some(weights)) => (
+                Some(check(joints.into_u16().collect(), count, "JOINTS_0")?),
+                Some(check(weights.into_f32().map(normalize_weights).collect(), count, "WEIGHTS_0")?),
+            ),
+            _ => (None, None),
+        };
+
+        Ok(Self {
+            normals: reader.read_normals().map(|values| check(values.collect(), count, "NORMAL")).transpose()?,
+            tex_coords0: reader
+                .read_tex_coords(0)
+                .map(|values| check(values.into_f32().collect(), count, "TEXCOORD_0"))
+                .transpose()?,
+            tex_coords1: reader
+                .read_tex_coords(1)
+                .map(|values| check(values.into_f32().collect(), count, "TEXCOORD_1"))
+                .transpose()?,
+            tangents: reader.read_tangents().map(|values| check(values.collect(), count, "TANGENT")).transpose()?,
+            colors: reader
+                .read_colors(0)
+                .map(|values| check(values.into_rgba_f32().collect(), count, "COLOR_0"))
+                .transpose()?,
+            joints,
+            weights,
+            positions,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Раскладка вершинного буфера: позиция, нормаль и первые UV есть всегда
+    /// (при отсутствии заполняются значениями по умолчанию), остальное — если прочитано
+    pub fn layout(&self) -> VertexLayout {
+        let mut attributes = vec![VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::TexCoord0];
+        if self.tangents.is_some() {
+            attributes.push(VertexAttribute::Tangent);
+        }
+        if self.colors.is_some() {
+            attributes.push(VertexAttribute::Color);
+        }
+        if self.tex_coords1.is_some() {
+            attributes.push(VertexAttribute::TexCoord1);
+        }
+        if self.joints.is_some() && self.weights.is_some() {
+            attributes.extend([VertexAttribute::Joints, VertexAttribute::Weights]);
+        }
+        VertexLayout::new(&attributes)
+    }
+
+    /// Данные вершинного буфера в раскладке `layout`
+    pub fn to_bytes(&self, layout: &VertexLayout) -> Vec<u8> {
+        fn push<T: bytemuck::Pod>(data: &mut Vec<u8>, stream: &Option<Vec<T>>, index: usize, default: T) {
+            let value = stream.as_ref().map_or(default, |values| values[index]);
+            data.extend_from_slice(bytemuck::bytes_of(&value));
+        }
+
+        let mut data = Vec::with_capacity(self.len() * layout.stride() as usize);
+        for index in 0..self.len() {
+            for attribute in layout.attributes() {
+                match attribute {
+                    VertexAttribute::Position => data.extend_from_slice(bytemuck::bytes_of(&self.positions[index])),
+                    VertexAttribute::Normal => push(&mut data, &self.normals, index, DEFAULT_NORMAL),
+                    VertexAttribute::TexCoord0 => push(&mut data, &self.tex_coords0, index, [0.0; 2]),
+                    VertexAttribute::TexCoord1 => push(&mut data, &self.tex_coords1, index, [0.0; 2]),
+                    VertexAttribute::Tangent => push(&mut data, &self.tangents, index, [1.0, 0.0, 0.0, 1.0]),
+                    VertexAttribute::Color => push(&mut data, &self.colors, index, [1.0; 4]),
+                    VertexAttribute::Joints => push(&mut data, &self.joints, index, [0; 4]),
+                    VertexAttribute::Weights => push(&mut data, &self.weights, index, [1.0, 0.0, 0.0, 0.0]),
+                }
+            }
+        }
+        data
+    }
+
+    /// Копия, в которой у каждого индекса своя вершина
+    pub fn unweld(&self, indices: &[u32]) -> Self {
+        fn pick<T: Copy>(values: &[T], indices: &[u32]) -> Vec<T> {
+            indices.iter().map(|&index| values[index as usize]).collect()
+        }
+        fn pick_stream<T: Copy>(stream: &Option<Vec<T>>, indices: &[u32]) -> Option<Vec<T>> {
+            stream.as_deref().map(|values| pick(values, indices))
+        }
+
+        Self {
+            positions: pick(&self.positions, indices),
+            normals: pick_stream(&self.normals, indices),
+            tex_coords0: pick_stream(&self.tex_coords0, indices),
+            tex_coords1: pick_stream(&self.tex_coords1, indices),
+            tangents: pick_stream(&self.tangents, indices),
+            colors: pick_stream(&self.colors, indices),
+            joints: pick_stream(&self.joints, indices),
+            weights: pick_stream(&self.weights, indices),
+        }
+    }
+
+    /// Нормали граней; вершины, общие для нескольких треугольников, получают
+    /// нормаль последнего, поэтому обычно вызывается после [`Self::unweld`]
+    pub fn generate_flat_normals(&mut self, indices: &[u32]) {
+        let mut normals = vec![DEFAULT_NORMAL; self.len()];
+        for triangle in indices.chunks_exact(3) {
+            let normal = self.face_normal(triangle).normalize_or(Vec3::from(DEFAULT_NORMAL));
+            for &index in triangle {
+                normals[index as usize] = normal.into();
+            }
+        }
+        self.normals = Some(normals);
+    }
+
+    /// Нормали вершин как сумма нормалей соседних граней, взвешенных по площади
+    pub fn generate_smooth_normals(&mut self, indices: &[u32]) {
+        let mut sums = vec![Vec3::ZERO; self.len()];
+        for triangle in indices.chunks_exact(3) {
+            // Длина векторного произведения равна удвоенной площади
+            let normal = self.face_normal(triangle);
+            for &index in triangle {
+                sums[index as usize] += normal;
+            }
+        }
+        let normals = sums
+            .into_iter()
+            .map(|sum| sum.normalize_or(Vec3::from(DEFAULT_NORMAL)).into())
+            .collect();
+        self.normals = Some(normals);
+    }
+
+    /// Касательные по MikkTSpace для UV из набора `tex_coord`; без нормалей
+    /// или этих UV ничего не делает. `false`, если построить не удалось.
+    pub fn generate_tangents(&mut self, indices: &[u32], tex_coord: u32) -> bool {
+        let tex_coords = match tex_coord {
+            0 => self.tex_coords0.as_deref(),
+            1 => self.tex_coords1.as_deref(),
+            _ => None,
+        };
+        let (Some(normals), Some(tex_coords)) = (self.normals.as_deref(), tex_coords) else {
+            return false;
+        };
+        let mut geometry = TangentGeometry {
+            positions: &self.positions,
+            normals,
+            tex_coords,
+            indices,
+            tangents: vec![[1.0, 0.0, 0.0, 1.0]; self.positions.len()],
+        };
+        if !bevy_mikktspace::generate_tangents(&mut geometry) {
+            return false;
+        }
+        self.tangents = Some(geometry.tangents);
+        true
+    }
+
+    /// Ненормированная нормаль треугольника
+    fn face_normal(&self, triangle: &[u32]) -> Vec3 {
+        let [a, b, c] = [0, 1, 2].map(|corner| Vec3::from(self.positions[triangle[corner] as usize]));
+        (b - a).cross(c - a)
+    }
+}
+
+/// Индексы в топологии, которую умеет рисовать пайплайн: полосы и веера
+/// треугольников становятся списками, ломаные и замкнутые линии — отрезками
+pub fn convert_topology(mode: Mode, indices: &[u32]) -> (wgpu::PrimitiveTopology, Vec<u32>) {
+    use wgpu::PrimitiveTopology;
+
+    match mode {
+        Mode::Triangles => (PrimitiveTopology::TriangleList, indices.to_vec()),
+        Mode::TriangleStrip => {
+            // Чётность треугольника сохраняет порядок обхода
+            let triangles = indices
+                .windows(3)
+                .enumerate()
+                .flat_map(|(i, window)| match i % 2 {
+                    0 => [window[0], window[1], window[2]],
+                    _ => [window[1], window[0], window[2]],
+                })
+                .collect();
+            (PrimitiveTopology::TriangleList, triangles)
+        }
+        Mode::TriangleFan => {
+            let triangles = match indices.split_first() {
+                Some((&center, rest)) => rest.windows(2).flat_map(|edge| [edge[0], edge[1], center]).collect(),
+                None => Vec::new(),
+            };
+            (PrimitiveTopology::TriangleList, triangles)
+        }
+        Mode::Lines => (PrimitiveTopology::LineList, indices.to_vec()),
+        Mode::LineStrip | Mode::LineLoop => {
+            let mut lines: Vec<u32> = indices.windows(2).flatten().copied().collect();
+            if mode == Mode::LineLoop && indices.len() > 2 {
+                lines.extend([indices[indices.len() - 1], indices[0]]);
+            }
+            (PrimitiveTopology::LineList, lines)
+        }
+        Mode::Points => (PrimitiveTopology::PointList, indices.to_vec()),
+    }
+}
+
+/// Индексированный список треугольников для MikkTSpace
+struct TangentGeometry<'a> {
+    positions: &'a [[f32; 3]],
+    normals: &'a [[f32; 3]],
+    tex_coords: &'a [[f32; 2]],
+    indices: &'a [u32],
+    tangents: Vec<[f32; 4]>,
+}
+
+impl TangentGeometry<'_> {
+    fn index(&self, face: usize, vert: usize) -> usize {
+        self.indices[face * 3 + vert] as usize
+    }
+}
+
+impl bevy_mikktspace::Geometry for TangentGeometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[self.index(face, vert)]
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[self.index(face, vert)]
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.tex_coords[self.index(face, vert)]
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        let index = self.index(face, vert);
+        self.tangents[index] = tangent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quad() -> (VertexStreams, Vec<u32>) {
+        let streams = VertexStreams {
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]],
+            tex_coords0: Some(vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]]),
+            ..Default::default()
+        };
+        (streams, vec![0, 1, 2, 0, 2, 3])
+    }
+
+    #[test]
+    fn test_convert_topology() {
+        let (topology, strip) = convert_topology(Mode::TriangleStrip, &[0, 1, 2, 3]);
+        assert_eq!(topology, wgpu::PrimitiveTopology::TriangleList);
+        assert_eq!(strip, [0, 1, 2, 2, 1, 3]);
+        let (_, fan) = convert_topology(Mode::TriangleFan, &[0, 1, 2, 3]);
+        assert_eq!(fan, [1, 2, 0, 2, 3, 0]);
+        let (topology, lines) = convert_topology(Mode::LineLoop, &[0, 1, 2]);
+        assert_eq!(topology, wgpu::PrimitiveTopology::LineList);
+        assert_eq!(lines, [0, 1, 1, 2, 2, 0]);
+        let (topology, _) = convert_topology(Mode::Points, &[0]);
+        assert_eq!(topology, wgpu::PrimitiveTopology::PointList);
+    }
+
+    #[test]
+    fn test_generated_normals_and_tangents() {
+        let (mut streams, indices) = quad();
+        streams.generate_smooth_normals(&indices);
+        assert!(streams.normals.as_ref().unwrap().iter().all(|normal| *normal == [0.0, 0.0, 1.0]));
+
+        let mut flat = streams.unweld(&indices);
+        flat.generate_flat_normals(&[0, 1, 2, 3, 4, 5]);
+        assert_eq!(flat.len(), 6);
+        assert_eq!(flat.normals.as_ref().unwrap()[4], [0.0, 0.0, 1.0]);
+
+        // U растёт вдоль +X, V (glTF, сверху вниз) — вдоль -Y
+        assert!(streams.generate_tangents(&indices, 0));
+        for tangent in streams.tangents.as_ref().unwrap() {
+            assert!((Vec3::new(tangent[0], tangent[1], tangent[2]) - Vec3::X).length() < 1e-4);
+            assert_eq!(tangent[3].abs(), 1.0);
+        }
+        assert!(!streams.generate_tangents(&indices, 1));
+    }
+
+    #[test]
+    fn test_default_attributes_in_buffer() {
+        let (streams, _) = quad();
+        let layout = streams.layout();
+        assert_eq!(layout, VertexLayout::BASIC);
+
+        let vertices: Vec<crate::res::vertex::Vertex> = bytemuck::pod_collect_to_vec(&streams.to_bytes(&layout));
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(vertices[1].normal, DEFAULT_NORMAL);
+        assert_eq!(vertices[1].tex_coord, [1.0, 1.0]);
+    }
+}
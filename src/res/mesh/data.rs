@@ -0,0 +1,371 @@
+//! Копия геометрии меша на CPU и утилиты над ней.
+//!
+//! Хранит позиции, нормали, первые UV, необязательные касательные и
+//! индексы; утилиты ниже считают, что индексы образуют список треугольников.
+//! По копии выбирает лучом [`Scene::raycast`]; отсечение в
+//! [`SceneRenderer`] и сферы трассировщика берут [`Mesh::bounds`], которые
+//! есть и без копии.
+//!
+//! [`Scene::raycast`]: crate::res::scene::Scene::raycast
+//! [`SceneRenderer`]: crate::core::scene_renderer::SceneRenderer
+//! [`Mesh::bounds`]: super::Mesh::bounds
+
+use std::collections::HashMap;
+use std::iter::Sum;
+use std::ops::Add;
+
+use glam::{Mat3, Mat4, Vec3};
+
+use super::attributes::{NormalGeneration, VertexStreams, DEFAULT_NORMAL};
+use crate::math::bounds::{Aabb, BoundingSphere};
+
+/// Геометрия меша на CPU
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    /// Той же длины, что `positions`
+    pub normals: Vec<[f32; 3]>,
+    /// Той же длины, что `positions`
+    pub tex_coords: Vec<[f32; 2]>,
+    /// `xyz` — касательная, `w` — знак битангенса
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub indices: Vec<u32>,
+}
+
+/// Размер меша
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    /// Треугольники с совпадающими индексами или нулевой площадью
+    pub degenerate_triangles: usize,
+    /// Вершины, на которые не ссылается ни один индекс
+    pub unused_vertices: usize,
+}
+
+impl Add for MeshStats {
+    type Output = MeshStats;
+
+    fn add(self, other: MeshStats) -> MeshStats {
+        MeshStats {
+            vertices: self.vertices + other.vertices,
+            triangles: self.triangles + other.triangles,
+            degenerate_triangles: self.degenerate_triangles + other.degenerate_triangles,
+            unused_vertices: self.unused_vertices + other.unused_vertices,
+        }
+    }
+}
+
+impl Sum for MeshStats {
+    fn sum<I: Iterator<Item = MeshStats>>(iter: I) -> MeshStats {
+        iter.fold(MeshStats::default(), Add::add)
+    }
+}
+
+/// Пересечение луча с треугольником
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// В единицах длины `direction`
+    pub distance: f32,
+    /// Номер треугольника, его индексы — `indices[3 * triangle..][..3]`
+    pub triangle: usize,
+    /// Веса вершин треугольника в точке попадания
+    pub barycentric: Vec3,
+}
+
+impl MeshData {
+    /// Данные из потоков атрибутов; недостающие нормали и UV заполняются
+    /// так же, как в вершинном буфере
+    pub fn from_streams(streams: &VertexStreams, indices: &[u32]) -> Self {
+        let count = streams.len();
+        Self {
+            positions: streams.positions.clone(),
+            normals: streams.normals.clone().unwrap_or_else(|| vec![DEFAULT_NORMAL; count]),
+            tex_coords: streams.tex_coords0.clone().unwrap_or_else(|| vec![[0.0; 2]; count]),
+            tangents: streams.tangents.clone(),
+            indices: indices.to_vec(),
+        }
+    }
+
+    /// Потоки атрибутов для загрузки на GPU
+    pub fn to_streams(&self) -> VertexStreams {
+        VertexStreams {
+            positions: self.positions.clone(),
+            normals: Some(self.normals.clone()),
+            tex_coords0: Some(self.tex_coords.clone()),
+            tangents: self.tangents.clone(),
+            ..Default::default()
+        }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Вершины треугольников по порядку
+    pub fn triangles(&self) -> impl Iterator<Item = [Vec3; 3]> + '_ {
+        self.indices
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| Vec3::from(self.positions[triangle[corner] as usize])))
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::from_points(self.positions.iter().copied().map(Vec3::from))
+    }
+
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        let points: Vec<Vec3> = self.positions.iter().copied().map(Vec3::from).collect();
+        BoundingSphere::from_points(&points)
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        let mut used = vec![false; self.vertex_count()];
+        for &index in &self.indices {
+            used[index as usize] = true;
+        }
+        let degenerate_triangles = self
+            .indices
+            .chunks_exact(3)
+            .zip(self.triangles())
+            .filter(|(indices, [a, b, c])| {
+                indices[0] == indices[1]
+                    || indices[1] == indices[2]
+                    || indices[0] == indices[2]
+                    || (*b - *a).cross(*c - *a).length_squared() == 0.0
+            })
+            .count();
+        MeshStats {
+            vertices: self.vertex_count(),
+            triangles: self.triangle_count(),
+            degenerate_triangles,
+            unused_vertices: used.iter().filter(|used| !**used).count(),
+        }
+    }
+
+    /// Пересчитывает нормали и сбрасывает касательные. [`NormalGeneration::Flat`]
+    /// разделяет вершины треугольников, так что их число становится равным числу индексов.
+    pub fn recompute_normals(&mut self, mode: NormalGeneration) {
+        self.tangents = None;
+        let mut streams = self.to_streams();
+        match mode {
+            NormalGeneration::Flat => {
+                streams = streams.unweld(&self.indices);
+                self.indices = (0..streams.len() as u32).collect();
+                streams.generate_flat_normals(&self.indices);
+            }
+            NormalGeneration::Smooth => streams.generate_smooth_normals(&self.indices),
+        }
+        *self = Self::from_streams(&streams, &self.indices);
+    }
+
+    /// Касательные по MikkTSpace для `tex_coords`; `false`, если построить не удалось
+    pub fn generate_tangents(&mut self) -> bool {
+        let mut streams = self.to_streams();
+        let generated = streams.generate_tangents(&self.indices, 0);
+        if generated {
+            self.tangents = streams.tangents;
+        }
+        generated
+    }
+
+    /// Объединяет вершины, у которых все атрибуты совпадают с
+    /// точностью до `epsilon` (0 — точное совпадение), и убирает вершины без
+    /// индексов. Возвращает, на сколько вершин стало меньше.
+    pub fn weld(&mut self, epsilon: f32) -> usize {
+        let quantize = |value: f32| {
+            if epsilon > 0.0 {
+                (value / epsilon).round() as i64
+            } else {
+                // -0.0 и 0.0 — одна вершина
+                (value + 0.0).to_bits() as i64
+            }
+        };
+
+        let mut welded = MeshData {
+            tangents: self.tangents.as_ref().map(|_| Vec::new()),
+            ..Default::default()
+        };
+        let mut unique = HashMap::new();
+        let mut remap = vec![None; self.vertex_count()];
+        for index in &mut self.indices {
+            let vertex = *index as usize;
+            let new_index = *remap[vertex].get_or_insert_with(|| {
+                let (position, normal, tex_coord) =
+                    (self.positions[vertex], self.normals[vertex], self.tex_coords[vertex]);
+                let tangent = self.tangents.as_ref().map_or([0.0; 4], |tangents| tangents[vertex]);
+                let key = (
+                    position.map(quantize),
+                    normal.map(quantize),
+                    tex_coord.map(quantize),
+                    tangent.map(quantize),
+                );
+                *unique.entry(key).or_insert_with(|| {
+                    welded.positions.push(position);
+                    welded.normals.push(normal);
+                    welded.tex_coords.push(tex_coord);
+                    if let Some(tangents) = &mut welded.tangents {
+                        tangents.push(tangent);
+                    }
+                    welded.positions.len() as u32 - 1
+                })
+            });
+            *index = new_index;
+        }
+
+        let removed = self.vertex_count() - welded.vertex_count();
+        welded.indices = std::mem::take(&mut self.indices);
+        *self = welded;
+        removed
+    }
+
+    /// Дописывает `other`, преобразованный `transform`; зеркальное
+    /// преобразование не выворачивает треугольники наизнанку. Касательные
+    /// остаются, только если они есть у обеих частей.
+    pub fn append(&mut self, other: &MeshData, transform: Mat4) {
+        let offset = self.vertex_count() as u32;
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+        let mirrored = transform.determinant() < 0.0;
+
+        self.positions
+            .extend(other.positions.iter().map(|&position| transform.transform_point3(position.into()).to_array()));
+        self.normals.extend(other.normals.iter().map(|&normal| {
+            (normal_matrix * Vec3::from(normal)).normalize_or(Vec3::from(DEFAULT_NORMAL)).to_array()
+        }));
+        self.tex_coords.extend_from_slice(&other.tex_coords);
+        let transform_tangent = |&[x, y, z, w]: &[f32; 4]| {
+            let tangent = transform.transform_vector3(Vec3::new(x, y, z)).normalize_or(Vec3::X);
+            tangent.extend(if mirrored { -w } else { w }).to_array()
+        };
+        self.tangents = match (self.tangents.take(), &other.tangents) {
+            (Some(mut tangents), Some(other_tangents)) => {
+                tangents.extend(other_tangents.iter().map(transform_tangent));
+                Some(tangents)
+            }
+            // Первая часть задаёт, есть ли касательные
+            (None, Some(other_tangents)) if offset == 0 => Some(other_tangents.iter().map(transform_tangent).collect()),
+            _ => None,
+        };
+        for triangle in other.indices.chunks_exact(3) {
+            let triangle = if mirrored { [triangle[0], triangle[2], triangle[1]] } else { [triangle[0], triangle[1], triangle[2]] };
+            self.indices.extend(triangle.map(|index| index + offset));
+        }
+    }
+
+    /// Один меш из нескольких, каждый со своим преобразованием
+    pub fn merge<'a>(parts: impl IntoIterator<Item = (&'a MeshData, Mat4)>) -> MeshData {
+        let mut merged = MeshData::default();
+        for (part, transform) in parts {
+            merged.append(part, transform);
+        }
+        merged
+    }
+
+    /// Ближайшее пересечение луча с треугольниками (с обеих сторон)
+    pub fn raycast(&self, origin: Vec3, direction: Vec3) -> Option<RayHit> {
+        let mut nearest: Option<RayHit> = None;
+        for (triangle, [a, b, c]) in self.triangles().enumerate() {
+            // Мёллер — Трумбор
+            let (edge1, edge2) = (b - a, c - a);
+            let p = direction.cross(edge2);
+            let det = edge1.dot(p);
+            if det.abs() < f32::EPSILON {
+                continue;
+            }
+            let inv_det = 1.0 / det;
+            let s = origin - a;
+            let u = s.dot(p) * inv_det;
+            if !(0.0..=1.0).contains(&u) {
+                continue;
+            }
+            let q = s.cross(edge1);
+            let v = direction.dot(q) * inv_det;
+            if v < 0.0 || u + v > 1.0 {
+                continue;
+            }
+            let distance = edge2.dot(q) * inv_det;
+            if distance >= 0.0 && nearest.is_none_or(|hit| distance < hit.distance) {
+                nearest = Some(RayHit {
+                    distance,
+                    triangle,
+                    barycentric: Vec3::new(1.0 - u - v, u, v),
+                });
+            }
+        }
+        nearest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Квадрат 1×1 в плоскости XY из двух треугольников с отдельными вершинами
+    fn quad() -> MeshData {
+        let corners = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let uvs = [[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]];
+        let order = [0, 1, 2, 0, 2, 3];
+        MeshData {
+            positions: order.map(|i| corners[i]).to_vec(),
+            normals: vec![[0.0, 0.0, 1.0]; 6],
+            tex_coords: order.map(|i| uvs[i]).to_vec(),
+            tangents: None,
+            indices: (0..6).collect(),
+        }
+    }
+
+    #[test]
+    fn test_weld_and_stats() {
+        let mut mesh = quad();
+        mesh.positions[3][0] = 1e-6;
+        assert_eq!(mesh.weld(0.0), 1);
+        assert_eq!(mesh.weld(1e-4), 1);
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+
+        mesh.positions.push([5.0; 3]);
+        mesh.normals.push([0.0, 0.0, 1.0]);
+        mesh.tex_coords.push([0.0; 2]);
+        mesh.indices.extend([0, 0, 1]);
+        let stats = mesh.stats();
+        assert_eq!(stats, MeshStats { vertices: 5, triangles: 3, degenerate_triangles: 1, unused_vertices: 1 });
+        assert_eq!([stats, stats].into_iter().sum::<MeshStats>().triangles, 6);
+
+        mesh.recompute_normals(NormalGeneration::Flat);
+        assert_eq!(mesh.vertex_count(), 9);
+        assert_eq!(mesh.normals[0], [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_merge_and_bounds() {
+        let quad = quad();
+        let mirror = Mat4::from_translation(Vec3::Z) * Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0));
+        let merged = MeshData::merge([(&quad, Mat4::IDENTITY), (&quad, mirror)]);
+        assert_eq!(merged.vertex_count(), 12);
+        assert_eq!(merged.indices[6..9], [6, 8, 7]);
+        assert_eq!(merged.normals[6], [0.0, 0.0, 1.0]);
+        assert_eq!(merged.aabb(), Aabb::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0)));
+
+        // Обход зеркальной копии даёт ту же нормаль, что и её атрибут
+        let [a, b, c] = merged.triangles().nth(2).unwrap();
+        assert!((b - a).cross(c - a).z > 0.0);
+
+        let sphere = quad.bounding_sphere();
+        assert_eq!(sphere.center, Vec3::new(0.5, 0.5, 0.0));
+        assert!((sphere.radius - 0.5f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_raycast() {
+        let quad = quad();
+        let hit = quad.raycast(Vec3::new(0.25, 0.75, 2.0), Vec3::new(0.0, 0.0, -2.0)).unwrap();
+        assert_eq!(hit.distance, 1.0);
+        assert_eq!(hit.triangle, 1);
+        assert!((hit.barycentric.element_sum() - 1.0).abs() < 1e-6);
+        assert!(quad.raycast(Vec3::new(2.0, 0.5, 1.0), Vec3::NEG_Z).is_none());
+        assert!(quad.raycast(Vec3::new(0.5, 0.5, 1.0), Vec3::Z).is_none());
+    }
+}
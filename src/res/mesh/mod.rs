@@ -0,0 +1,288 @@
+pub mod attributes;
+pub mod data;
+pub mod procedural;
+pub mod simplify;
+
+use glam::Vec3;
+use gltf::Primitive;
+use wgpu::{util::DeviceExt, Device};
+use super::{error::AssetError, material::Material, morph::MorphTargets, vertex::{VertexAttribute, VertexLayout}, Handle, MeshKey, Resource};
+use crate::math::bounds::Aabb;
+
+pub use attributes::NormalGeneration;
+pub use data::{MeshData, MeshStats};
+use attributes::{convert_topology, VertexStreams};
+
+/// Настройки импорта примитивов glTF
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeshImportOptions {
+    /// Как строить нормали примитивов, в которых их нет
+    pub normals: NormalGeneration,
+    /// Сохранять ли копию геометрии на CPU ([`Mesh::data`]) для выбора лучом
+    /// ([`Scene::raycast`](super::scene::Scene::raycast)); границы [`Mesh::bounds`] есть всегда
+    pub keep_data: bool,
+    /// Доли треугольников уровней детализации по убыванию, например
+    /// `[0.5, 0.25, 0.1]`; пусто — без LOD
+    pub lod_ratios: Vec<f32>,
+}
+
+/// Упрощённый вариант меша поверх того же вершинного буфера
+#[derive(Debug, Clone)]
+pub struct MeshLod {
+    /// Доля треугольников исходного меша, которую просили оставить
+    pub ratio: f32,
+    pub indices: Vec<u32>,
+    pub index_buffer: wgpu::Buffer,
+}
+
+/// Меш, содержащий вершины, индексы и идентификатор материала.
+#[derive(Debug, Clone)] 
+pub struct Mesh {
+    pub material: Option<Handle<Material>>,
+    pub indices: Vec<u32>,
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer:  wgpu::Buffer,
+    /// Есть суставы и веса, рисуется пайплайном со скиннингом
+    pub skinned: bool,
+    /// Атрибуты вершин в `vertex_buffer`
+    pub layout: VertexLayout,
+    /// Список треугольников, отрезков или точек
+    pub topology: wgpu::PrimitiveTopology,
+    /// Морф-цели; `vertex_buffer` смешан по весам по умолчанию, узлы со
+    /// своими весами рисуются из [`Mesh::create_morph_buffer`]
+    pub morph_targets: Option<MorphTargets>,
+    /// Границы вершин без морфов и скиннинга, в пространстве меша
+    pub bounds: Aabb,
+    /// Копия геометрии на CPU, если её попросили сохранить
+    pub data: Option<MeshData>,
+    /// Уровни детализации от подробного к грубому, без исходного
+    pub lods: Vec<MeshLod>,
+}
+
+impl Resource for Mesh {
+    type Key = MeshKey;
+    type LoadParams = Mesh;
+    
+    /// Загружает меш из параметров загрузки.
+    ///
+    /// # Пример
+    /// ```ignore
+    /// let mesh = Mesh::load(existing_mesh)?;
+    /// ```
+    fn load(mesh: Self::LoadParams) -> Result<Self, AssetError> {
+        Ok(Self {
+            material: mesh.material,
+            indices: mesh.indices,
+            vertex_buffer: mesh.vertex_buffer,
+            index_buffer: mesh.index_buffer,
+            skinned: mesh.skinned,
+            layout: mesh.layout,
+            topology: mesh.topology,
+            morph_targets: mesh.morph_targets,
+            bounds: mesh.bounds,
+            data: mesh.data,
+            lods: mesh.lods,
+        })
+    }
+}
+
+impl Mesh {
+    
+    /// Создаёт меш из GLTF-примитива. Недостающие нормали строятся по
+    /// `options.normals`, касательные — по MikkTSpace, если у материала есть
+    /// карта нормалей; полосы и веера переводятся в списки треугольников.
+    ///
+    /// # Аргументы
+    /// * `primitive` - GLTF примитив
+    /// * `buffers` - Список буферов GLTF файла
+    /// * `mesh_weights` - Веса морф-целей по умолчанию из меша GLTF
+    /// * `options` - Нормали, копия геометрии и уровни детализации
+    ///
+    /// # Пример
+    /// ```ignore
+    /// let mesh = Mesh::from_gltf_primitive(&primitive, &buffers, None, None, &MeshImportOptions::default(), device)?;
+    /// ```
+    pub fn from_gltf_primitive(
+        primitive: &Primitive,
+        buffers: &[Vec<u8>], 
+        material: Option<Handle<Material>>,
+        mesh_weights: Option<&[f32]>,
+        options: &MeshImportOptions,
+        device: Device,
+    ) -> Result<Self, AssetError> {
+        let mut streams = VertexStreams::from_gltf_primitive(primitive, buffers)?;
+        let vertex_count = streams.len();
+
+        // Без индексов вершины идут по порядку
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertex_count as u32).collect(),
+        };
+        if let Some(index) = indices.iter().find(|&&index| index as usize >= vertex_count) {
+            return Err(AssetError::invalid(format!("index {} out of range for {} vertices", index, vertex_count)));
+        }
+        let (topology, mut indices) = convert_topology(primitive.mode(), &indices);
+
+        let mut morph_targets = MorphTargets::from_gltf_primitive(primitive, buffers, mesh_weights, vertex_count)?;
+        if topology == wgpu::PrimitiveTopology::TriangleList {
+            if streams.normals.is_none() {
+                match options.normals {
+                    NormalGeneration::Flat => {
+                        // У каждого угла треугольника своя вершина
+                        if indices.iter().enumerate().any(|(i, &index)| index as usize != i) {
+                            streams = streams.unweld(&indices);
+                            if let Some(morph) = &mut morph_targets {
+                                morph.remap(&indices);
+                            }
+                            indices = (0..streams.len() as u32).collect();
+                        }
+                        streams.generate_flat_normals(&indices);
+                    }
+                    NormalGeneration::Smooth => streams.generate_smooth_normals(&indices),
+                }
+            }
+            if streams.tangents.is_none() {
+                if let Some(normal_texture) = primitive.material().normal_texture() {
+                    streams.generate_tangents(&indices, normal_texture.tex_coord());
+                }
+            }
+        }
+
+        let bounds = Aabb::from_points(streams.positions.iter().copied().map(Vec3::from));
+        let data = options.keep_data.then(|| MeshData::from_streams(&streams, &indices));
+
+        let layout = streams.layout();
+        let contents = streams.to_bytes(&layout);
+        if let Some(morph) = &mut morph_targets {
+            morph.set_base_vertices(&contents, &layout);
+        }
+        let initial = morph_targets
+            .as_ref()
+            .map(|morph| morph.blend_vertices(&morph.default_weights));
+
+        let (vertex_buffer, index_buffer) =
+            create_buffers(&device, initial.as_deref().unwrap_or(&contents), &indices, wgpu::BufferUsages::VERTEX);
+
+        let mut mesh = Self {
+            material,
+            indices,
+            vertex_buffer,
+            index_buffer,
+            skinned: layout.contains(VertexAttribute::Joints),
+            layout,
+            topology,
+            morph_targets,
+            bounds,
+            data,
+            lods: Vec::new(),
+        };
+        mesh.generate_lods(&streams.positions, &options.lod_ratios, &device);
+        Ok(mesh)
+    }
+
+    /// Загружает на GPU список треугольников, собранный на CPU; данные
+    /// остаются в [`Mesh::data`]
+    pub fn from_data(data: MeshData, material: Option<Handle<Material>>, device: &Device) -> Self {
+        let streams = data.to_streams();
+        let layout = streams.layout();
+        let (vertex_buffer, index_buffer) =
+            create_buffers(device, &streams.to_bytes(&layout), &data.indices, wgpu::BufferUsages::VERTEX);
+
+        Self {
+            material,
+            indices: data.indices.clone(),
+            vertex_buffer,
+            index_buffer,
+            skinned: false,
+            layout,
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            morph_targets: None,
+            bounds: data.aabb(),
+            data: Some(data),
+            lods: Vec::new(),
+        }
+    }
+
+    /// Строит уровни детализации для долей `ratios` упрощением по квадрикам
+    /// (см. [`simplify`]); `positions` — позиции вершин `vertex_buffer`.
+    /// Уровни, которые не вышли грубее предыдущего, пропускаются; у отрезков
+    /// и точек уровней нет.
+    pub fn generate_lods(&mut self, positions: &[[f32; 3]], ratios: &[f32], device: &Device) {
+        self.lods.clear();
+        let full = self.triangle_count();
+        let mut previous = full;
+        for &ratio in ratios.iter().filter(|&&ratio| ratio > 0.0 && ratio < 1.0) {
+            let target = ((full as f32 * ratio).round() as usize).max(1);
+            let indices = simplify::simplify(positions, &self.indices, target);
+            if indices.len() / 3 >= previous {
+                continue;
+            }
+            previous = indices.len() / 3;
+            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("LOD Index Buffer"),
+                contents: bytemuck::cast_slice(&indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            self.lods.push(MeshLod { ratio, indices, index_buffer });
+        }
+    }
+
+    /// Число уровней детализации вместе с исходным
+    pub fn lod_count(&self) -> usize {
+        1 + self.lods.len()
+    }
+
+    /// Индексный буфер и число индексов уровня `level`: 0 — исходный меш,
+    /// номер больше последнего даёт самый грубый уровень
+    pub fn lod(&self, level: usize) -> (&wgpu::Buffer, u32) {
+        match level.min(self.lods.len()) {
+            0 => (&self.index_buffer, self.indices.len() as u32),
+            level => {
+                let lod = &self.lods[level - 1];
+                (&lod.index_buffer, lod.indices.len() as u32)
+            }
+        }
+    }
+
+    /// Число треугольников; для отрезков и точек 0
+    pub fn triangle_count(&self) -> usize {
+        match self.topology {
+            wgpu::PrimitiveTopology::TriangleList => self.indices.len() / 3,
+            _ => 0,
+        }
+    }
+
+    /// Вершинный буфер узла с морф-целями, смешанными по его весам (обычно
+    /// [`SceneNode::weights`] после применения анимации). Меш общий для узлов,
+    /// поэтому `vertex_buffer` не трогается; при смене весов буфер
+    /// перезаписывается данными [`MorphTargets::blend_vertices`].
+    /// `None`, если морф-целей нет.
+    ///
+    /// [`SceneNode::weights`]: super::scene::SceneNode::weights
+    pub fn create_morph_buffer(&self, device: &Device, weights: &[f32]) -> Option<wgpu::Buffer> {
+        let morph = self.morph_targets.as_ref()?;
+        Some(device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Morph Vertex Buffer"),
+            contents: &morph.blend_vertices(weights),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        }))
+    }
+}
+
+fn create_buffers(device: &Device, vertices: &[u8], indices: &[u32], usage: wgpu::BufferUsages) -> (wgpu::Buffer, wgpu::Buffer) {
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Vertex Buffer"),
+        contents: vertices,
+        usage,
+    });
+
+    let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Index Buffer"),
+        contents: bytemuck::cast_slice(indices),
+        usage: wgpu::BufferUsages::INDEX,
+    });
+
+    (vertex_buffer, index_buffer)
+}
+
@@ -0,0 +1,374 @@
+//! Процедурные меши: плоскость и сетка, куб, UV-сфера, икосфера, цилиндр,
+//! конус, тор и капсула.
+//!
+//! Фигуры построены вокруг начала координат, ось вращения — Y, треугольники
+//! обходятся против часовой стрелки снаружи, V в UV растёт вниз, как в glTF.
+//! Касательные строятся по MikkTSpace. Результат загружается на GPU через
+//! [`Mesh::from_data`](super::Mesh::from_data), например для гизмо и
+//! заместителей источников света.
+
+use std::{collections::HashMap, f32::consts::PI};
+
+use glam::{UVec2, Vec2, Vec3};
+
+use super::data::MeshData;
+
+/// Прямоугольник в XZ с нормалью +Y
+pub fn plane(size: Vec2) -> MeshData {
+    grid(size, UVec2::ONE)
+}
+
+/// Плоскость в XZ с нормалью +Y, разбитая на `cells` клеток; UV от 0 до 1
+pub fn grid(size: Vec2, cells: UVec2) -> MeshData {
+    let cells = cells.max(UVec2::ONE);
+    let mut builder = Builder::default();
+    for row in 0..=cells.y {
+        for column in 0..=cells.x {
+            let uv = UVec2::new(column, row).as_vec2() / cells.as_vec2();
+            let position = (uv - 0.5) * size;
+            builder.vertex(Vec3::new(position.x, 0.0, position.y), Vec3::Y, uv);
+        }
+    }
+    let stride = cells.x + 1;
+    for row in 0..cells.y {
+        for column in 0..cells.x {
+            let top_left = row * stride + column;
+            builder.quad([top_left, top_left + stride, top_left + stride + 1, top_left + 1]);
+        }
+    }
+    builder.finish()
+}
+
+/// Прямоугольный параллелепипед; у каждой грани свои вершины и UV от 0 до 1
+pub fn cube(size: Vec3) -> MeshData {
+    // Нормаль, направление вправо и вверх на грани, если смотреть снаружи
+    const FACES: [(Vec3, Vec3, Vec3); 6] = [
+        (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+        (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+        (Vec3::Y, Vec3::X, Vec3::NEG_Z),
+        (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+        (Vec3::Z, Vec3::X, Vec3::Y),
+        (Vec3::NEG_Z, Vec3::NEG_X, Vec3::Y),
+    ];
+
+    let half = size * 0.5;
+    let mut builder = Builder::default();
+    for (normal, right, up) in FACES {
+        let corners = [(-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0), (1.0, 1.0)].map(|(x, y)| {
+            let position = (normal + right * x + up * y) * half;
+            builder.vertex(position, normal, Vec2::new(x + 1.0, 1.0 - y) * 0.5)
+        });
+        builder.quad(corners);
+    }
+    builder.finish()
+}
+
+/// Сфера из `sectors` долей по долготе и `stacks` поясов по широте
+pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> MeshData {
+    let stacks = stacks.max(2);
+    let profile = (0..=stacks)
+        .map(|stack| {
+            let v = stack as f32 / stacks as f32;
+            let normal = Vec2::new((v * PI).sin(), (v * PI).cos());
+            ProfilePoint { position: normal * radius, normal, v }
+        })
+        .collect::<Vec<_>>();
+    let mut builder = Builder::default();
+    builder.lathe(&profile, sectors);
+    builder.finish()
+}
+
+/// Сфера из подразбитого икосаэдра: треугольники почти одинаковые, без
+/// сгущения у полюсов. Каждое подразбиение увеличивает их число вчетверо.
+pub fn icosphere(radius: f32, subdivisions: u32) -> MeshData {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let mut directions: Vec<Vec3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .map(|(x, y, z)| Vec3::new(x, y, z).normalize())
+    .to_vec();
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                directions.push((directions[a as usize] + directions[b as usize]).normalize());
+                directions.len() as u32 - 1
+            })
+        };
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let (ab, bc, ca) = (midpoint(a, b), midpoint(b, c), midpoint(c, a));
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    let mut builder = Builder::default();
+    for &direction in &directions {
+        builder.vertex(direction * radius, direction, sphere_uv(direction));
+    }
+    // Треугольники на шве U = 0/1 получают копии вершин с U + 1
+    let mut seam = HashMap::new();
+    for triangle in &mut triangles {
+        let us = triangle.map(|index| builder.data.tex_coords[index as usize][0]);
+        if us.iter().copied().fold(f32::MIN, f32::max) - us.iter().copied().fold(f32::MAX, f32::min) <= 0.5 {
+            continue;
+        }
+        for (index, u) in triangle.iter_mut().zip(us) {
+            if u < 0.5 {
+                *index = *seam.entry(*index).or_insert_with(|| {
+                    let direction = directions[*index as usize];
+                    builder.vertex(direction * radius, direction, sphere_uv(direction) + Vec2::X)
+                });
+            }
+        }
+    }
+    for triangle in triangles {
+        builder.triangle(triangle);
+    }
+    builder.finish()
+}
+
+/// Цилиндр высотой `height` с крышками
+pub fn cylinder(radius: f32, height: f32, segments: u32) -> MeshData {
+    let half = height * 0.5;
+    let profile = [(half, 0.0), (-half, 1.0)].map(|(y, v)| ProfilePoint {
+        position: Vec2::new(radius, y),
+        normal: Vec2::X,
+        v,
+    });
+    let mut builder = Builder::default();
+    builder.lathe(&profile, segments);
+    builder.disc(half, radius, segments, true);
+    builder.disc(-half, radius, segments, false);
+    builder.finish()
+}
+
+/// Конус высотой `height` с вершиной сверху и основанием снизу
+pub fn cone(radius: f32, height: f32, segments: u32) -> MeshData {
+    let half = height * 0.5;
+    let normal = Vec2::new(height, radius).normalize_or(Vec2::X);
+    let profile = [
+        ProfilePoint { position: Vec2::new(0.0, half), normal, v: 0.0 },
+        ProfilePoint { position: Vec2::new(radius, -half), normal, v: 1.0 },
+    ];
+    let mut builder = Builder::default();
+    builder.lathe(&profile, segments);
+    builder.disc(-half, radius, segments, false);
+    builder.finish()
+}
+
+/// Тор в плоскости XZ: `major_radius` — до центра трубки, `minor_radius` — её радиус
+pub fn torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> MeshData {
+    let minor_segments = minor_segments.max(3);
+    // Сечение обходится от внешнего экватора вниз
+    let profile = (0..=minor_segments)
+        .map(|segment| {
+            let v = segment as f32 / minor_segments as f32;
+            let angle = v * 2.0 * PI;
+            let normal = Vec2::new(angle.cos(), -angle.sin());
+            ProfilePoint {
+                position: Vec2::new(major_radius, 0.0) + normal * minor_radius,
+                normal,
+                v,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut builder = Builder::default();
+    builder.lathe(&profile, major_segments);
+    builder.finish()
+}
+
+/// Капсула: цилиндр высотой `height` с полусферами по краям, полная высота
+/// `height + 2 * radius`; `rings` — поясов в каждой полусфере
+pub fn capsule(radius: f32, height: f32, segments: u32, rings: u32) -> MeshData {
+    let rings = rings.max(1);
+    let half = height * 0.5;
+    let length = PI * radius + height;
+    let mut profile = Vec::with_capacity(2 * rings as usize + 2);
+    for (offset, first_angle, first_arc) in [(half, 0.0, 0.0), (-half, PI / 2.0, PI / 2.0 * radius + height)] {
+        for ring in 0..=rings {
+            let angle = ring as f32 / rings as f32 * PI / 2.0;
+            let normal = Vec2::new((first_angle + angle).sin(), (first_angle + angle).cos());
+            profile.push(ProfilePoint {
+                position: normal * radius + Vec2::new(0.0, offset),
+                normal,
+                v: (first_arc + angle * radius) / length,
+            });
+        }
+    }
+    let mut builder = Builder::default();
+    builder.lathe(&profile, segments);
+    builder.finish()
+}
+
+/// UV направления на сфере: U — долгота от +Z к +X, V — от северного полюса
+fn sphere_uv(direction: Vec3) -> Vec2 {
+    let u = direction.x.atan2(direction.z) / (2.0 * PI);
+    Vec2::new(u.rem_euclid(1.0), direction.y.clamp(-1.0, 1.0).acos() / PI)
+}
+
+/// Точка образующей тела вращения: `x` — расстояние до оси, `y` — высота
+#[derive(Debug, Clone, Copy)]
+struct ProfilePoint {
+    position: Vec2,
+    normal: Vec2,
+    v: f32,
+}
+
+#[derive(Default)]
+struct Builder {
+    data: MeshData,
+}
+
+impl Builder {
+    fn vertex(&mut self, position: Vec3, normal: Vec3, uv: Vec2) -> u32 {
+        self.data.positions.push(position.to_array());
+        self.data.normals.push(normal.to_array());
+        self.data.tex_coords.push(uv.to_array());
+        self.data.positions.len() as u32 - 1
+    }
+
+    fn triangle(&mut self, triangle: [u32; 3]) {
+        self.data.indices.extend(triangle);
+    }
+
+    /// Четырёхугольник: левый верхний, левый нижний, правый нижний, правый верхний
+    fn quad(&mut self, [a, b, c, d]: [u32; 4]) {
+        self.triangle([a, b, c]);
+        self.triangle([a, c, d]);
+    }
+
+    /// Тело вращения вокруг Y; образующая идёт сверху вниз, U растёт от +Z к +X
+    fn lathe(&mut self, profile: &[ProfilePoint], segments: u32) {
+        let segments = segments.max(3);
+        let first = self.data.positions.len() as u32;
+        for segment in 0..=segments {
+            let u = segment as f32 / segments as f32;
+            let (sin, cos) = (u * 2.0 * PI).sin_cos();
+            for point in profile {
+                let around = |value: Vec2| Vec3::new(value.x * sin, value.y, value.x * cos);
+                self.vertex(around(point.position), around(point.normal), Vec2::new(u, point.v));
+            }
+        }
+
+        // Точки на оси (полюса, вершина конуса) с погрешностью sin(PI)
+        let max_radius = profile.iter().map(|point| point.position.x).fold(0.0, f32::max);
+        let on_axis = |point: &ProfilePoint| point.position.x <= max_radius * 1e-6;
+        let stride = profile.len() as u32;
+        for segment in 0..segments {
+            for (ring, pair) in profile.windows(2).enumerate() {
+                let top_left = first + segment * stride + ring as u32;
+                let [a, b, c, d] = [top_left, top_left + 1, top_left + stride + 1, top_left + stride];
+                // На оси половина четырёхугольника вырождается
+                if !on_axis(&pair[0]) {
+                    self.triangle([a, c, d]);
+                }
+                if !on_axis(&pair[1]) {
+                    self.triangle([a, b, c]);
+                }
+            }
+        }
+    }
+
+    /// Круг на высоте `y`, смотрящий вверх или вниз
+    fn disc(&mut self, y: f32, radius: f32, segments: u32, up: bool) {
+        let segments = segments.max(3);
+        let normal = if up { Vec3::Y } else { Vec3::NEG_Y };
+        let center = self.vertex(Vec3::new(0.0, y, 0.0), normal, Vec2::splat(0.5));
+        for segment in 0..=segments {
+            let (sin, cos) = (segment as f32 / segments as f32 * 2.0 * PI).sin_cos();
+            // Снизу круг виден зеркально
+            let uv = Vec2::new(if up { sin } else { -sin }, cos) * 0.5 + 0.5;
+            self.vertex(Vec3::new(sin * radius, y, cos * radius), normal, uv);
+        }
+        for segment in 1..=segments {
+            let (current, next) = (center + segment, center + segment + 1);
+            self.triangle(if up { [center, current, next] } else { [center, next, current] });
+        }
+    }
+
+    fn finish(mut self) -> MeshData {
+        self.data.generate_tangents();
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shapes() -> Vec<(&'static str, MeshData)> {
+        vec![
+            ("grid", grid(Vec2::new(2.0, 4.0), UVec2::new(2, 3))),
+            ("cube", cube(Vec3::new(1.0, 2.0, 3.0))),
+            ("uv_sphere", uv_sphere(1.0, 16, 8)),
+            ("icosphere", icosphere(1.0, 2)),
+            ("cylinder", cylinder(0.5, 2.0, 12)),
+            ("cone", cone(0.5, 2.0, 12)),
+            ("torus", torus(1.0, 0.25, 16, 8)),
+            ("capsule", capsule(0.5, 1.0, 12, 4)),
+        ]
+    }
+
+    #[test]
+    fn test_shapes_are_consistent() {
+        for (name, mesh) in shapes() {
+            let count = mesh.vertex_count();
+            assert_eq!(mesh.normals.len(), count, "{name}");
+            assert_eq!(mesh.tex_coords.len(), count, "{name}");
+            assert_eq!(mesh.tangents.as_ref().map(Vec::len), Some(count), "{name}");
+            assert_eq!(mesh.stats().degenerate_triangles, 0, "{name}");
+
+            // Обход треугольников согласован с нормалями вершин
+            for (triangle, [a, b, c]) in mesh.indices.chunks_exact(3).zip(mesh.triangles()) {
+                let normal: Vec3 = triangle.iter().map(|&index| Vec3::from(mesh.normals[index as usize])).sum();
+                assert!((b - a).cross(c - a).dot(normal) > 0.0, "{name}: {triangle:?}");
+            }
+            for normal in &mesh.normals {
+                assert!((Vec3::from(*normal).length() - 1.0).abs() < 1e-5, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_shape_sizes() {
+        let grid = grid(Vec2::new(2.0, 4.0), UVec2::new(2, 3));
+        assert_eq!((grid.vertex_count(), grid.triangle_count()), (12, 12));
+        assert_eq!(grid.tex_coords[11], [1.0, 1.0]);
+
+        let cube = cube(Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!((cube.vertex_count(), cube.triangle_count()), (24, 12));
+        assert_eq!(cube.aabb().size(), Vec3::new(1.0, 2.0, 3.0));
+
+        let icosphere = icosphere(2.0, 1);
+        assert_eq!(icosphere.triangle_count(), 80);
+        assert!(icosphere.positions.iter().all(|position| (Vec3::from(*position).length() - 2.0).abs() < 1e-5));
+
+        let capsule = capsule(0.5, 1.0, 12, 4);
+        assert!((capsule.aabb().size() - Vec3::new(1.0, 2.0, 1.0)).abs().max_element() < 1e-5);
+        assert_eq!(capsule.tex_coords.last().unwrap()[1], 1.0);
+
+        let torus = torus(1.0, 0.25, 16, 8);
+        assert!((torus.aabb().size() - Vec3::new(2.5, 0.5, 2.5)).abs().max_element() < 1e-5);
+    }
+}
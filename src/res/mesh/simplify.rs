@@ -0,0 +1,297 @@
+//! Упрощение меша схлопыванием рёбер по квадрикам ошибки (Garland — Heckbert).
+//!
+//! Ребро схлопывается в одну из своих вершин, новых вершин не появляется:
+//! упрощённые индексы ссылаются на исходный вершинный буфер, и все уровни
+//! детализации используют его вместе с морф-целями и скиннингом. Вершины
+//! открытых краёв и швов UV, где вершины разделены, сдвигаются только вдоль
+//! прямого участка края, так что дыр и трещин не появляется; меши с плоскими
+//! нормалями, где у каждого треугольника свои вершины, поэтому почти не упрощаются.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use glam::DVec3;
+
+/// Вес штрафной плоскости границы относительно плоскостей граней
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// Синус угла, при котором соседние рёбра края считаются одной прямой
+const COLLINEAR_SINE: f64 = 1e-4;
+
+/// Индексы списка треугольников, упрощённого до `target_triangles` или
+/// до числа, ниже которого схлопывания выворачивают треугольники
+pub fn simplify(positions: &[[f32; 3]], indices: &[u32], target_triangles: usize) -> Vec<u32> {
+    let mut triangles: Vec<[u32; 3]> = indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .filter(|[a, b, c]| a != b && b != c && a != c)
+        .collect();
+    if triangles.len() <= target_triangles {
+        return triangles.concat();
+    }
+
+    let points: Vec<DVec3> = positions.iter().map(|&position| DVec3::from(position.map(f64::from))).collect();
+    let mut quadrics = vec![Quadric::default(); points.len()];
+    let mut vertex_triangles = vec![Vec::new(); points.len()];
+    let mut edges: HashMap<(u32, u32), Option<usize>> = HashMap::new();
+    for (index, triangle) in triangles.iter().enumerate() {
+        let normal = face_normal(&points, triangle);
+        let area = normal.length() * 0.5;
+        if let Some(unit) = normal.try_normalize() {
+            let quadric = Quadric::from_plane(unit, points[triangle[0] as usize], area);
+            for &vertex in triangle {
+                quadrics[vertex as usize].add(&quadric);
+            }
+        }
+        for corner in 0..3 {
+            let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+            vertex_triangles[a as usize].push(index);
+            // У ребра с одним треугольником запоминается этот треугольник
+            edges
+                .entry((a.min(b), a.max(b)))
+                .and_modify(|owner| *owner = None)
+                .or_insert(Some(index));
+        }
+    }
+
+    // Плоскость через край, перпендикулярная грани: край не уползает внутрь
+    let mut boundary = vec![Vec::new(); points.len()];
+    for (&(a, b), owner) in &edges {
+        let Some(owner) = *owner else { continue };
+        boundary[a as usize].push(b);
+        boundary[b as usize].push(a);
+        let edge = points[b as usize] - points[a as usize];
+        if let Some(normal) = edge.cross(face_normal(&points, &triangles[owner])).try_normalize() {
+            let quadric = Quadric::from_plane(normal, points[a as usize], BOUNDARY_WEIGHT * edge.length_squared());
+            quadrics[a as usize].add(&quadric);
+            quadrics[b as usize].add(&quadric);
+        }
+    }
+
+    let mut versions = vec![0u32; points.len()];
+    let mut removed = vec![false; points.len()];
+    let mut alive = vec![true; triangles.len()];
+    let mut live = triangles.len();
+    let mut heap = BinaryHeap::new();
+    for &(a, b) in edges.keys() {
+        heap.push(Collapse::cheapest(a, b, &points, &quadrics, &versions));
+    }
+
+    while live > target_triangles {
+        let Some(collapse) = heap.pop() else { break };
+        let (from, to) = (collapse.from as usize, collapse.to as usize);
+        if removed[from] || removed[to] || collapse.versions != (versions[from], versions[to]) {
+            continue;
+        }
+        // Вершина края уходит только по краю и только если он прямой
+        let along_boundary = boundary[from].is_empty()
+            || boundary[from].contains(&collapse.to)
+                && boundary[from].iter().filter(|&&other| other != collapse.to).all(|&other| {
+                    let incoming = points[from] - points[other as usize];
+                    let outgoing = points[to] - points[from];
+                    incoming.dot(outgoing) > 0.0
+                        && incoming.cross(outgoing).length() <= COLLINEAR_SINE * incoming.length() * outgoing.length()
+                });
+        if !along_boundary {
+            continue;
+        }
+        // Схлопывание не должно выворачивать треугольники или убирать все
+        // треугольники вершины, оставляя дыру
+        let mut kept = vertex_triangles[from]
+            .iter()
+            .filter(|&&index| alive[index] && !triangles[index].contains(&collapse.to))
+            .peekable();
+        if kept.peek().is_none() {
+            continue;
+        }
+        let flips = kept.any(|&index| {
+            let triangle = triangles[index];
+            let moved = triangle.map(|vertex| if vertex == collapse.from { collapse.to } else { vertex });
+            face_normal(&points, &moved).dot(face_normal(&points, &triangle)) <= 0.0
+        });
+        if flips {
+            continue;
+        }
+
+        for index in std::mem::take(&mut vertex_triangles[from]) {
+            if !alive[index] {
+                continue;
+            }
+            if triangles[index].contains(&collapse.to) {
+                alive[index] = false;
+                live -= 1;
+            } else {
+                for vertex in &mut triangles[index] {
+                    if *vertex == collapse.from {
+                        *vertex = collapse.to;
+                    }
+                }
+                vertex_triangles[to].push(index);
+            }
+        }
+        let quadric = quadrics[from];
+        quadrics[to].add(&quadric);
+        removed[from] = true;
+        versions[to] += 1;
+        for other in std::mem::take(&mut boundary[from]) {
+            boundary[other as usize].retain(|&vertex| vertex != collapse.from);
+            if other != collapse.to && !boundary[to].contains(&other) {
+                boundary[to].push(other);
+                boundary[other as usize].push(collapse.to);
+            }
+        }
+
+        vertex_triangles[to].retain(|&index| alive[index]);
+        let mut neighbours: Vec<u32> = vertex_triangles[to]
+            .iter()
+            .flat_map(|&index| triangles[index])
+            .filter(|&vertex| vertex != collapse.to)
+            .collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for neighbour in neighbours {
+            heap.push(Collapse::cheapest(collapse.to, neighbour, &points, &quadrics, &versions));
+        }
+    }
+
+    triangles
+        .into_iter()
+        .zip(alive)
+        .filter_map(|(triangle, alive)| alive.then_some(triangle))
+        .flatten()
+        .collect()
+}
+
+/// Ненормированная нормаль треугольника
+fn face_normal(points: &[DVec3], triangle: &[u32; 3]) -> DVec3 {
+    let [a, b, c] = triangle.map(|vertex| points[vertex as usize]);
+    (b - a).cross(c - a)
+}
+
+/// Сумма квадратов расстояний до плоскостей: симметричная матрица 4×4
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    /// aa, ab, ac, ad, bb, bc, bd, cc, cd, dd
+    terms: [f64; 10],
+}
+
+impl Quadric {
+    fn from_plane(normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let [a, b, c] = normal.to_array();
+        let d = -normal.dot(point);
+        let terms = [a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d];
+        Self { terms: terms.map(|term| term * weight) }
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (term, other) in self.terms.iter_mut().zip(other.terms) {
+            *term += other;
+        }
+    }
+
+    fn error(&self, point: DVec3) -> f64 {
+        let [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd] = self.terms;
+        let DVec3 { x, y, z } = point;
+        aa * x * x + 2.0 * ab * x * y + 2.0 * ac * x * z + 2.0 * ad * x
+            + bb * y * y + 2.0 * bc * y * z + 2.0 * bd * y
+            + cc * z * z + 2.0 * cd * z
+            + dd
+    }
+}
+
+/// Кандидат на схлопывание `from` в `to`; устаревает, когда меняется
+/// квадрика одной из вершин
+#[derive(Debug, Clone, Copy)]
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    versions: (u32, u32),
+}
+
+impl Collapse {
+    /// Лучшее из двух направлений схлопывания ребра
+    fn cheapest(a: u32, b: u32, points: &[DVec3], quadrics: &[Quadric], versions: &[u32]) -> Self {
+        let mut quadric = quadrics[a as usize];
+        quadric.add(&quadrics[b as usize]);
+        let (cost_a, cost_b) = (quadric.error(points[a as usize]), quadric.error(points[b as usize]));
+        let (from, to, cost) = if cost_a < cost_b { (b, a, cost_a) } else { (a, b, cost_b) };
+        Self {
+            cost,
+            from,
+            to,
+            versions: (versions[from as usize], versions[to as usize]),
+        }
+    }
+}
+
+// Куча максимальная, поэтому порядок по стоимости обратный
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+#[cfg(test)]
+mod tests {
+    use glam::{UVec2, Vec2, Vec3};
+
+    use super::*;
+    use crate::res::mesh::{procedural, MeshData};
+
+    /// Треугольники упрощённого меша не смотрят против `outward`
+    fn assert_not_flipped(mesh: &MeshData, indices: &[u32], outward: impl Fn(Vec3) -> Vec3) {
+        let simplified = MeshData { indices: indices.to_vec(), ..mesh.clone() };
+        for [a, b, c] in simplified.triangles() {
+            // Треугольники из вершин шва могут встать ребром, но не внутрь
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+            assert!(normal.dot(outward((a + b + c) / 3.0).normalize()) > -1e-3);
+        }
+    }
+
+    #[test]
+    fn test_simplify_plane_keeps_corners() {
+        let grid = procedural::grid(Vec2::splat(2.0), UVec2::splat(8));
+        let indices = simplify(&grid.positions, &grid.indices, 2);
+        assert_eq!(indices.len(), 6);
+        assert_not_flipped(&grid, &indices, |_| Vec3::Y);
+
+        let simplified = MeshData { indices, ..grid.clone() };
+        let used = simplified.indices.iter().map(|&index| Vec3::from(grid.positions[index as usize]));
+        assert_eq!(crate::math::bounds::Aabb::from_points(used), grid.aabb());
+    }
+
+    #[test]
+    fn test_simplify_keeps_split_faces_closed() {
+        let cube = procedural::cube(Vec3::ONE);
+        assert_eq!(simplify(&cube.positions, &cube.indices, 6), cube.indices);
+    }
+
+    #[test]
+    fn test_simplify_sphere() {
+        let sphere = procedural::icosphere(1.0, 3);
+        let target = sphere.triangle_count() / 4;
+        let indices = simplify(&sphere.positions, &sphere.indices, target);
+        assert!(indices.len() / 3 <= target);
+        assert!(indices.len() / 3 > target / 2);
+        assert_not_flipped(&sphere, &indices, |center| center);
+
+        assert_eq!(simplify(&sphere.positions, &sphere.indices, usize::MAX), sphere.indices);
+    }
+}
@@ -11,8 +11,12 @@
 
 pub mod storage;
 pub mod asset_manager;
+pub mod loader;
+pub mod hot_reload;
+pub mod manifest;
 pub mod vertex;
 pub mod mesh;
+pub mod morph;
 pub mod scin;
 pub mod texture;
 pub mod material;
@@ -22,12 +26,14 @@ pub mod buffer;
 pub mod image;
 pub mod scene;
 pub mod camera;
+pub mod error;
 
 #[cfg(test)]
 pub mod test;  // Модуль тестов (только для тестирования)
 
 use std::{marker::PhantomData, path::Path};
 use buffer::{load_gltf_buffers, BufferData};
+use error::AssetError;
 use gltf::Gltf;
 use image::{load_gltf_images, ImageData};
 use slotmap::new_key_type;
@@ -67,7 +73,7 @@ new_key_type! {
 ///     type Key = MeshKey;
 ///     type LoadParams = String;
 ///     
-///     fn load(params: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>> {
+///     fn load(params: Self::LoadParams) -> Result<Self, AssetError> {
 ///         println!("Загружаем ресурс из: {}", params);
 ///         Ok(MyResource)
 ///     }
@@ -84,7 +90,7 @@ pub trait Resource {
     ///
     /// # Ошибки
     /// Возвращает ошибку, если не удалось загрузить ресурс
-    fn load(params: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>>
+    fn load(params: Self::LoadParams) -> Result<Self, AssetError>
     where
         Self: Sized;
 }
@@ -127,20 +133,16 @@ impl<T: Resource> Handle<T> {
 pub fn load_gltf_file_data(
     gltf: &Gltf,
     base_path: Option<&Path>,
-) -> Result<(Vec<BufferData>, Vec<ImageData>), Box<dyn std::error::Error>> {
-    let buffers =  match load_gltf_buffers(gltf, base_path){
-        Ok(buffers) => buffers,
-        Err(_) => return Err("Error loading file data (buffers)".into()),
-    };
-    match load_gltf_images(gltf, base_path, &buffers) {
-        Ok(images) => return Ok((buffers, images)),
-        Err(_) => return Err("Error loading file data (images)".into()),
-    } 
+) -> Result<(Vec<BufferData>, Vec<ImageData>), AssetError> {
+    let buffers = load_gltf_buffers(gltf, base_path)?;
+    let images = load_gltf_images(gltf, base_path, &buffers)?;
+    Ok((buffers, images))
 }
 
 
-pub async fn load_binary(file_name: &str) -> Result<Vec<u8>,Box<dyn std::error::Error>> {
+/// Читает файл из каталога `assets` в текущем потоке; фоновая загрузка
+/// glTF — [`loader::AssetLoader`]
+pub fn load_binary(file_name: &str) -> Result<Vec<u8>, AssetError> {
     let path = std::path::Path::new("assets").join(file_name);
-    let data = std::fs::read(path)?;
-    Ok(data)
+    std::fs::read(&path).map_err(|err| AssetError::io(path, err))
 }
\ No newline at end of file
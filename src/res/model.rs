@@ -2,7 +2,7 @@ use std::ops::Range;
 
 use gltf::mesh::Mode;
 
-use super::{animation::Animation, material::Material, mesh::{Mesh}, Handle, ModelKey, Resource};
+use super::{animation::Animation, error::AssetError, material::Material, mesh::{Mesh}, Handle, ModelKey, Resource};
 
 #[derive(Debug, Clone)] 
 pub struct Model {
@@ -14,7 +14,7 @@ impl Resource for Model {
     type Key = ModelKey;
     type LoadParams = Model; 
     
-    fn load(model: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>> {
+    fn load(model: Self::LoadParams) -> Result<Self, AssetError> {
         Ok(model) 
     }
 }
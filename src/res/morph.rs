@@ -0,0 +1,296 @@
+//! Морф-цели (blend shapes) glTF.
+//!
+//! Смешивание идёт на CPU: позиции, нормали и касательные исходных вершин
+//! сдвигаются на взвешенную сумму дельт, результат перезаписывает вершинный
+//! буфер меша. Позиция и нормаль лежат в начале любой [`VertexLayout`],
+//! касательная — там, где её разместила раскладка; знак битангенса в `w`
+//! не меняется.
+
+use gltf::Primitive;
+
+use super::error::AssetError;
+use super::vertex::{VertexAttribute, VertexLayout};
+
+/// Смещения позиции и нормали в вершине
+const POSITION_OFFSET: usize = 0;
+const NORMAL_OFFSET: usize = 12;
+
+/// Дельты одной морф-цели, по элементу на вершину
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MorphTarget {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub tangents: Option<Vec<[f32; 3]>>,
+}
+
+/// Морф-цели примитива и исходные вершины для смешивания
+#[derive(Debug, Clone, Default)]
+pub struct MorphTargets {
+    pub targets: Vec<MorphTarget>,
+    /// Веса из меша glTF, дополненные нулями до числа целей
+    pub default_weights: Vec<f32>,
+    /// Исходные данные вершинного буфера, шаг вершины в байтах и смещение
+    /// касательной, если она есть в раскладке
+    base_vertices: Vec<u8>,
+    stride: usize,
+    tangent_offset: Option<usize>,
+}
+
+impl MorphTargets {
+    /// Читает цели примитива; `None`, если их нет
+    pub fn from_gltf_primitive(
+        primitive: &Primitive,
+        buffers: &[Vec<u8>],
+        mesh_weights: Option<&[f32]>,
+        vertex_count: usize,
+    ) -> Result<Option<Self>, AssetError> {
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+        let mut targets = Vec::new();
+        for (index, (positions, normals, tangents)) in reader.read_morph_targets().enumerate() {
+            let check = |values: Vec<[f32; 3]>, attribute: &str| {
+                if values.len() == vertex_count {
+                    Ok(values)
+                } else {
+                    Err(AssetError::invalid(format!(
+                        "morph target {} has {} {} deltas for {} vertices",
+                        index,
+                        values.len(),
+                        attribute,
+                        vertex_count
+                    )))
+                }
+            };
+            let positions = match positions {
+                Some(values) => check(values.collect(), "position")?,
+                None => vec![[0.0; 3]; vertex_count],
+            };
+            targets.push(MorphTarget {
+                positions,
+                normals: normals.map(|values| check(values.collect(), "normal")).transpose()?,
+                tangents: tangents.map(|values| check(values.collect(), "tangent")).transpose()?,
+            });
+        }
+        if targets.is_empty() {
+            return Ok(None);
+        }
+
+        let mut default_weights = mesh_weights.map(|weights| weights.to_vec()).unwrap_or_default();
+        default_weights.resize(targets.len(), 0.0);
+        Ok(Some(Self {
+            targets,
+            default_weights,
+            base_vertices: Vec::new(),
+            stride: 0,
+            tangent_offset: None,
+        }))
+    }
+
+    /// Переставляет дельты вслед за вершинами: `indices[i]` — исходная вершина
+    /// новой вершины `i` (см. [`VertexStreams::unweld`])
+    ///
+    /// [`VertexStreams::unweld`]: super::mesh::attributes::VertexStreams::unweld
+    pub fn remap(&mut self, indices: &[u32]) {
+        let pick = |values: &[[f32; 3]]| indices.iter().map(|&index| values[index as usize]).collect();
+        for target in &mut self.targets {
+            target.positions = pick(&target.positions);
+            target.normals = target.normals.as_deref().map(pick);
+            target.tangents = target.tangents.as_deref().map(pick);
+        }
+    }
+
+    /// Запоминает исходные вершины, поверх которых смешиваются цели
+    pub fn set_base_vertices(&mut self, data: &[u8], layout: &VertexLayout) {
+        self.base_vertices = data.to_vec();
+        self.stride = layout.stride() as usize;
+        self.tangent_offset = layout.offset(VertexAttribute::Tangent).map(|offset| offset as usize);
+    }
+
+    /// Позиции и нормали после смешивания; лишние веса игнорируются,
+    /// нормали не перенормируются (это делает шейдер)
+    pub fn blend(
+        &self,
+        base_positions: &[[f32; 3]],
+        base_normals: &[[f32; 3]],
+        weights: &[f32],
+    ) -> (Vec<[f32; 3]>, Vec<[f32; 3]>) {
+        let mut positions = base_positions.to_vec();
+        let mut normals = base_normals.to_vec();
+        for (target, &weight) in self.targets.iter().zip(weights) {
+            if weight == 0.0 {
+                continue;
+            }
+            add_scaled(&mut positions, &target.positions, weight);
+            if let Some(deltas) = &target.normals {
+                add_scaled(&mut normals, deltas, weight);
+            }
+        }
+        (positions, normals)
+    }
+
+    /// Касательные (`xyz`) после смешивания; цели без касательных их не меняют
+    pub fn blend_tangents(&self, base_tangents: &[[f32; 3]], weights: &[f32]) -> Vec<[f32; 3]> {
+        let mut tangents = base_tangents.to_vec();
+        for (target, &weight) in self.targets.iter().zip(weights) {
+            if weight == 0.0 {
+                continue;
+            }
+            if let Some(deltas) = &target.tangents {
+                add_scaled(&mut tangents, deltas, weight);
+            }
+        }
+        tangents
+    }
+
+    /// Данные вершинного буфера с применёнными весами
+    pub fn blend_vertices(&self, weights: &[f32]) -> Vec<u8> {
+        let mut data = self.base_vertices.clone();
+        if self.stride == 0 {
+            return data;
+        }
+        let read = |vertex: &[u8], offset: usize| -> [f32; 3] {
+            bytemuck::pod_read_unaligned(&vertex[offset..offset + 12])
+        };
+        let (positions, normals): (Vec<_>, Vec<_>) = data
+            .chunks_exact(self.stride)
+            .map(|vertex| (read(vertex, POSITION_OFFSET), read(vertex, NORMAL_OFFSET)))
+            .unzip();
+        let (positions, normals) = self.blend(&positions, &normals, weights);
+
+        for ((vertex, position), normal) in data.chunks_exact_mut(self.stride).zip(positions).zip(normals) {
+            vertex[POSITION_OFFSET..POSITION_OFFSET + 12].copy_from_slice(bytemuck::bytes_of(&position));
+            vertex[NORMAL_OFFSET..NORMAL_OFFSET + 12].copy_from_slice(bytemuck::bytes_of(&normal));
+        }
+
+        if let Some(offset) = self.tangent_offset {
+            let tangents: Vec<_> = data.chunks_exact(self.stride).map(|vertex| read(vertex, offset)).collect();
+            let tangents = self.blend_tangents(&tangents, weights);
+            for (vertex, tangent) in data.chunks_exact_mut(self.stride).zip(tangents) {
+                vertex[offset..offset + 12].copy_from_slice(bytemuck::bytes_of(&tangent));
+            }
+        }
+        data
+    }
+}
+
+fn add_scaled(values: &mut [[f32; 3]], deltas: &[[f32; 3]], weight: f32) {
+    for (value, delta) in values.iter_mut().zip(deltas) {
+        for axis in 0..3 {
+            value[axis] += delta[axis] * weight;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::vertex::Vertex;
+
+    #[test]
+    fn test_morph_targets_from_gltf() {
+        let gltf = gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "buffers": [{ "byteLength": 48 }],
+                "bufferViews": [
+                    { "buffer": 0, "byteOffset": 0, "byteLength": 24 },
+                    { "buffer": 0, "byteOffset": 24, "byteLength": 24 }
+                ],
+                "accessors": [
+                    { "bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3", "min": [0, 0, 0], "max": [1, 0, 0] },
+                    { "bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC3", "min": [0, 0, 0], "max": [0, 1, 0] }
+                ],
+                "meshes": [{
+                    "weights": [0.5],
+                    "primitives": [{
+                        "attributes": { "POSITION": 0 },
+                        "targets": [{ "POSITION": 1 }, { "NORMAL": 1 }]
+                    }]
+                }]
+            }"#,
+        )
+        .unwrap();
+        let data: [[f32; 3]; 4] = [[0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 1.0, 0.0]];
+        let buffers = vec![bytemuck::cast_slice(&data).to_vec()];
+        let mesh = gltf.meshes().next().unwrap();
+        let primitive = mesh.primitives().next().unwrap();
+
+        let morph = MorphTargets::from_gltf_primitive(&primitive, &buffers, mesh.weights(), 2)
+            .unwrap()
+            .unwrap();
+        assert_eq!(morph.targets.len(), 2);
+        assert_eq!(morph.default_weights, [0.5, 0.0]);
+        assert_eq!(morph.targets[0].positions[1], [0.0, 1.0, 0.0]);
+        // Цель без POSITION не сдвигает вершины
+        assert_eq!(morph.targets[1].positions, [[0.0; 3]; 2]);
+        assert!(morph.targets[1].normals.is_some());
+
+        assert!(MorphTargets::from_gltf_primitive(&primitive, &buffers, None, 3).is_err());
+    }
+
+    #[test]
+    fn test_blend_vertices() {
+        let mut morph = MorphTargets {
+            targets: vec![
+                MorphTarget {
+                    positions: vec![[0.0, 2.0, 0.0]],
+                    normals: Some(vec![[1.0, 0.0, 0.0]]),
+                    tangents: None,
+                },
+                MorphTarget {
+                    positions: vec![[4.0, 0.0, 0.0]],
+                    normals: None,
+                    tangents: None,
+                },
+            ],
+            ..Default::default()
+        };
+        let base = [Vertex {
+            position: [1.0, 1.0, 1.0],
+            normal: [0.0, 0.0, 1.0],
+            tex_coord: [0.25, 0.75],
+        }];
+        morph.set_base_vertices(bytemuck::cast_slice(&base), &VertexLayout::BASIC);
+
+        let blended: Vec<Vertex> = bytemuck::pod_collect_to_vec(&morph.blend_vertices(&[0.5, 0.25]));
+        assert_eq!(blended[0].position, [2.0, 2.0, 1.0]);
+        assert_eq!(blended[0].normal, [0.5, 0.0, 1.0]);
+        assert_eq!(blended[0].tex_coord, [0.25, 0.75]);
+
+        // Нулевые веса возвращают исходные вершины
+        assert_eq!(morph.blend_vertices(&[0.0, 0.0]), bytemuck::cast_slice::<Vertex, u8>(&base));
+    }
+
+    #[test]
+    fn test_blend_vertex_tangents() {
+        let mut morph = MorphTargets {
+            targets: vec![
+                MorphTarget {
+                    positions: vec![[0.0, 1.0, 0.0]],
+                    normals: None,
+                    tangents: Some(vec![[0.0, 2.0, 0.0]]),
+                },
+                MorphTarget {
+                    positions: vec![[0.0; 3]],
+                    normals: None,
+                    tangents: None,
+                },
+            ],
+            ..Default::default()
+        };
+        let layout = VertexLayout::new(&[
+            VertexAttribute::Position,
+            VertexAttribute::Normal,
+            VertexAttribute::TexCoord0,
+            VertexAttribute::Tangent,
+        ]);
+        // position, normal, tex_coord, tangent
+        let base: [f32; 12] = [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.5, 0.5, 1.0, 0.0, 0.0, -1.0];
+        morph.set_base_vertices(bytemuck::cast_slice(&base), &layout);
+
+        let blended: Vec<f32> = bytemuck::pod_collect_to_vec(&morph.blend_vertices(&[0.5, 1.0]));
+        assert_eq!(blended[..3], [0.0, 0.5, 0.0]);
+        assert_eq!(blended[6..8], [0.5, 0.5]);
+        // Знак битангенса в w сохраняется
+        assert_eq!(blended[8..], [1.0, 1.0, 0.0, -1.0]);
+    }
+}
@@ -1,198 +1,384 @@
-use std::{error::Error, fmt, path::Path};
-use gltf::json::extensions::scene;
-use wgpu::Device;
+use glam::{Mat4, Quat, Vec3};
 
-use crate::res::mesh::Mesh;
-use super::{buffer::{to_vec, BufferData}, material::Material, model::Model, Handle, Resource, SceneKey};
+use crate::scene::{light::{Light, LightType}, transform::Transform};
+use super::{camera::Camera, error::AssetError, mesh::{data::RayHit, Mesh}, model::Model, scin::Skin, storage::Storage, Handle, Resource, SceneKey};
 
-#[derive(Debug, Clone)] 
-pub struct Scene {
-   pub models: Vec<Handle<Model>>
+/// Точечный источник из `KHR_lights_punctual`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeLight {
+    pub light_type: LightType,
+    pub color: Vec3,
+    /// Кандела для точечных и прожекторов, люксы для направленных
+    pub intensity: f32,
 }
 
-impl Resource for Scene {
-    type Key = SceneKey;
-    type LoadParams = Scene;
-    
+impl NodeLight {
+    /// Источники, объявленные в корне документа, в порядке индексов
+    pub fn from_gltf(document: &gltf::Document) -> Vec<Self> {
+        let lights = document
+            .extension_value("KHR_lights_punctual")
+            .and_then(|ext| ext.get("lights"))
+            .and_then(|lights| lights.as_array());
+        let Some(lights) = lights else {
+            return Vec::new();
+        };
+
+        lights
+            .iter()
+            .map(|light| {
+                let number = |value: Option<&gltf::json::Value>, default: f32| {
+                    value.and_then(|v| v.as_f64()).map_or(default, |v| v as f32)
+                };
+                let range = number(light.get("range"), f32::INFINITY);
+                let light_type = match light.get("type").and_then(|t| t.as_str()) {
+                    Some("spot") => LightType::Spot {
+                        angle: number(
+                            light.get("spot").and_then(|spot| spot.get("outerConeAngle")),
+                            std::f32::consts::FRAC_PI_4,
+                        ),
+                        range,
+                    },
+                    Some("directional") => LightType::Directional,
+                    _ => LightType::Point { range },
+                };
+                let color = light
+                    .get("color")
+                    .and_then(|c| c.as_array())
+                    .filter(|c| c.len() == 3)
+                    .map_or(Vec3::ONE, |c| {
+                        Vec3::new(number(c.first(), 1.0), number(c.get(1), 1.0), number(c.get(2), 1.0))
+                    });
+                Self {
+                    light_type,
+                    color,
+                    intensity: number(light.get("intensity"), 1.0),
+                }
+            })
+            .collect()
+    }
 
-    fn load(params: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>> {
-        Ok(params) 
+    pub fn to_light(&self) -> Light {
+        Light::new(self.light_type, self.color, false, self.intensity)
     }
 }
 
-#[derive(Debug)]
-pub struct LoadSceneDataError {
-    message: String,
-    source: Option<Box<dyn Error + Send + Sync>>,
+/// Узел дерева сцены
+#[derive(Debug, Clone)]
+pub struct SceneNode {
+    pub name: String,
+    /// Индекс узла в исходном glTF
+    pub gltf_index: usize,
+    /// Преобразование относительно родителя
+    pub transform: Transform,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub model: Option<Handle<Model>>,
+    pub camera: Option<Handle<Camera>>,
+    pub light: Option<NodeLight>,
+    /// Скелет, которым деформируется модель узла
+    pub skin: Option<Handle<Skin>>,
+    /// Веса морф-целей меша узла
+    pub weights: Vec<f32>,
 }
 
-impl fmt::Display for LoadSceneDataError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Scene loading error: {}", self.message)
-    }
+/// Попадание луча в меш узла, см. [`Scene::raycast`]
+#[derive(Debug, Clone)]
+pub struct ScenePick {
+    pub node: usize,
+    pub mesh: Handle<Mesh>,
+    /// Попадание в пространстве меша; `distance` на луче тот же, что в мире
+    pub hit: RayHit,
 }
 
-impl Error for LoadSceneDataError {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        self.source.as_ref().map(|e| &**e as _)
+/// Сцена: узлы хранятся плоским списком, родитель всегда раньше детей
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub name: String,
+    pub nodes: Vec<SceneNode>,
+    pub roots: Vec<usize>,
+    /// Все модели сцены без повторов
+    pub models: Vec<Handle<Model>>,
+}
+
+impl Resource for Scene {
+    type Key = SceneKey;
+    type LoadParams = Scene;
+
+
+    fn load(params: Self::LoadParams) -> Result<Self, AssetError> {
+        Ok(params)
     }
 }
 
-impl LoadSceneDataError {
-    pub fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
-            source: None,
+impl Scene {
+    /// Строит дерево узлов сцены glTF. `models`, `cameras`, `skins` и `lights`
+    /// индексируются так же, как меши, камеры, скелеты и источники в файле.
+    pub fn from_gltf(
+        scene: &gltf::Scene,
+        models: &[Handle<Model>],
+        cameras: &[Handle<Camera>],
+        skins: &[Handle<Skin>],
+        lights: &[NodeLight],
+    ) -> Result<Self, AssetError> {
+        let mut result = Scene {
+            name: scene.name().unwrap_or_default().to_string(),
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            models: Vec::new(),
+        };
+        let mut visited = std::collections::HashSet::new();
+        let mut stack: Vec<(gltf::Node, Option<usize>)> = scene.nodes().map(|node| (node, None)).collect();
+        stack.reverse();
+
+        while let Some((node, parent)) = stack.pop() {
+            if !visited.insert(node.index()) {
+                return Err(AssetError::NodeCycle(node.index()));
+            }
+            let missing = |kind, index| AssetError::MissingReference {
+                node: node.index(),
+                kind,
+                index,
+            };
+
+            let model = match node.mesh() {
+                Some(mesh) => Some(models.get(mesh.index()).cloned().ok_or(missing("mesh", mesh.index()))?),
+                None => None,
+            };
+            let camera = match node.camera() {
+                Some(camera) => Some(cameras.get(camera.index()).cloned().ok_or(missing("camera", camera.index()))?),
+                None => None,
+            };
+            let skin = match node.skin() {
+                Some(skin) => Some(skins.get(skin.index()).cloned().ok_or(missing("skin", skin.index()))?),
+                None => None,
+            };
+            let light = match node
+                .extension_value("KHR_lights_punctual")
+                .and_then(|ext| ext.get("light"))
+                .and_then(|light| light.as_u64())
+            {
+                Some(index) => Some(*lights.get(index as usize).ok_or(missing("light", index as usize))?),
+                None => None,
+            };
+
+            if let Some(model) = &model {
+                if !result.models.iter().any(|m| m.key() == model.key()) {
+                    result.models.push(model.clone());
+                }
+            }
+
+            let (translation, rotation, scale) = node.transform().decomposed();
+            let index = result.nodes.len();
+            result.nodes.push(SceneNode {
+                name: node.name().unwrap_or_default().to_string(),
+                gltf_index: node.index(),
+                transform: Transform::new(Vec3::from(translation), Quat::from_array(rotation), Vec3::from(scale)),
+                parent,
+                children: Vec::new(),
+                model,
+                camera,
+                light,
+                skin,
+                weights: node
+                    .weights()
+                    .or_else(|| node.mesh().and_then(|mesh| mesh.weights()))
+                    .map(|weights| weights.to_vec())
+                    .unwrap_or_default(),
+            });
+            match parent {
+                Some(parent) => result.nodes[parent].children.push(index),
+                None => result.roots.push(index),
+            }
+            let first_child = stack.len();
+            stack.extend(node.children().map(|child| (child, Some(index))));
+            stack[first_child..].reverse();
         }
+
+        Ok(result)
     }
 
-    pub fn with_source(message: impl Into<String>, source: impl Error + Send + Sync + 'static) -> Self {
-        Self {
-            message: message.into(),
-            source: Some(Box::new(source)),
+    /// Мировые матрицы узлов в порядке `nodes`
+    pub fn world_transforms(&self) -> Vec<Mat4> {
+        let mut world: Vec<Mat4> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let local = node.transform.to_matrix();
+            let matrix = match node.parent {
+                Some(parent) => world[parent] * local,
+                None => local,
+            };
+            world.push(matrix);
         }
+        world
     }
-}
 
-impl Scene {
-    // pub fn from_gltf_scene( 
-    // scene: gltf::Scene,
-    // buffers: Vec<BufferData>,
-    // material_handles: Vec<Handle<Material>>
-    // )-> Result<Scene,  Box<dyn std::error::Error>> {
-    //     let buff = to_vec(buffers);
-    //     let mut nodes = Vec::new();
-    //     for node in scene.nodes() {
-    //         let mut meshes = Vec::new();
-    //         let mesh = node.mesh().expect("Got mesh");
-    //         let primitives = mesh.primitives();
-    //         primitives.for_each(|primitive| {
-    //              match Mesh::from_gltf_primitive(
-    //                 &primitive, 
-    //                 &buff,
-    //                 material_handles.get(primitive.material().index().unwrap_or(0)).cloned(),
-    //             ) {
-    //                 Ok(mesh) =>  meshes.push(mesh),
-    //                 Err(_) => todo!(),
-    //             }
-    //         });
-    //         nodes.push(meshes);
-    //     }
-    //     Ok(nodes)
-    // }
-}
+    /// Узел сцены, созданный из узла glTF с индексом `gltf_index`
+    pub fn node_by_gltf_index(&self, gltf_index: usize) -> Option<usize> {
+        self.nodes.iter().position(|node| node.gltf_index == gltf_index)
+    }
 
-/// Загружает данные сцены из GLTF-файла, преобразуя их в меши.
-///
-/// # Параметры
-/// - `scene`: Сцена из GLTF-файла, содержащая узлы (ноды) с мешами.
-/// - `buffers`: Вектор буферов, содержащих данные вершин, индексов и т. д.
-///
-/// # Возвращает
-/// - `Result<Vec<Vec<Mesh>>, Box<dyn std::error::Error>>`:  
-///   - В случае успеха — двумерный вектор мешей (по одному вектору на каждый узел).  
-///   - В случае ошибки — `Box<dyn Error>` (например, если не удалось распарсить буферы).  
-///
-/// # Пример использования
-/// ```rust
-/// let gltf_doc = gltf::Gltf::open("model.glb")?;
-/// let scene = gltf_doc.default_scene().unwrap();
-/// let buffers = load_buffers(&gltf_doc)?; // Предположим, что буферы загружены
-/// let meshes = load_scene_data(scene, buffers)?;
-/// ```
-pub fn load_scene_meshes(
-    scene: gltf::Scene,
-    buffers: Vec<BufferData>,
-    material_handles: Vec<Handle<Material>>,
-    device: Device
-)-> Result<Vec<Vec<Mesh>>,  Box<dyn std::error::Error>> {
-    let buff = to_vec(buffers);
-    let mut nodes = Vec::new();
-    for node in scene.nodes() {
-        let mut meshes = Vec::new();
-        let mesh = node.mesh().expect("Got mesh");
-        let primitives = mesh.primitives();
-        primitives.for_each(|primitive| {
-             match Mesh::from_gltf_primitive(
-                &primitive, 
-                &buff,
-                material_handles.get(primitive.material().index().unwrap_or(0)).cloned(),
-                device.clone()
-            ) {
-                Ok(mesh) =>  meshes.push(mesh),
-                Err(_) => todo!(),
+    /// Первый узел с указанным именем
+    pub fn find_node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name)
+    }
+
+    /// Ближайший меш под лучом. Проверяются только меши с копией геометрии
+    /// ([`Mesh::data`]), в исходной позе без морфов и скиннинга; луч сначала
+    /// проверяется по [`Mesh::bounds`].
+    pub fn raycast(
+        &self,
+        models: &Storage<Model>,
+        meshes: &Storage<Mesh>,
+        origin: Vec3,
+        direction: Vec3,
+    ) -> Option<ScenePick> {
+        let world = self.world_transforms();
+        let mut nearest: Option<ScenePick> = None;
+        for (index, node) in self.nodes.iter().enumerate() {
+            let Some(model) = node.model.clone().and_then(|model| models.get(model)) else {
+                continue;
+            };
+            if world[index].determinant() == 0.0 {
+                continue;
+            }
+            // Аффинное преобразование сохраняет параметр точки на луче
+            let inverse = world[index].inverse();
+            let (local_origin, local_direction) = (inverse.transform_point3(origin), inverse.transform_vector3(direction));
+            for handle in &model.meshes {
+                let Some(mesh) = meshes.get(handle.clone()) else {
+                    continue;
+                };
+                let Some(data) = &mesh.data else {
+                    continue;
+                };
+                if mesh.bounds.intersect_ray(local_origin, local_direction).is_none() {
+                    continue;
+                }
+                let Some(hit) = data.raycast(local_origin, local_direction) else {
+                    continue;
+                };
+                if nearest.as_ref().is_none_or(|pick| hit.distance < pick.hit.distance) {
+                    nearest = Some(ScenePick { node: index, mesh: handle.clone(), hit });
+                }
             }
-        });
-        nodes.push(meshes);
+        }
+        nearest
     }
-    Ok(nodes)
 }
 
+#[cfg(test)]
+mod tests {
+    use slotmap::SlotMap;
+
+    use super::*;
+    use crate::res::{mesh::procedural, CameraKey};
 
-pub fn load_children_nodes_meshes(
-    node: gltf::Node,
-    buffers: Vec<BufferData>,
-    material_handles: Vec<Handle<Material>>,
-    device: Device
-)-> Result<Vec<Mesh>,  Box<dyn std::error::Error>> {
-    let buff = to_vec(buffers);
-    let children = node.children();
-    let mut meshes = Vec::new();
-    for node in children {
-        let mesh = node.mesh().expect("Got mesh");
-        let primitives = mesh.primitives();
-        primitives.for_each(|primitive| {
-            match Mesh::from_gltf_primitive(
-                &primitive, 
-                &buff,
-                material_handles.get(primitive.material().index().unwrap_or(0)).cloned(),
-                device.clone()
-            ) {
-                Ok(mesh) =>  meshes.push(mesh),
-                Err(_) => todo!(),
+    const SCENE: &[u8] = br#"{
+        "asset": { "version": "2.0" },
+        "extensionsUsed": ["KHR_lights_punctual"],
+        "extensions": {
+            "KHR_lights_punctual": {
+                "lights": [
+                    { "type": "spot", "color": [1.0, 0.5, 0.0], "intensity": 10.0, "spot": { "outerConeAngle": 0.5 } },
+                    { "type": "directional" }
+                ]
             }
-        });
+        },
+        "cameras": [
+            { "type": "perspective", "perspective": { "yfov": 1.0, "znear": 0.1 } }
+        ],
+        "scenes": [{ "name": "main", "nodes": [0, 3] }],
+        "nodes": [
+            { "name": "root", "translation": [1.0, 0.0, 0.0], "children": [1] },
+            { "name": "arm", "scale": [2.0, 2.0, 2.0], "children": [2] },
+            { "name": "eye", "translation": [0.0, 1.0, 0.0], "camera": 0 },
+            { "name": "sun", "extensions": { "KHR_lights_punctual": { "light": 1 } } }
+        ]
+    }"#;
+
+    #[test]
+    fn test_scene_from_gltf() {
+        let gltf = gltf::Gltf::from_slice(SCENE).unwrap();
+        let lights = NodeLight::from_gltf(&gltf);
+        assert_eq!(lights.len(), 2);
+        assert_eq!(lights[0].light_type, LightType::Spot { angle: 0.5, range: f32::INFINITY });
+        assert_eq!(lights[0].color, Vec3::new(1.0, 0.5, 0.0));
+        assert_eq!(lights[1].intensity, 1.0);
+
+        let camera = Camera::from_gltf(&gltf.cameras().next().unwrap());
+        // Соотношение сторон не задано в файле и берётся от окна
+        let expected = 1.0 / (0.5f32.tan() * 1.5);
+        assert!((camera.projection_matrix(1.5).col(0).x - expected).abs() < 1e-6);
+        let mut keys: SlotMap<CameraKey, ()> = SlotMap::with_key();
+        let cameras = vec![Handle::new(keys.insert(()))];
+
+        let scene = Scene::from_gltf(&gltf.scenes().next().unwrap(), &[], &cameras, &[], &lights).unwrap();
+        assert_eq!(scene.name, "main");
+        let names: Vec<_> = scene.nodes.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, ["root", "arm", "eye", "sun"]);
+        assert_eq!(scene.roots, [0, 3]);
+        assert_eq!(scene.nodes[1].parent, Some(0));
+        assert_eq!(scene.nodes[1].children, [2]);
+        assert!(scene.nodes[2].camera.is_some());
+        assert_eq!(scene.nodes[3].light.unwrap().light_type, LightType::Directional);
+
+        // Смещение глаза масштабируется родителем: (1, 0, 0) + 2 * (0, 1, 0)
+        let world = scene.world_transforms();
+        let eye = scene.find_node("eye").unwrap();
+        assert_eq!(world[eye].transform_point3(Vec3::ZERO), Vec3::new(1.0, 2.0, 0.0));
     }
-    Ok(meshes)
-}
 
+    #[test]
+    fn test_missing_reference() {
+        let gltf = gltf::Gltf::from_slice(SCENE).unwrap();
+        match Scene::from_gltf(&gltf.scenes().next().unwrap(), &[], &[], &[], &[]) {
+            Err(AssetError::MissingReference { node: 2, kind: "camera", index: 0 }) => {}
+            other => panic!("expected missing camera, got {:?}", other.map(|scene| scene.nodes.len())),
+        }
+    }
+
+    #[test]
+    #[ignore = "requires a GPU adapter"]
+    fn test_raycast() {
+        let (device, _) = crate::res::test::test_device();
+        let mut meshes = Storage::new();
+        let mut models = Storage::new();
+        let cube = procedural::cube(Vec3::ONE);
+        let picked = meshes.load(Mesh::from_data(cube.clone(), None, &device)).unwrap();
+        // Меш без копии геометрии лучом не выбирается
+        let mut hidden = Mesh::from_data(cube, None, &device);
+        hidden.data = None;
+        let hidden = meshes.load(hidden).unwrap();
+        let model = models.load(Model { meshes: vec![picked.clone()], animations: None }).unwrap();
+        let hidden_model = models.load(Model { meshes: vec![hidden], animations: None }).unwrap();
+
+        let node = |name: &str, position: Vec3, scale: f32, model: &Handle<Model>| SceneNode {
+            name: name.to_string(),
+            gltf_index: 0,
+            transform: Transform::new(position, Quat::IDENTITY, Vec3::splat(scale)),
+            parent: None,
+            children: Vec::new(),
+            model: Some(model.clone()),
+            camera: None,
+            light: None,
+            skin: None,
+            weights: Vec::new(),
+        };
+        let scene = Scene {
+            name: String::new(),
+            nodes: vec![
+                node("hidden", Vec3::new(0.0, 0.0, 2.0), 1.0, &hidden_model),
+                node("far", Vec3::new(0.0, 0.0, -3.0), 1.0, &model),
+                node("near", Vec3::ZERO, 2.0, &model),
+            ],
+            roots: vec![0, 1, 2],
+            models: vec![model, hidden_model],
+        };
 
-// #[cfg(test)]
-// mod tests {
-//     use crate::res::buffer::load_gltf_buffers;
-
-//     use super::*;
-//     use gltf::Gltf;
-//     use std::path::Path;
-//     use std::fs::File;
-//     use std::io::Read;
-
-//     /// Загружает тестовый GLB-файл (например, куб)
-//     fn load_test_glb(path: &str) -> Result<Gltf, Box<dyn std::error::Error>> {
-//         let mut file = File::open(path)?;
-//         let mut data = Vec::new();
-//         file.read_to_end(&mut data)?;
-//         Ok(Gltf::from_slice(&data)?)
-//     }
-
-//     #[test]
-//     fn test_load_scene_data_with_real_glb() {
-//         // 1. Загружаем тестовый GLB (например, `cube.glb` из папки `test_assets`)
-//         let gltf = load_test_glb("test_assets/cube.glb").expect("Failed to load test GLB file");
-        
-//         // 2. Загружаем буферы (используя вашу функцию `load_gltf_buffers`)
-//         let buffers = load_gltf_buffers(&gltf, Some(Path::new("test_assets/")))
-//             .expect("Failed to load GLTF buffers");
-
-//         // 3. Берём сцену (например, дефолтную)
-//         let scene = gltf.default_scene().expect("GLTF has no default scene");
-
-//         // 4. Тестируем `load_scene_data`
-//         let result = load_scene_data(scene, buffers);
-//         assert!(result.is_ok(), "Failed to load scene data: {:?}", result.err());
-
-//         let meshes = result.unwrap();
-//         assert!(!meshes.is_empty(), "No nodes loaded in the scene");
-//         assert!(!meshes[0].is_empty(), "First node has no meshes");
-//     }
-// }
\ No newline at end of file
+        let pick = scene.raycast(&models, &meshes, Vec3::new(0.2, 0.0, 5.0), -Vec3::Z).unwrap();
+        assert_eq!(pick.node, 2);
+        assert_eq!(pick.mesh.key(), picked.key());
+        // Грань куба со стороной 2 на z = 1
+        assert!((pick.hit.distance - 4.0).abs() < 1e-5);
+        assert!(scene.raycast(&models, &meshes, Vec3::new(5.0, 0.0, 5.0), -Vec3::Z).is_none());
+    }
+}
@@ -0,0 +1,267 @@
+//! Скелеты (skins) glTF и матрицы суставов для скиннинга на GPU.
+//!
+//! Матрица сустава переводит вершину из пространства привязки в пространство
+//! меша: `inverse(world[меш]) * world[сустав] * inverse_bind`. Меш при этом
+//! рисуется с мировой матрицей своего узла, как и без скелета, так что
+//! преобразование узла меша сокращается, как требует спецификация glTF.
+
+use glam::Mat4;
+use wgpu::util::DeviceExt;
+
+use super::{error::AssetError, scene::Scene, Resource, ScinKey};
+
+/// Наибольшее число суставов в одном скелете, под него выделяется буфер палитры
+pub const MAX_JOINTS: usize = 256;
+
+/// Скелет: суставы в порядке индексов `JOINTS_0`
+#[derive(Debug, Clone)]
+pub struct Skin {
+    pub name: String,
+    /// Индексы узлов glTF, служащих суставами
+    pub joints: Vec<usize>,
+    /// Обратные матрицы привязки, по одной на сустав
+    pub inverse_bind_matrices: Vec<Mat4>,
+    /// Общий корень скелета, если указан в файле
+    pub skeleton: Option<usize>,
+}
+
+impl Resource for Skin {
+    type Key = ScinKey;
+    type LoadParams = Skin;
+
+    fn load(skin: Self::LoadParams) -> Result<Self, AssetError> {
+        Ok(skin)
+    }
+}
+
+impl Skin {
+    /// Читает скелет; без `inverseBindMatrices` матрицы единичные
+    pub fn from_gltf(skin: &gltf::Skin, buffers: &[Vec<u8>]) -> Result<Self, AssetError> {
+        let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+        if joints.len() > MAX_JOINTS {
+            return Err(AssetError::invalid(format!(
+                "skin has {} joints, at most {} are supported",
+                joints.len(),
+                MAX_JOINTS
+            )));
+        }
+        let reader = skin.reader(|buffer| buffers.get(buffer.index()).map(|data| data.as_slice()));
+        let inverse_bind_matrices: Vec<Mat4> = match reader.read_inverse_bind_matrices() {
+            Some(matrices) => matrices.map(|m| Mat4::from_cols_array_2d(&m)).collect(),
+            None => vec![Mat4::IDENTITY; joints.len()],
+        };
+        if inverse_bind_matrices.len() != joints.len() {
+            return Err(AssetError::invalid(format!(
+                "{} inverse bind matrices for {} joints",
+                inverse_bind_matrices.len(),
+                joints.len()
+            )));
+        }
+        Ok(Self {
+            name: skin.name().unwrap_or_default().to_string(),
+            joints,
+            inverse_bind_matrices,
+            skeleton: skin.skeleton().map(|node| node.index()),
+        })
+    }
+
+    /// Матрицы суставов для узла `mesh_node` сцены по мировым матрицам `world`
+    /// (см. [`Scene::world_transforms`]). Суставы, которых нет в сцене, дают
+    /// единичную матрицу.
+    pub fn joint_matrices(&self, scene: &Scene, world: &[Mat4], mesh_node: usize) -> Vec<Mat4> {
+        let mesh_inverse = world[mesh_node].inverse();
+        self.joints
+            .iter()
+            .zip(&self.inverse_bind_matrices)
+            .map(|(&joint, inverse_bind)| match scene.node_by_gltf_index(joint) {
+                Some(node) => mesh_inverse * world[node] * *inverse_bind,
+                None => Mat4::IDENTITY,
+            })
+            .collect()
+    }
+}
+
+/// Storage-буфер матриц суставов (`joint_matrices` в `shaders/main.wgsl`)
+#[derive(Debug, Clone)]
+pub struct JointPalette {
+    pub buffer: wgpu::Buffer,
+}
+
+impl JointPalette {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("joint_palette"),
+            contents: bytemuck::cast_slice(&[Mat4::IDENTITY.to_cols_array_2d(); MAX_JOINTS]),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+        Self { buffer }
+    }
+
+    /// Записывает матрицы, посчитанные [`Skin::joint_matrices`], обычно раз в кадр
+    pub fn update(&self, queue: &wgpu::Queue, matrices: &[Mat4]) {
+        let data: Vec<[[f32; 4]; 4]> = matrices
+            .iter()
+            .take(MAX_JOINTS)
+            .map(|matrix| matrix.to_cols_array_2d())
+            .collect();
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&data));
+    }
+
+    /// Bind group группы 2 для [`PipelineType::LitSkinned`](crate::core::PipelineType::LitSkinned)
+    pub fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        transform: &wgpu::Buffer,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("skinned_transform_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: transform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
+
+/// Записи layout'а группы 2 для скиннинга: матрица модели и палитра суставов
+pub const SKINNED_TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::VERTEX,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+];
+
+/// Смешивает матрицы суставов с весами вершины, как вершинный шейдер
+pub fn skin_matrix(matrices: &[Mat4], joints: [u16; 4], weights: [f32; 4]) -> Mat4 {
+    joints
+        .iter()
+        .zip(weights)
+        .filter(|(_, weight)| *weight > 0.0)
+        .fold(Mat4::ZERO, |sum, (&joint, weight)| {
+            sum + matrices.get(joint as usize).copied().unwrap_or(Mat4::IDENTITY) * weight
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Quat, Vec3};
+    use slotmap::SlotMap;
+
+    use super::*;
+    use crate::res::Handle;
+
+    const ARM: &[u8] = br#"{
+        "asset": { "version": "2.0" },
+        "scenes": [{ "nodes": [0, 2] }],
+        "nodes": [
+            { "name": "shoulder", "children": [1] },
+            { "name": "elbow", "translation": [0.0, 1.0, 0.0] },
+            { "name": "arm", "translation": [5.0, 0.0, 0.0], "skin": 0 }
+        ],
+        "skins": [{ "joints": [0, 1], "inverseBindMatrices": 0, "skeleton": 0 }],
+        "buffers": [{ "byteLength": 128 }],
+        "bufferViews": [{ "buffer": 0, "byteLength": 128 }],
+        "accessors": [{ "bufferView": 0, "componentType": 5126, "count": 2, "type": "MAT4" }]
+    }"#;
+
+    /// Обратные матрицы привязки для позы, в которой локоть на (0, 1, 0)
+    fn arm_buffers() -> Vec<Vec<u8>> {
+        let matrices = [Mat4::IDENTITY, Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0))];
+        vec![bytemuck::cast_slice(&matrices.map(|m| m.to_cols_array())).to_vec()]
+    }
+
+    fn arm() -> (Scene, Skin) {
+        let gltf = gltf::Gltf::from_slice(ARM).unwrap();
+        let skin = Skin::from_gltf(&gltf.skins().next().unwrap(), &arm_buffers()).unwrap();
+        let mut keys: SlotMap<ScinKey, ()> = SlotMap::with_key();
+        let skins = [Handle::new(keys.insert(()))];
+        let scene = Scene::from_gltf(&gltf.scenes().next().unwrap(), &[], &[], &skins, &[]).unwrap();
+        (scene, skin)
+    }
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        assert!(actual.abs_diff_eq(expected, 1e-5), "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_skin_from_gltf() {
+        let (_, skin) = arm();
+        assert_eq!(skin.joints, [0, 1]);
+        assert_eq!(skin.skeleton, Some(0));
+        assert_eq!(skin.inverse_bind_matrices[1].w_axis.y, -1.0);
+    }
+
+    /// Положение вершины в мире: модель узла меша, затем скиннинг
+    fn skinned_world(scene: &Scene, skin: &Skin, joints: [u16; 4], weights: [f32; 4], point: Vec3) -> Vec3 {
+        let world = scene.world_transforms();
+        let mesh_node = scene.find_node("arm").unwrap();
+        let matrices = skin.joint_matrices(scene, &world, mesh_node);
+        (world[mesh_node] * skin_matrix(&matrices, joints, weights)).transform_point3(point)
+    }
+
+    #[test]
+    fn test_bind_pose() {
+        // В позе привязки вершина остаётся на месте, смещение узла меша не учитывается
+        let (scene, skin) = arm();
+        let hand = Vec3::new(0.0, 2.0, 0.0);
+        assert_near(skinned_world(&scene, &skin, [0, 1, 0, 0], [0.3, 0.7, 0.0, 0.0], hand), hand);
+    }
+
+    #[test]
+    fn test_rotated_pose() {
+        // Плечо повёрнуто на 90° вокруг Z: точка кисти (0, 2, 0) уходит в (-2, 0, 0)
+        let (mut scene, skin) = arm();
+        let shoulder = scene.find_node("shoulder").unwrap();
+        scene.nodes[shoulder].transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+
+        let hand = Vec3::new(0.0, 2.0, 0.0);
+        let elbow_only = skinned_world(&scene, &skin, [1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0], hand);
+        assert_near(elbow_only, Vec3::new(-2.0, 0.0, 0.0));
+
+        // Пополам между суставами: оба поворачивают одинаково, результат тот же
+        let blended = skinned_world(&scene, &skin, [0, 1, 0, 0], [0.5, 0.5, 0.0, 0.0], hand);
+        assert_near(blended, Vec3::new(-2.0, 0.0, 0.0));
+
+        // Локоть согнут на 90° относительно плеча: кисть на (-1, 1, 0)
+        scene.nodes[shoulder].transform.rotation = Quat::IDENTITY;
+        let elbow = scene.find_node("elbow").unwrap();
+        scene.nodes[elbow].transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+        let bent = skinned_world(&scene, &skin, [1, 0, 0, 0], [1.0, 0.0, 0.0, 0.0], hand);
+        assert_near(bent, Vec3::new(-1.0, 1.0, 0.0));
+
+        // Половина веса на плече: середина между (0, 2, 0) и (-1, 1, 0)
+        let half = skinned_world(&scene, &skin, [0, 1, 0, 0], [0.5, 0.5, 0.0, 0.0], hand);
+        assert_near(half, Vec3::new(-0.5, 1.5, 0.0));
+    }
+
+    #[test]
+    fn test_mismatched_inverse_bind_matrices() {
+        let json = String::from_utf8(ARM.to_vec()).unwrap().replace(r#""count": 2"#, r#""count": 1"#);
+        let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+        assert!(Skin::from_gltf(&gltf.skins().next().unwrap(), &arm_buffers()).is_err());
+    }
+}
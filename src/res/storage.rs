@@ -1,12 +1,29 @@
-use std::marker::PhantomData;
+use std::{collections::HashMap, marker::PhantomData};
 
-use slotmap::SlotMap;
+use slotmap::{SecondaryMap, SlotMap};
 
-use super::{Handle, Resource};
+use super::{error::AssetError, Handle, Resource};
 
+/// Учёт использования ресурса
+#[derive(Debug, Clone)]
+struct Entry {
+    ref_count: usize,
+    /// Путь или URI, по которому ресурс загружен
+    path: Option<String>,
+    /// Сколько раз ресурс подменялся через [`Storage::replace`]
+    version: u64,
+}
+
+/// Хранилище ресурсов одного типа.
+///
+/// `Handle<T>` сам по себе слабый: после удаления ресурса `get` вернёт `None`.
+/// Владение задаётся счётчиком ссылок: `load` даёт счётчик 1, [`Storage::retain`]
+/// увеличивает его, [`Storage::release`] уменьшает и удаляет ресурс на нуле.
 #[derive(Debug, Clone)] 
 pub struct Storage<T: Resource> {
     slotmap: SlotMap<T::Key, T>,
+    entries: SecondaryMap<T::Key, Entry>,
+    paths: HashMap<String, T::Key>,
 }
 
 impl<T: Resource> Default for Storage<T> {
@@ -19,6 +36,8 @@ impl<T: Resource> Storage<T> {
     pub fn new() -> Self {
         Self {
             slotmap: SlotMap::with_key(),
+            entries: SecondaryMap::new(),
+            paths: HashMap::new(),
         }
     }
 
@@ -31,16 +50,79 @@ impl<T: Resource> Storage<T> {
         )
     }
     
-    pub fn load(&mut self, params: T::LoadParams) -> Result<Handle<T>, Box<dyn std::error::Error>> {
+    pub fn load(&mut self, params: T::LoadParams) -> Result<Handle<T>, AssetError> {
         let resource = T::load(params)?;
         let key = self.slotmap.insert(resource);
+        self.entries.insert(key, Entry { ref_count: 1, path: None, version: 0 });
         Ok(Handle {
             key,
             _phantom: PhantomData,
         })
     }
 
-    pub fn load_all<I>(&mut self, params_iter: I) -> Result<Vec<Handle<T>>, Box<dyn std::error::Error>>
+    /// Загружает ресурс и запоминает путь, по которому его потом найдёт [`Storage::find`]
+    pub fn load_with_path(
+        &mut self,
+        path: impl Into<String>,
+        params: T::LoadParams,
+    ) -> Result<Handle<T>, AssetError> {
+        let path = path.into();
+        let handle = self.load(params)?;
+        if let Some(previous) = self.paths.insert(path.clone(), handle.key) {
+            if let Some(entry) = self.entries.get_mut(previous) {
+                entry.path = None;
+            }
+        }
+        if let Some(entry) = self.entries.get_mut(handle.key) {
+            entry.path = Some(path);
+        }
+        Ok(handle)
+    }
+
+    /// Ресурс, загруженный по этому пути
+    pub fn find(&self, path: &str) -> Option<Handle<T>> {
+        self.paths.get(path).map(|&key| Handle::new(key))
+    }
+
+    /// Путь, с которым ресурс был загружен
+    pub fn path(&self, handle: Handle<T>) -> Option<&str> {
+        self.entries.get(handle.key)?.path.as_deref()
+    }
+
+    /// Добавляет владельца ресурса; `false`, если ресурса уже нет
+    pub fn retain(&mut self, handle: Handle<T>) -> bool {
+        match self.entries.get_mut(handle.key) {
+            Some(entry) => {
+                entry.ref_count += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Убирает владельца; последний удаляет ресурс и возвращает его
+    pub fn release(&mut self, handle: Handle<T>) -> Option<T> {
+        let entry = self.entries.get_mut(handle.key)?;
+        entry.ref_count = entry.ref_count.saturating_sub(1);
+        if entry.ref_count > 0 {
+            return None;
+        }
+        self.remove(handle)
+    }
+
+    pub fn ref_count(&self, handle: Handle<T>) -> usize {
+        self.entries.get(handle.key).map_or(0, |entry| entry.ref_count)
+    }
+
+    pub fn len(&self) -> usize {
+        self.slotmap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slotmap.is_empty()
+    }
+
+    pub fn load_all<I>(&mut self, params_iter: I) -> Result<Vec<Handle<T>>, AssetError>
     where
         I: IntoIterator<Item = T::LoadParams>
     {
@@ -55,7 +137,28 @@ impl<T: Resource> Storage<T> {
         self.slotmap.get_mut(handle.key)
     }
     
+    /// Подменяет ресурс, сохраняя ключ, путь и счётчик, и увеличивает его
+    /// версию; возвращает прежний. `None`, если ресурса уже нет, новый тогда
+    /// не добавляется.
+    pub fn replace(&mut self, handle: Handle<T>, resource: T) -> Option<T> {
+        let current = self.slotmap.get_mut(handle.key)?;
+        if let Some(entry) = self.entries.get_mut(handle.key) {
+            entry.version += 1;
+        }
+        Some(std::mem::replace(current, resource))
+    }
+
+    /// Версия ресурса: растёт при каждой подмене, так что кэши GPU-объектов,
+    /// построенных из ресурса, видят перезагрузку. 0 для отсутствующего.
+    pub fn version(&self, handle: Handle<T>) -> u64 {
+        self.entries.get(handle.key).map_or(0, |entry| entry.version)
+    }
+
+    /// Удаляет ресурс независимо от счётчика ссылок
     pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        if let Some(Entry { path: Some(path), .. }) = self.entries.remove(handle.key) {
+            self.paths.remove(&path);
+        }
         self.slotmap.remove(handle.key)
     }
     
@@ -66,3 +169,55 @@ impl<T: Resource> Storage<T> {
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::camera::{Camera, Projection};
+
+    fn camera(name: &str) -> Camera {
+        Camera {
+            name: name.to_string(),
+            projection: Projection::Orthographic { xmag: 1.0, ymag: 1.0, znear: 0.0, zfar: 1.0 },
+        }
+    }
+
+    #[test]
+    fn test_ref_counting() {
+        let mut storage: Storage<Camera> = Storage::new();
+        let handle = storage.load_with_path("scene.gltf#camera0", camera("a")).unwrap();
+        assert_eq!(storage.find("scene.gltf#camera0").map(|h| h.key()), Some(handle.key()));
+        assert_eq!(storage.path(handle.clone()), Some("scene.gltf#camera0"));
+
+        assert!(storage.retain(handle.clone()));
+        assert_eq!(storage.ref_count(handle.clone()), 2);
+        assert!(storage.release(handle.clone()).is_none());
+        assert!(storage.contains(handle.clone()));
+
+        // Последний владелец удаляет ресурс и путь
+        assert_eq!(storage.release(handle.clone()).map(|camera| camera.name), Some("a".to_string()));
+        assert!(!storage.contains(handle.clone()));
+        assert!(storage.find("scene.gltf#camera0").is_none());
+        assert!(!storage.retain(handle));
+        assert!(storage.is_empty());
+    }
+
+    #[test]
+    fn test_replace_keeps_key() {
+        let mut storage: Storage<Camera> = Storage::new();
+        let handle = storage.load_with_path("scene.gltf#camera0", camera("old")).unwrap();
+        storage.retain(handle.clone());
+        assert_eq!(storage.version(handle.clone()), 0);
+
+        assert_eq!(storage.replace(handle.clone(), camera("new")).map(|camera| camera.name), Some("old".to_string()));
+        assert_eq!(storage.version(handle.clone()), 1);
+        assert_eq!(storage.get(handle.clone()).map(|camera| camera.name.as_str()), Some("new"));
+        assert_eq!(storage.ref_count(handle.clone()), 2);
+        assert_eq!(storage.find("scene.gltf#camera0").map(|h| h.key()), Some(handle.key()));
+
+        storage.remove(handle.clone());
+        assert!(storage.replace(handle.clone(), camera("late")).is_none());
+        assert_eq!(storage.version(handle), 0);
+        assert!(storage.is_empty());
+    }
+}
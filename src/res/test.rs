@@ -1,3 +1,15 @@
+/// Устройство для тестов, которым нужен GPU. Такие тесты помечены
+/// `#[ignore]` и запускаются через `cargo test -- --ignored`; без адаптера
+/// тест падает, а не проходит молча
+#[cfg(test)]
+pub(crate) fn test_device() -> (wgpu::Device, wgpu::Queue) {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        .expect("GPU test requires a wgpu adapter");
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+        .expect("failed to create a wgpu device for the GPU test")
+}
+
 #[cfg(test)]
 mod tests {
     use gltf::Gltf;
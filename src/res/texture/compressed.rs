@@ -0,0 +1,597 @@
+//! Сжатые текстуры в контейнерах KTX2 и DDS.
+//!
+//! Поддерживаются двумерные текстуры с mip-уровнями в форматах BC1–BC7,
+//! ETC2 и ASTC 4x4, а также несжатый RGBA8. Если адаптер не умеет формат,
+//! BC1–BC5 распаковываются на CPU в RGBA8. Декодеров BC6H, BC7, ETC2 и ASTC
+//! нет: такие текстуры загружаются только адаптерами с нужной
+//! `TEXTURE_COMPRESSION_*` (ETC2 и ASTC обычно есть на мобильных GPU, BC —
+//! на настольных), иначе [`GpuTexture::from_compressed`] возвращает
+//! [`CompressedTextureError::NoFallback`], а импорт glTF берёт обычный
+//! `source` текстуры, если он есть. Суперсжатие KTX2 (BasisLZ, Zstandard)
+//! требует транскодера и пока не поддерживается.
+//!
+//! [`GpuTexture::from_compressed`]: super::gpu_texture::GpuTexture::from_compressed
+
+use super::TextureKind;
+
+const DDS_MAGIC: &[u8; 4] = b"DDS ";
+const DDS_HEADER_SIZE: usize = 128;
+const DDS_DX10_HEADER_SIZE: usize = 20;
+const DDS_CUBEMAP: u32 = 0x200;
+const DDS_FOURCC: u32 = 0x4;
+const DDS_RGB: u32 = 0x40;
+
+const KTX2_MAGIC: [u8; 12] = [0xAB, b'K', b'T', b'X', b' ', b'2', b'0', 0xBB, b'\r', b'\n', 0x1A, b'\n'];
+const KTX2_LEVEL_INDEX_OFFSET: usize = 80;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum CompressedTextureError {
+    #[error("unknown texture container")]
+    UnknownContainer,
+    #[error("texture container is truncated")]
+    Truncated,
+    #[error("unsupported texture format: {0}")]
+    UnsupportedFormat(String),
+    #[error("unsupported texture layout: {0}")]
+    UnsupportedLayout(String),
+    #[error("KTX2 supercompression scheme {0} needs a transcoder")]
+    Supercompressed(u32),
+    #[error("{0:?} is not supported by the adapter and cannot be decompressed on the CPU (only BC1-BC5 can)")]
+    NoFallback(wgpu::TextureFormat),
+}
+
+/// Контейнер изображения, определённый по сигнатуре
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageContainer {
+    Ktx2,
+    Dds,
+    /// PNG, JPEG и прочее, что декодирует `image`
+    Other,
+}
+
+impl ImageContainer {
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&KTX2_MAGIC) {
+            ImageContainer::Ktx2
+        } else if bytes.starts_with(DDS_MAGIC) {
+            ImageContainer::Dds
+        } else {
+            ImageContainer::Other
+        }
+    }
+}
+
+/// Текстура в формате GPU: уровни идут от нулевого к меньшим
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressedImage {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    pub levels: Vec<Vec<u8>>,
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, CompressedTextureError> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(CompressedTextureError::Truncated)
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, CompressedTextureError> {
+    let low = read_u32(bytes, offset)? as u64;
+    let high = read_u32(bytes, offset + 4)? as u64;
+    Ok(low | (high << 32))
+}
+
+/// KTX2, который без транскодера не разобрать: BasisLZ или zstd-суперсжатие
+/// либо неопределённый vkFormat (UASTC и ETC1S из `KHR_texture_basisu`)
+pub fn ktx2_needs_transcoder(bytes: &[u8]) -> bool {
+    match (read_u32(bytes, 12), read_u32(bytes, 44)) {
+        (Ok(vk_format), Ok(supercompression)) => vk_format == 0 || supercompression != 0,
+        _ => false,
+    }
+}
+
+/// Размер уровня `level` в байтах; `None`, если он не помещается в `usize`
+/// (размеры берутся из непроверенного заголовка)
+pub fn level_size(format: wgpu::TextureFormat, width: u32, height: u32, level: u32) -> Option<usize> {
+    let (block_width, block_height) = format.block_dimensions();
+    let block_size = format.block_copy_size(None).unwrap_or(4);
+    let width = width.checked_shr(level).unwrap_or(0).max(1);
+    let height = height.checked_shr(level).unwrap_or(0).max(1);
+    let size = u64::from(width.div_ceil(block_width))
+        .checked_mul(u64::from(height.div_ceil(block_height)))?
+        .checked_mul(u64::from(block_size))?;
+    usize::try_from(size).ok()
+}
+
+/// Проверяет, что у текстуры не больше mip-уровней, чем допускают её размеры
+fn check_level_count(width: u32, height: u32, level_count: u32) -> Result<(), CompressedTextureError> {
+    let max_levels = u32::BITS - width.max(height).max(1).leading_zeros();
+    if level_count > max_levels {
+        return Err(CompressedTextureError::UnsupportedLayout(format!(
+            "{} mip levels for {}x{}",
+            level_count, width, height
+        )));
+    }
+    Ok(())
+}
+
+/// Байты `offset..offset + length`; переполнение считается обрезанным файлом
+fn read_range(bytes: &[u8], offset: usize, length: usize) -> Result<&[u8], CompressedTextureError> {
+    offset
+        .checked_add(length)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or(CompressedTextureError::Truncated)
+}
+
+/// Приводит формат к цветовому пространству, которое требует `kind`
+fn with_color_space(format: wgpu::TextureFormat, kind: TextureKind) -> wgpu::TextureFormat {
+    if kind.is_srgb() {
+        format.add_srgb_suffix()
+    } else {
+        format.remove_srgb_suffix()
+    }
+}
+
+impl CompressedImage {
+    /// Разбирает KTX2 или DDS; `kind` выбирает sRGB или линейный вариант формата
+    pub fn from_bytes(bytes: &[u8], kind: TextureKind) -> Result<Self, CompressedTextureError> {
+        let mut image = match ImageContainer::detect(bytes) {
+            ImageContainer::Ktx2 => Self::parse_ktx2(bytes)?,
+            ImageContainer::Dds => Self::parse_dds(bytes)?,
+            ImageContainer::Other => return Err(CompressedTextureError::UnknownContainer),
+        };
+        image.format = with_color_space(image.format, kind);
+        Ok(image)
+    }
+
+    fn parse_dds(bytes: &[u8]) -> Result<Self, CompressedTextureError> {
+        let height = read_u32(bytes, 12)?;
+        let width = read_u32(bytes, 16)?;
+        let level_count = read_u32(bytes, 28)?.max(1);
+        let pixel_flags = read_u32(bytes, 80)?;
+        let four_cc = bytes.get(84..88).ok_or(CompressedTextureError::Truncated)?;
+        if read_u32(bytes, 112)? & DDS_CUBEMAP != 0 {
+            return Err(CompressedTextureError::UnsupportedLayout("DDS cube map".into()));
+        }
+
+        let (format, data_offset) = if pixel_flags & DDS_FOURCC != 0 && four_cc == b"DX10" {
+            let dxgi = read_u32(bytes, DDS_HEADER_SIZE)?;
+            let array_size = read_u32(bytes, DDS_HEADER_SIZE + 12)?;
+            if array_size > 1 {
+                return Err(CompressedTextureError::UnsupportedLayout("DDS texture array".into()));
+            }
+            (dxgi_format(dxgi)?, DDS_HEADER_SIZE + DDS_DX10_HEADER_SIZE)
+        } else if pixel_flags & DDS_FOURCC != 0 {
+            let format = match four_cc {
+                b"DXT1" => wgpu::TextureFormat::Bc1RgbaUnorm,
+                b"DXT2" | b"DXT3" => wgpu::TextureFormat::Bc2RgbaUnorm,
+                b"DXT4" | b"DXT5" => wgpu::TextureFormat::Bc3RgbaUnorm,
+                b"ATI1" | b"BC4U" => wgpu::TextureFormat::Bc4RUnorm,
+                b"ATI2" | b"BC5U" => wgpu::TextureFormat::Bc5RgUnorm,
+                other => {
+                    return Err(CompressedTextureError::UnsupportedFormat(
+                        String::from_utf8_lossy(other).into_owned(),
+                    ))
+                }
+            };
+            (format, DDS_HEADER_SIZE)
+        } else if pixel_flags & DDS_RGB != 0 && read_u32(bytes, 88)? == 32 && read_u32(bytes, 92)? == 0xff {
+            (wgpu::TextureFormat::Rgba8Unorm, DDS_HEADER_SIZE)
+        } else {
+            return Err(CompressedTextureError::UnsupportedFormat(format!(
+                "DDS pixel format flags {:#x}",
+                pixel_flags
+            )));
+        };
+
+        check_level_count(width, height, level_count)?;
+        let mut offset = data_offset;
+        let levels = (0..level_count)
+            .map(|level| {
+                let size = level_size(format, width, height, level).ok_or(CompressedTextureError::Truncated)?;
+                let data = read_range(bytes, offset, size)?;
+                offset += size;
+                Ok(data.to_vec())
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            levels,
+        })
+    }
+
+    fn parse_ktx2(bytes: &[u8]) -> Result<Self, CompressedTextureError> {
+        let vk_format = read_u32(bytes, 12)?;
+        let width = read_u32(bytes, 20)?;
+        let height = read_u32(bytes, 24)?.max(1);
+        let depth = read_u32(bytes, 28)?;
+        let layer_count = read_u32(bytes, 32)?;
+        let face_count = read_u32(bytes, 36)?;
+        let level_count = read_u32(bytes, 40)?.max(1);
+        let supercompression = read_u32(bytes, 44)?;
+
+        if depth > 1 || layer_count > 1 || face_count != 1 {
+            return Err(CompressedTextureError::UnsupportedLayout(format!(
+                "KTX2 depth {}, layers {}, faces {}",
+                depth, layer_count, face_count
+            )));
+        }
+        if supercompression != 0 {
+            return Err(CompressedTextureError::Supercompressed(supercompression));
+        }
+        let format = vk_format_to_wgpu(vk_format)?;
+        check_level_count(width, height, level_count)?;
+
+        let levels = (0..level_count)
+            .map(|level| {
+                let entry = KTX2_LEVEL_INDEX_OFFSET + level as usize * 24;
+                let offset = usize::try_from(read_u64(bytes, entry)?).map_err(|_| CompressedTextureError::Truncated)?;
+                let length =
+                    usize::try_from(read_u64(bytes, entry + 8)?).map_err(|_| CompressedTextureError::Truncated)?;
+                // Без суперсжатия уровень занимает ровно столько, сколько требуют размеры
+                if Some(length) != level_size(format, width, height, level) {
+                    return Err(CompressedTextureError::Truncated);
+                }
+                read_range(bytes, offset, length).map(<[u8]>::to_vec)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            width,
+            height,
+            format,
+            levels,
+        })
+    }
+
+    /// Можно ли загрузить уровни на GPU как есть
+    pub fn is_supported_by(&self, features: wgpu::Features) -> bool {
+        let (block_width, block_height) = self.format.block_dimensions();
+        features.contains(self.format.required_features())
+            && self.width.is_multiple_of(block_width)
+            && self.height.is_multiple_of(block_height)
+    }
+
+    /// Загружается ли текстура на устройство с `features`: как есть или
+    /// после распаковки на CPU
+    pub fn is_loadable_on(&self, features: wgpu::Features) -> bool {
+        use wgpu::TextureFormat as F;
+        let has_cpu_decoder = matches!(
+            self.format.remove_srgb_suffix(),
+            F::Bc1RgbaUnorm | F::Bc2RgbaUnorm | F::Bc3RgbaUnorm | F::Bc4RUnorm | F::Bc5RgUnorm | F::Rgba8Unorm
+        );
+        has_cpu_decoder || self.is_supported_by(features)
+    }
+
+    /// Распаковывает уровни в RGBA8 (запасной путь для BC1–BC5)
+    pub fn decompress(&self) -> Result<Vec<image::RgbaImage>, CompressedTextureError> {
+        use wgpu::TextureFormat as F;
+        let decode_block: fn(&[u8]) -> [[u8; 4]; 16] = match self.format.remove_srgb_suffix() {
+            F::Bc1RgbaUnorm => decode_bc1_block,
+            F::Bc2RgbaUnorm => decode_bc2_block,
+            F::Bc3RgbaUnorm => decode_bc3_block,
+            F::Bc4RUnorm => decode_bc4_block,
+            F::Bc5RgUnorm => decode_bc5_block,
+            F::Rgba8Unorm => {
+                return self
+                    .levels
+                    .iter()
+                    .enumerate()
+                    .map(|(level, data)| {
+                        let (width, height) = self.level_dimensions(level as u32);
+                        image::RgbaImage::from_raw(width, height, data.clone())
+                            .ok_or(CompressedTextureError::Truncated)
+                    })
+                    .collect();
+            }
+            other => return Err(CompressedTextureError::NoFallback(other)),
+        };
+        let block_size = self.format.block_copy_size(None).unwrap_or(16) as usize;
+
+        self.levels
+            .iter()
+            .enumerate()
+            .map(|(level, data)| {
+                let (width, height) = self.level_dimensions(level as u32);
+                let blocks_x = width.div_ceil(4);
+                let mut image = image::RgbaImage::new(width, height);
+                for (index, block) in data.chunks_exact(block_size).enumerate() {
+                    let (bx, by) = (index as u32 % blocks_x, index as u32 / blocks_x);
+                    for (texel, color) in decode_block(block).into_iter().enumerate() {
+                        let (x, y) = (bx * 4 + texel as u32 % 4, by * 4 + texel as u32 / 4);
+                        if x < width && y < height {
+                            image.put_pixel(x, y, image::Rgba(color));
+                        }
+                    }
+                }
+                Ok(image)
+            })
+            .collect()
+    }
+
+    pub fn level_dimensions(&self, level: u32) -> (u32, u32) {
+        let dimension = |size: u32| size.checked_shr(level).unwrap_or(0).max(1);
+        (dimension(self.width), dimension(self.height))
+    }
+}
+
+fn dxgi_format(dxgi: u32) -> Result<wgpu::TextureFormat, CompressedTextureError> {
+    use wgpu::TextureFormat as F;
+    Ok(match dxgi {
+        28 => F::Rgba8Unorm,
+        29 => F::Rgba8UnormSrgb,
+        71 => F::Bc1RgbaUnorm,
+        72 => F::Bc1RgbaUnormSrgb,
+        74 => F::Bc2RgbaUnorm,
+        75 => F::Bc2RgbaUnormSrgb,
+        77 => F::Bc3RgbaUnorm,
+        78 => F::Bc3RgbaUnormSrgb,
+        80 => F::Bc4RUnorm,
+        81 => F::Bc4RSnorm,
+        83 => F::Bc5RgUnorm,
+        84 => F::Bc5RgSnorm,
+        95 => F::Bc6hRgbUfloat,
+        96 => F::Bc6hRgbFloat,
+        98 => F::Bc7RgbaUnorm,
+        99 => F::Bc7RgbaUnormSrgb,
+        other => return Err(CompressedTextureError::UnsupportedFormat(format!("DXGI format {}", other))),
+    })
+}
+
+fn vk_format_to_wgpu(vk_format: u32) -> Result<wgpu::TextureFormat, CompressedTextureError> {
+    use wgpu::{AstcBlock, AstcChannel, TextureFormat as F};
+    Ok(match vk_format {
+        0 => {
+            return Err(CompressedTextureError::UnsupportedFormat(
+                "KTX2 without vkFormat (Basis Universal)".into(),
+            ))
+        }
+        37 => F::Rgba8Unorm,
+        43 => F::Rgba8UnormSrgb,
+        133 => F::Bc1RgbaUnorm,
+        134 => F::Bc1RgbaUnormSrgb,
+        135 => F::Bc2RgbaUnorm,
+        136 => F::Bc2RgbaUnormSrgb,
+        137 => F::Bc3RgbaUnorm,
+        138 => F::Bc3RgbaUnormSrgb,
+        139 => F::Bc4RUnorm,
+        140 => F::Bc4RSnorm,
+        141 => F::Bc5RgUnorm,
+        142 => F::Bc5RgSnorm,
+        143 => F::Bc6hRgbUfloat,
+        144 => F::Bc6hRgbFloat,
+        145 => F::Bc7RgbaUnorm,
+        146 => F::Bc7RgbaUnormSrgb,
+        147 => F::Etc2Rgb8Unorm,
+        148 => F::Etc2Rgb8UnormSrgb,
+        151 => F::Etc2Rgba8Unorm,
+        152 => F::Etc2Rgba8UnormSrgb,
+        157 => F::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::Unorm,
+        },
+        158 => F::Astc {
+            block: AstcBlock::B4x4,
+            channel: AstcChannel::UnormSrgb,
+        },
+        other => return Err(CompressedTextureError::UnsupportedFormat(format!("vkFormat {}", other))),
+    })
+}
+
+fn expand_565(color: u16) -> [u8; 3] {
+    let r = ((color >> 11) & 0x1f) as u8;
+    let g = ((color >> 5) & 0x3f) as u8;
+    let b = (color & 0x1f) as u8;
+    [(r << 3) | (r >> 2), (g << 2) | (g >> 4), (b << 3) | (b >> 2)]
+}
+
+/// Цветовой блок BC1; `four_color` принудительно для BC2/BC3
+fn decode_color_block(block: &[u8], four_color: bool) -> [[u8; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let (e0, e1) = (expand_565(c0), expand_565(c1));
+    let mix = |a: u8, b: u8, wa: u16, wb: u16| ((a as u16 * wa + b as u16 * wb) / (wa + wb)) as u8;
+
+    let mut palette = [[0u8; 4]; 4];
+    palette[0] = [e0[0], e0[1], e0[2], 255];
+    palette[1] = [e1[0], e1[1], e1[2], 255];
+    if four_color || c0 > c1 {
+        palette[2] = [mix(e0[0], e1[0], 2, 1), mix(e0[1], e1[1], 2, 1), mix(e0[2], e1[2], 2, 1), 255];
+        palette[3] = [mix(e0[0], e1[0], 1, 2), mix(e0[1], e1[1], 1, 2), mix(e0[2], e1[2], 1, 2), 255];
+    } else {
+        palette[2] = [mix(e0[0], e1[0], 1, 1), mix(e0[1], e1[1], 1, 1), mix(e0[2], e1[2], 1, 1), 255];
+        palette[3] = [0, 0, 0, 0];
+    }
+
+    let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    std::array::from_fn(|texel| palette[((indices >> (2 * texel)) & 0b11) as usize])
+}
+
+/// Блок из двух опорных значений и 3-битных индексов (альфа BC3, каналы BC4/BC5)
+fn decode_interpolated_block(block: &[u8]) -> [u8; 16] {
+    let (a0, a1) = (block[0] as u32, block[1] as u32);
+    let mut palette = [0u8; 8];
+    palette[0] = a0 as u8;
+    palette[1] = a1 as u8;
+    if a0 > a1 {
+        for i in 1..7 {
+            palette[i + 1] = (((7 - i as u32) * a0 + i as u32 * a1) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            palette[i + 1] = (((5 - i as u32) * a0 + i as u32 * a1) / 5) as u8;
+        }
+        palette[6] = 0;
+        palette[7] = 255;
+    }
+
+    let bits = block[2..8]
+        .iter()
+        .enumerate()
+        .fold(0u64, |acc, (i, byte)| acc | ((*byte as u64) << (8 * i)));
+    std::array::from_fn(|texel| palette[((bits >> (3 * texel)) & 0b111) as usize])
+}
+
+fn decode_bc1_block(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_color_block(block, false)
+}
+
+fn decode_bc2_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let mut colors = decode_color_block(&block[8..16], true);
+    for (texel, color) in colors.iter_mut().enumerate() {
+        let alpha = (block[texel / 2] >> (4 * (texel % 2))) & 0x0f;
+        color[3] = alpha * 17;
+    }
+    colors
+}
+
+fn decode_bc3_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let alpha = decode_interpolated_block(&block[0..8]);
+    let mut colors = decode_color_block(&block[8..16], true);
+    for (color, alpha) in colors.iter_mut().zip(alpha) {
+        color[3] = alpha;
+    }
+    colors
+}
+
+fn decode_bc4_block(block: &[u8]) -> [[u8; 4]; 16] {
+    decode_interpolated_block(block).map(|red| [red, 0, 0, 255])
+}
+
+fn decode_bc5_block(block: &[u8]) -> [[u8; 4]; 16] {
+    let red = decode_interpolated_block(&block[0..8]);
+    let green = decode_interpolated_block(&block[8..16]);
+    std::array::from_fn(|texel| [red[texel], green[texel], 0, 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BC1 блок 4x4: красный и синий, первая строка красная, остальные синие
+    const RED_BLUE_BC1: [u8; 8] = [0x00, 0xf8, 0x1f, 0x00, 0x00, 0x55, 0x55, 0x55];
+
+    fn dds_dxt1(levels: &[&[u8]], width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = vec![0u8; DDS_HEADER_SIZE];
+        bytes[0..4].copy_from_slice(DDS_MAGIC);
+        bytes[4..8].copy_from_slice(&124u32.to_le_bytes());
+        bytes[12..16].copy_from_slice(&height.to_le_bytes());
+        bytes[16..20].copy_from_slice(&width.to_le_bytes());
+        bytes[28..32].copy_from_slice(&(levels.len() as u32).to_le_bytes());
+        bytes[80..84].copy_from_slice(&DDS_FOURCC.to_le_bytes());
+        bytes[84..88].copy_from_slice(b"DXT1");
+        for level in levels {
+            bytes.extend_from_slice(level);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_dds() {
+        let bytes = dds_dxt1(&[&[RED_BLUE_BC1, RED_BLUE_BC1].concat(), &RED_BLUE_BC1], 8, 4);
+        assert_eq!(ImageContainer::detect(&bytes), ImageContainer::Dds);
+
+        let image = CompressedImage::from_bytes(&bytes, TextureKind::Color).unwrap();
+        assert_eq!((image.width, image.height), (8, 4));
+        assert_eq!(image.format, wgpu::TextureFormat::Bc1RgbaUnormSrgb);
+        assert_eq!(image.levels.len(), 2);
+        assert!(image.is_supported_by(wgpu::Features::TEXTURE_COMPRESSION_BC));
+        assert!(!image.is_supported_by(wgpu::Features::empty()));
+
+        let truncated = &bytes[..bytes.len() - 1];
+        assert_eq!(
+            CompressedImage::from_bytes(truncated, TextureKind::Color),
+            Err(CompressedTextureError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_decompress_bc1() {
+        let bytes = dds_dxt1(&[&RED_BLUE_BC1], 4, 4);
+        let image = CompressedImage::from_bytes(&bytes, TextureKind::Data).unwrap();
+        let levels = image.decompress().unwrap();
+
+        assert_eq!(levels[0].get_pixel(3, 0).0, [255, 0, 0, 255]);
+        assert_eq!(levels[0].get_pixel(0, 1).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_decode_bc3_alpha() {
+        // Альфа: a0 = 255, a1 = 0, индексы 0 и 1 чередуются
+        let mut block = [0u8; 16];
+        block[0] = 255;
+        block[2] = 0b1000;
+        block[8..16].copy_from_slice(&RED_BLUE_BC1);
+        let texels = decode_bc3_block(&block);
+        assert_eq!(texels[0][3], 255);
+        assert_eq!(texels[1][3], 0);
+        assert_eq!(texels[2][3], 255);
+    }
+
+    fn ktx2(vk_format: u32, supercompression: u32, level: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0u8; KTX2_LEVEL_INDEX_OFFSET + 24];
+        bytes[0..12].copy_from_slice(&KTX2_MAGIC);
+        for (offset, value) in [(12, vk_format), (16, 1), (20, 4), (24, 4), (36, 1), (40, 1), (44, supercompression)] {
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+        let data_offset = bytes.len() as u64;
+        bytes[80..88].copy_from_slice(&data_offset.to_le_bytes());
+        bytes[88..96].copy_from_slice(&(level.len() as u64).to_le_bytes());
+        bytes[96..104].copy_from_slice(&(level.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(level);
+        bytes
+    }
+
+    #[test]
+    fn test_parse_ktx2() {
+        let bytes = ktx2(145, 0, &[0u8; 16]);
+        assert_eq!(ImageContainer::detect(&bytes), ImageContainer::Ktx2);
+        let image = CompressedImage::from_bytes(&bytes, TextureKind::Normal).unwrap();
+        assert_eq!(image.format, wgpu::TextureFormat::Bc7RgbaUnorm);
+        assert_eq!(image.levels, vec![vec![0u8; 16]]);
+        assert_eq!(image.decompress(), Err(CompressedTextureError::NoFallback(image.format)));
+        assert!(!image.is_loadable_on(wgpu::Features::TEXTURE_COMPRESSION_ETC2));
+        assert!(image.is_loadable_on(wgpu::Features::TEXTURE_COMPRESSION_BC));
+
+        assert_eq!(
+            CompressedImage::from_bytes(&ktx2(0, 1, &[]), TextureKind::Color),
+            Err(CompressedTextureError::Supercompressed(1))
+        );
+        assert!(!ktx2_needs_transcoder(&bytes));
+        assert!(ktx2_needs_transcoder(&ktx2(0, 0, &[0u8; 16])));
+        assert!(ktx2_needs_transcoder(&ktx2(145, 2, &[0u8; 16])));
+    }
+
+    #[test]
+    fn test_huge_dimensions() {
+        // Размеры и смещения из заголовка не переполняют арифметику
+        let bytes = dds_dxt1(&[&RED_BLUE_BC1], u32::MAX, u32::MAX);
+        assert_eq!(
+            CompressedImage::from_bytes(&bytes, TextureKind::Color),
+            Err(CompressedTextureError::Truncated)
+        );
+        let bytes = dds_dxt1(&[&RED_BLUE_BC1[..]; 40], 4, 4);
+        assert!(matches!(
+            CompressedImage::from_bytes(&bytes, TextureKind::Color),
+            Err(CompressedTextureError::UnsupportedLayout(_))
+        ));
+
+        let mut bytes = ktx2(145, 0, &[0u8; 16]);
+        bytes[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            CompressedImage::from_bytes(&bytes, TextureKind::Color),
+            Err(CompressedTextureError::Truncated)
+        );
+        let mut bytes = ktx2(145, 0, &[0u8; 16]);
+        bytes[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            CompressedImage::from_bytes(&bytes, TextureKind::Color),
+            Err(CompressedTextureError::Truncated)
+        );
+    }
+}
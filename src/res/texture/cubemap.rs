@@ -0,0 +1,175 @@
+//! Подготовка данных кубических карт на CPU.
+//!
+//! Грани идут в порядке wgpu: +X, -X, +Y, -Y, +Z, -Z.
+
+use std::f32::consts::PI;
+
+use glam::Vec3;
+
+pub const CUBE_FACE_COUNT: u32 = 6;
+
+/// Направление из центра куба через точку `(u, v)` грани `face`,
+/// где `u, v` лежат в `[0, 1]`, а `v` растёт вниз
+pub fn cube_face_direction(face: u32, u: f32, v: f32) -> Vec3 {
+    let s = 2.0 * u - 1.0;
+    let t = 2.0 * v - 1.0;
+    let direction = match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    };
+    direction.normalize()
+}
+
+/// Грань и координаты `(u, v)` на ней для направления; обратное к [`cube_face_direction`]
+pub fn direction_to_cube_face(direction: Vec3) -> (u32, f32, f32) {
+    let abs = direction.abs();
+    let (face, s, t, major) = if abs.x >= abs.y && abs.x >= abs.z {
+        if direction.x > 0.0 {
+            (0, -direction.z, -direction.y, abs.x)
+        } else {
+            (1, direction.z, -direction.y, abs.x)
+        }
+    } else if abs.y >= abs.z {
+        if direction.y > 0.0 {
+            (2, direction.x, direction.z, abs.y)
+        } else {
+            (3, direction.x, -direction.z, abs.y)
+        }
+    } else if direction.z > 0.0 {
+        (4, direction.x, -direction.y, abs.z)
+    } else {
+        (5, -direction.x, -direction.y, abs.z)
+    };
+    (face, 0.5 * (s / major + 1.0), 0.5 * (t / major + 1.0))
+}
+
+/// Билинейная выборка из граней кубической карты (без перехода через рёбра)
+pub fn sample_cube(faces: &[Vec<[f32; 4]>], face_size: u32, direction: Vec3) -> [f32; 4] {
+    let (face, u, v) = direction_to_cube_face(direction);
+    let face = &faces[face as usize];
+    let max = (face_size - 1) as f32;
+    let x = (u * face_size as f32 - 0.5).clamp(0.0, max);
+    let y = (v * face_size as f32 - 0.5).clamp(0.0, max);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (x1, y1) = ((x0 + 1.0).min(max), (y0 + 1.0).min(max));
+
+    let texel = |x: f32, y: f32| glam::Vec4::from(face[(y as u32 * face_size + x as u32) as usize]);
+    let top = texel(x0, y0).lerp(texel(x1, y0), fx);
+    let bottom = texel(x0, y1).lerp(texel(x1, y1), fx);
+    top.lerp(bottom, fy).into()
+}
+
+/// Телесный угол тексела `(x, y)` грани размера `face_size`
+pub fn cube_texel_solid_angle(face_size: u32, x: u32, y: u32) -> f32 {
+    let s = 2.0 * (x as f32 + 0.5) / face_size as f32 - 1.0;
+    let t = 2.0 * (y as f32 + 0.5) / face_size as f32 - 1.0;
+    let texel_area = (2.0 / face_size as f32).powi(2);
+    texel_area / (1.0 + s * s + t * t).powf(1.5)
+}
+
+/// UV-координаты равнопромежуточной (equirectangular) панорамы для направления
+pub fn direction_to_equirectangular(direction: Vec3) -> (f32, f32) {
+    let direction = direction.normalize();
+    let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+/// Перепроецирует панораму в шесть граней `face_size x face_size` (RGBA, линейный цвет)
+pub fn equirectangular_to_cube_faces(image: &image::Rgb32FImage, face_size: u32) -> Vec<Vec<[f32; 4]>> {
+    (0..CUBE_FACE_COUNT)
+        .map(|face| {
+            let mut pixels = Vec::with_capacity((face_size * face_size) as usize);
+            for y in 0..face_size {
+                for x in 0..face_size {
+                    let u = (x as f32 + 0.5) / face_size as f32;
+                    let v = (y as f32 + 0.5) / face_size as f32;
+                    let (eu, ev) = direction_to_equirectangular(cube_face_direction(face, u, v));
+                    let [r, g, b] = sample_bilinear(image, eu, ev);
+                    pixels.push([r, g, b, 1.0]);
+                }
+            }
+            pixels
+        })
+        .collect()
+}
+
+/// Билинейная выборка с повтором по горизонтали и ограничением по вертикали
+fn sample_bilinear(image: &image::Rgb32FImage, u: f32, v: f32) -> [f32; 3] {
+    let (width, height) = image.dimensions();
+    let x = u * width as f32 - 0.5;
+    let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+
+    let column = |x: f32| (x as i64).rem_euclid(width as i64) as u32;
+    let row = |y: f32| (y as u32).min(height - 1);
+    let texel = |x: f32, y: f32| Vec3::from(image.get_pixel(column(x), row(y)).0);
+
+    let top = texel(x0, y0).lerp(texel(x0 + 1.0, y0), fx);
+    let bottom = texel(x0, y0 + 1.0).lerp(texel(x0 + 1.0, y0 + 1.0), fx);
+    top.lerp(bottom, fy).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_face_centers() {
+        let expected = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        for (face, direction) in expected.into_iter().enumerate() {
+            let center = cube_face_direction(face as u32, 0.5, 0.5);
+            assert!(center.abs_diff_eq(direction, 1e-6), "face {}: {}", face, center);
+        }
+        // Верх грани +Z смотрит вверх
+        assert!(cube_face_direction(4, 0.5, 0.0).y > 0.0);
+    }
+
+    #[test]
+    fn test_direction_to_cube_face_roundtrip() {
+        for face in 0..CUBE_FACE_COUNT {
+            for (u, v) in [(0.5, 0.5), (0.1, 0.8), (0.9, 0.25)] {
+                let (found, fu, fv) = direction_to_cube_face(cube_face_direction(face, u, v));
+                assert_eq!(found, face);
+                assert!((fu - u).abs() < 1e-5 && (fv - v).abs() < 1e-5, "face {}: {} {}", face, fu, fv);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solid_angles_cover_sphere() {
+        let size = 16;
+        let total: f32 = (0..CUBE_FACE_COUNT)
+            .map(|_| {
+                (0..size)
+                    .flat_map(|y| (0..size).map(move |x| cube_texel_solid_angle(size, x, y)))
+                    .sum::<f32>()
+            })
+            .sum();
+        assert!((total - 4.0 * PI).abs() < 0.05, "{}", total);
+    }
+
+    #[test]
+    fn test_equirectangular_to_cube_faces() {
+        // Верхняя половина панорамы белая, нижняя чёрная
+        let image = image::Rgb32FImage::from_fn(64, 32, |_, y| {
+            if y < 16 {
+                image::Rgb([1.0, 1.0, 1.0])
+            } else {
+                image::Rgb([0.0, 0.0, 0.0])
+            }
+        });
+        let faces = equirectangular_to_cube_faces(&image, 8);
+
+        assert_eq!(faces.len(), 6);
+        assert!(faces.iter().all(|face| face.len() == 64));
+        assert_eq!(faces[2][27], [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(faces[3][27], [0.0, 0.0, 0.0, 1.0]);
+    }
+}
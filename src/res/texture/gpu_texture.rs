@@ -1,10 +1,16 @@
 use std::{fmt, path::Path};
 
-use gltf::Error;
 use image::GenericImageView;
 use wgpu::{util::DeviceExt, BindGroupLayout, Sampler, TextureView};
 
-use crate::res::{storage::Storage, Handle, Resource, TextureKey};
+use crate::{core::pipeline_cache::PipelineCache, res::{error::AssetError, storage::Storage, Handle, Resource, TextureKey}};
+
+use super::{
+    compressed::{CompressedImage, CompressedTextureError},
+    cubemap::{equirectangular_to_cube_faces, CUBE_FACE_COUNT},
+    mipmap::generate_mip_chain,
+    TextureKind,
+};
 
 
 #[derive(Debug, Clone)] 
@@ -19,7 +25,7 @@ impl Resource for GpuTexture {
     
     type LoadParams = GpuTexture;
     
-    fn load(res: Self::LoadParams) -> Result<Self, Box<dyn std::error::Error>>{
+    fn load(res: Self::LoadParams) -> Result<Self, AssetError>{
         Ok(res) 
     }
 }
@@ -35,18 +41,43 @@ impl GpuTexture {
         queue: &wgpu::Queue,
         bytes: &Vec<u8>,
         label: &str,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
-        let img = image::load_from_memory(bytes).unwrap();
-        Ok(Self::from_image(device, queue, &img, Some(label))?)
+    ) -> Result<Self, AssetError> {
+        let img = image::load_from_memory(bytes)?;
+        Self::from_image(device, queue, &img, Some(label))
     }
 
+    /// Создаёт цветовую sRGB-текстуру с полной цепочкой mip-уровней
+    /// и сэмплером [`default_sampler_descriptor`]
     pub fn from_image(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         img: &image::DynamicImage,
         label: Option<&str>,
-    ) -> Result<Self, Error> {
+    ) -> Result<Self, AssetError> {
+        let sampler = device.create_sampler(&default_sampler_descriptor());
+        Self::from_image_with_sampler(device, queue, img, TextureKind::Color, sampler, true, label)
+    }
+
+    /// Создаёт текстуру в формате, подходящем для `kind`, с готовым (например,
+    /// общим из [`SamplerCache`]) сэмплером. При `generate_mipmaps` уровни
+    /// строятся на CPU.
+    ///
+    /// [`SamplerCache`]: super::sampler::SamplerCache
+    pub fn from_image_with_sampler(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        img: &image::DynamicImage,
+        kind: TextureKind,
+        sampler: Sampler,
+        generate_mipmaps: bool,
+        label: Option<&str>,
+    ) -> Result<Self, AssetError> {
         let rgba = img.to_rgba8();
+        let levels = if generate_mipmaps {
+            generate_mip_chain(&rgba, kind)
+        } else {
+            vec![rgba]
+        };
         let dimensions = img.dimensions();
 
         let size = wgpu::Extent3d {
@@ -58,40 +89,90 @@ impl GpuTexture {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label,
             size,
-            mip_level_count: 1,
+            mip_level_count: levels.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format: kind.format(),
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats:  &[wgpu::TextureFormat::Rgba8UnormSrgb]
+            view_formats: &[]
         });
 
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                aspect: wgpu::TextureAspect::All,
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            &rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
-                rows_per_image: Some(dimensions.1),
+        for (mip_level, level) in levels.iter().enumerate() {
+            let (width, height) = level.dimensions();
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    aspect: wgpu::TextureAspect::All,
+                    texture: &texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                },
+                level,
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Ok(Self {
+            texture,
+            view,
+            sampler,
+        })
+    }
+
+    /// Загружает KTX2/DDS. Если адаптер не поддерживает формат, уровни
+    /// распаковываются на CPU (см. [`CompressedImage::decompress`])
+    pub fn from_compressed(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &CompressedImage,
+        sampler: Sampler,
+        label: Option<&str>,
+    ) -> Result<Self, CompressedTextureError> {
+        if !image.is_loadable_on(device.features()) {
+            return Err(CompressedTextureError::NoFallback(image.format));
+        }
+        let (format, data) = if image.is_supported_by(device.features()) {
+            (image.format, image.levels.concat())
+        } else {
+            let format = if image.format.is_srgb() {
+                wgpu::TextureFormat::Rgba8UnormSrgb
+            } else {
+                wgpu::TextureFormat::Rgba8Unorm
+            };
+            let levels = image.decompress()?;
+            (format, levels.into_iter().flat_map(|level| level.into_raw()).collect())
+        };
+
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label,
+                size: wgpu::Extent3d {
+                    width: image.width,
+                    height: image.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: image.levels.len() as u32,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
             },
-            size,
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &data,
         );
-
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
 
         Ok(Self {
             texture,
@@ -104,17 +185,28 @@ impl GpuTexture {
         device: &wgpu::Device,
         config: &wgpu::SurfaceConfiguration,
         label: &str,
+    ) -> Self {
+        Self::create_multisampled_depth_texture(device, config.width, config.height, 1, label)
+    }
+
+    /// Буфер глубины с `sample_count` выборками на пиксель (для MSAA)
+    pub fn create_multisampled_depth_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        sample_count: u32,
+        label: &str,
     ) -> Self {
         let size = wgpu::Extent3d {
-            width: config.width,
-            height: config.height,
+            width: width.max(1),
+            height: height.max(1),
             depth_or_array_layers: 1,
         };
         let desc = wgpu::TextureDescriptor {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: DEPTH_FORMAT,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
@@ -143,36 +235,270 @@ impl GpuTexture {
         }
     }
 
-}
+    /// Создаёт кубическую карту из шести квадратных граней одного размера
+    /// в порядке +X, -X, +Y, -Y, +Z, -Z
+    pub fn from_cube_faces(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        faces: &[image::DynamicImage],
+        label: &str,
+    ) -> Result<Self, AssetError> {
+        if faces.len() != CUBE_FACE_COUNT as usize {
+            return Err(AssetError::invalid(format!(
+                "cube map needs {} faces, got {}",
+                CUBE_FACE_COUNT,
+                faces.len()
+            )));
+        }
+        let (width, height) = faces[0].dimensions();
+        if width != height || faces.iter().any(|face| face.dimensions() != (width, height)) {
+            return Err(AssetError::invalid("cube map faces must be square and of the same size"));
+        }
 
-pub fn get_texture_bind_group_layout(
-    device: &wgpu::Device,
-) -> BindGroupLayout {
-    return 
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Texture {
-                        multisampled: false,
-                        view_dimension: wgpu::TextureViewDimension::D2,
-                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    },
-                    count: None,
+        let data: Vec<u8> = faces.iter().flat_map(|face| face.to_rgba8().into_raw()).collect();
+        Ok(Self::create_cube(device, queue, width, 1, wgpu::TextureFormat::Rgba8UnormSrgb, &data, label))
+    }
+
+    /// Создаёт HDR кубическую карту из равнопромежуточной панорамы
+    /// (перепроекция на CPU). Значения пикселей считаются линейными, как в `.hdr`.
+    pub fn from_equirectangular(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &image::DynamicImage,
+        face_size: u32,
+        label: &str,
+    ) -> Self {
+        let faces = equirectangular_to_cube_faces(&image.to_rgb32f(), face_size);
+        Self::from_hdr_cube_mips(device, queue, face_size, &[faces], label)
+    }
+
+    /// Создаёт HDR кубическую карту с mip-уровнями: `mips[level][face]` —
+    /// пиксели грани размера `face_size >> level`
+    pub fn from_hdr_cube_mips(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        face_size: u32,
+        mips: &[Vec<Vec<[f32; 4]>>],
+        label: &str,
+    ) -> Self {
+        // LayerMajor: для каждой грани идут все её mip-уровни
+        let data: Vec<u8> = (0..CUBE_FACE_COUNT as usize)
+            .flat_map(|face| mips.iter().map(move |level| &level[face]))
+            .flatten()
+            .flatten()
+            .flat_map(|channel| half::f16::from_f32(*channel).to_le_bytes())
+            .collect();
+        Self::create_cube(
+            device,
+            queue,
+            face_size,
+            mips.len() as u32,
+            wgpu::TextureFormat::Rgba16Float,
+            &data,
+            label,
+        )
+    }
+
+    /// Двухканальная float-текстура `size x size` (BRDF LUT)
+    pub fn from_rg_f32(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        size: u32,
+        texels: &[[f32; 2]],
+        label: &str,
+    ) -> Self {
+        let data: Vec<u8> = texels
+            .iter()
+            .flatten()
+            .flat_map(|channel| half::f16::from_f32(*channel).to_le_bytes())
+            .collect();
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: size,
+                    height: size,
+                    depth_or_array_layers: 1,
                 },
-                wgpu::BindGroupLayoutEntry {
-                   binding: 1,
-                   visibility: wgpu::ShaderStages::FRAGMENT,
-                   ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                   count: None,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rg16Float,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &data,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    fn create_cube(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        face_size: u32,
+        mip_level_count: u32,
+        format: wgpu::TextureFormat,
+        data: &[u8],
+        label: &str,
+    ) -> Self {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: face_size,
+                    height: face_size,
+                    depth_or_array_layers: CUBE_FACE_COUNT,
                 },
-            ],
-        label: Some("texture_bind_group_layout"),
-    });
-}
+                mip_level_count,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            data,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Создаёт цветовую текстуру, в которую можно рисовать и из которой
+    /// можно читать в следующем проходе. При `sample_count > 1` текстура
+    /// служит multisampled-вложением и разрешается в одновыборочную цель.
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        label: &str,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
 
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+}
 
+/// Сэмплер текстур без настроек из файла: края зажаты, трилинейная фильтрация
+pub fn default_sampler_descriptor() -> wgpu::SamplerDescriptor<'static> {
+    wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    }
+}
 
+/// Записи layout'а: текстура базового цвета (binding 0) и её сэмплер (binding 1)
+pub const TEXTURE_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    },
+];
 
+/// Записи layout'а кубической карты: текстура (binding 0) и сэмплер (binding 1)
+pub const CUBE_TEXTURE_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::Cube,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    },
+];
 
+/// Layout текстуры с сэмплером из кэша: одинаковые записи дают один объект
+pub fn get_texture_bind_group_layout(
+    cache: &mut PipelineCache,
+    device: &wgpu::Device,
+) -> BindGroupLayout {
+    cache.bind_group_layout(device, TEXTURE_BIND_GROUP_LAYOUT_ENTRIES)
+}
@@ -0,0 +1,465 @@
+//! Предрасчёт освещения от окружения (image-based lighting) на CPU.
+//!
+//! Из кубической карты получаются три ресурса для `shaders/ibl.wgsl`:
+//! диффузная облучённость в виде 9 коэффициентов сферических гармоник,
+//! зеркальная карта с mip-уровнями по шероховатости (GGX) и BRDF LUT
+//! для split-sum аппроксимации.
+
+use std::f32::consts::PI;
+
+use glam::{Vec2, Vec3};
+use wgpu::util::DeviceExt;
+
+use crate::res::{error::AssetError, storage::Storage, Handle};
+
+use super::{
+    cubemap::{cube_face_direction, cube_texel_solid_angle, sample_cube, CUBE_FACE_COUNT},
+    gpu_texture::GpuTexture,
+};
+
+/// Свёртка с косинусом для полос 0, 1, 2 (Ramamoorthi, Hanrahan)
+const SH_BAND_CONVOLUTION: [f32; 3] = [PI, 2.0 * PI / 3.0, PI / 4.0];
+
+/// Базис SH9 для единичного направления
+fn sh_basis(n: Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * n.y,
+        0.488603 * n.z,
+        0.488603 * n.x,
+        1.092548 * n.x * n.y,
+        1.092548 * n.y * n.z,
+        0.315392 * (3.0 * n.z * n.z - 1.0),
+        1.092548 * n.x * n.z,
+        0.546274 * (n.x * n.x - n.y * n.y),
+    ]
+}
+
+fn sh_band(index: usize) -> usize {
+    match index {
+        0 => 0,
+        1..=3 => 1,
+        _ => 2,
+    }
+}
+
+/// Проекция сияния окружения на сферические гармоники второго порядка
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Sh9 {
+    pub coefficients: [Vec3; 9],
+}
+
+impl Sh9 {
+    /// Проецирует грани кубической карты с учётом телесного угла текселов
+    pub fn from_cube_faces(faces: &[Vec<[f32; 4]>], face_size: u32) -> Self {
+        let mut coefficients = [Vec3::ZERO; 9];
+        let mut total_weight = 0.0;
+        for (face, pixels) in faces.iter().enumerate().take(CUBE_FACE_COUNT as usize) {
+            for y in 0..face_size {
+                for x in 0..face_size {
+                    let u = (x as f32 + 0.5) / face_size as f32;
+                    let v = (y as f32 + 0.5) / face_size as f32;
+                    let direction = cube_face_direction(face as u32, u, v);
+                    let weight = cube_texel_solid_angle(face_size, x, y);
+                    let [r, g, b, _] = pixels[(y * face_size + x) as usize];
+                    let radiance = Vec3::new(r, g, b);
+
+                    for (coefficient, basis) in coefficients.iter_mut().zip(sh_basis(direction)) {
+                        *coefficient += radiance * basis * weight;
+                    }
+                    total_weight += weight;
+                }
+            }
+        }
+        // Сумма телесных углов дискретной сетки немного отличается от 4π
+        let normalization = 4.0 * PI / total_weight;
+        Self {
+            coefficients: coefficients.map(|coefficient| coefficient * normalization),
+        }
+    }
+
+    /// Коэффициенты, свёрнутые с косинусом: их сумма с базисом даёт облучённость
+    pub fn convolved(&self) -> [Vec3; 9] {
+        std::array::from_fn(|i| self.coefficients[i] * SH_BAND_CONVOLUTION[sh_band(i)])
+    }
+
+    /// Облучённость поверхности с нормалью `normal`
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        self.convolved()
+            .iter()
+            .zip(sh_basis(normal.normalize()))
+            .map(|(coefficient, basis)| *coefficient * basis)
+            .sum::<Vec3>()
+            .max(Vec3::ZERO)
+    }
+}
+
+/// Точка последовательности Хаммерсли `i` из `count`
+fn hammersley(i: u32, count: u32) -> Vec2 {
+    Vec2::new(i as f32 / count as f32, i.reverse_bits() as f32 * 2.328_306_4e-10)
+}
+
+/// Полувектор с распределением GGX вокруг `normal`
+fn importance_sample_ggx(xi: Vec2, normal: Vec3, roughness: f32) -> Vec3 {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+    let h = Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta);
+
+    let up = if normal.z.abs() < 0.999 { Vec3::Z } else { Vec3::X };
+    let tangent = up.cross(normal).normalize();
+    let bitangent = normal.cross(tangent);
+    (tangent * h.x + bitangent * h.y + normal * h.z).normalize()
+}
+
+/// Зеркальная карта: уровень `m` из `mip_count` соответствует шероховатости
+/// `m / (mip_count - 1)`. Возвращает уровни, в каждом — шесть граней.
+pub fn prefilter_specular(
+    faces: &[Vec<[f32; 4]>],
+    face_size: u32,
+    mip_count: u32,
+    sample_count: u32,
+) -> Vec<Vec<Vec<[f32; 4]>>> {
+    (0..mip_count)
+        .map(|mip| {
+            let size = (face_size >> mip).max(1);
+            let roughness = if mip_count > 1 {
+                mip as f32 / (mip_count - 1) as f32
+            } else {
+                0.0
+            };
+            (0..CUBE_FACE_COUNT)
+                .map(|face| {
+                    let mut pixels = Vec::with_capacity((size * size) as usize);
+                    for y in 0..size {
+                        for x in 0..size {
+                            let u = (x as f32 + 0.5) / size as f32;
+                            let v = (y as f32 + 0.5) / size as f32;
+                            let normal = cube_face_direction(face, u, v);
+                            pixels.push(prefilter_texel(faces, face_size, normal, roughness, sample_count));
+                        }
+                    }
+                    pixels
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn prefilter_texel(
+    faces: &[Vec<[f32; 4]>],
+    face_size: u32,
+    normal: Vec3,
+    roughness: f32,
+    sample_count: u32,
+) -> [f32; 4] {
+    if roughness == 0.0 {
+        return sample_cube(faces, face_size, normal);
+    }
+    // Приближение N = V = R
+    let mut color = Vec3::ZERO;
+    let mut total_weight = 0.0;
+    for i in 0..sample_count {
+        let h = importance_sample_ggx(hammersley(i, sample_count), normal, roughness);
+        let l = 2.0 * normal.dot(h) * h - normal;
+        let n_dot_l = normal.dot(l);
+        if n_dot_l > 0.0 {
+            let [r, g, b, _] = sample_cube(faces, face_size, l);
+            color += Vec3::new(r, g, b) * n_dot_l;
+            total_weight += n_dot_l;
+        }
+    }
+    let color = color / total_weight.max(f32::EPSILON);
+    [color.x, color.y, color.z, 1.0]
+}
+
+fn geometry_schlick_ggx(n_dot_v: f32, roughness: f32) -> f32 {
+    let k = roughness * roughness / 2.0;
+    n_dot_v / (n_dot_v * (1.0 - k) + k)
+}
+
+/// Масштаб и смещение Френеля `(A, B)` для `n_dot_v` и шероховатости
+fn integrate_brdf(n_dot_v: f32, roughness: f32, sample_count: u32) -> Vec2 {
+    let view = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+    let mut result = Vec2::ZERO;
+    for i in 0..sample_count {
+        let h = importance_sample_ggx(hammersley(i, sample_count), Vec3::Z, roughness);
+        let l = 2.0 * view.dot(h) * h - view;
+        let n_dot_l = l.z.max(0.0);
+        if n_dot_l > 0.0 {
+            let n_dot_h = h.z.max(0.0);
+            let v_dot_h = view.dot(h).max(0.0);
+            let geometry = geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+            let visibility = geometry * v_dot_h / (n_dot_h * n_dot_v).max(f32::EPSILON);
+            let fresnel = (1.0 - v_dot_h).powi(5);
+            result += Vec2::new((1.0 - fresnel) * visibility, fresnel * visibility);
+        }
+    }
+    result / sample_count as f32
+}
+
+/// BRDF LUT `size x size`: по x — `n_dot_v`, по y — шероховатость
+pub fn brdf_lut(size: u32, sample_count: u32) -> Vec<[f32; 2]> {
+    let mut texels = Vec::with_capacity((size * size) as usize);
+    for y in 0..size {
+        let roughness = (y as f32 + 0.5) / size as f32;
+        for x in 0..size {
+            let n_dot_v = (x as f32 + 0.5) / size as f32;
+            texels.push(integrate_brdf(n_dot_v, roughness, sample_count).into());
+        }
+    }
+    texels
+}
+
+/// Параметры предрасчёта окружения
+#[derive(Debug, Clone, Copy)]
+pub struct IblSettings {
+    /// Размер грани неба
+    pub skybox_size: u32,
+    /// Размер грани нулевого уровня зеркальной карты
+    pub specular_size: u32,
+    pub specular_mip_count: u32,
+    pub specular_sample_count: u32,
+    pub brdf_lut_size: u32,
+    pub brdf_sample_count: u32,
+    pub intensity: f32,
+}
+
+impl Default for IblSettings {
+    fn default() -> Self {
+        Self {
+            skybox_size: 512,
+            specular_size: 64,
+            specular_mip_count: 5,
+            specular_sample_count: 64,
+            brdf_lut_size: 64,
+            brdf_sample_count: 128,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl IblSettings {
+    /// Проверяет, что размеры текстур и числа выборок не нулевые
+    pub fn validate(&self) -> Result<(), AssetError> {
+        let fields = [
+            ("skybox_size", self.skybox_size),
+            ("specular_size", self.specular_size),
+            ("specular_sample_count", self.specular_sample_count),
+            ("brdf_lut_size", self.brdf_lut_size),
+            ("brdf_sample_count", self.brdf_sample_count),
+        ];
+        match fields.iter().find(|(_, value)| *value == 0) {
+            Some((name, _)) => Err(AssetError::invalid(format!("IBL setting `{}` must be greater than zero", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Uniform окружения (`Environment` в `shaders/ibl.wgsl`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct EnvironmentUniform {
+    pub irradiance_sh: [[f32; 4]; 9],
+    pub params: [f32; 4],
+}
+
+/// Окружение сцены: небо и ресурсы IBL в [`AssetManager::textures`]
+///
+/// [`AssetManager::textures`]: crate::res::asset_manager::AssetManager::textures
+#[derive(Debug, Clone)]
+pub struct Environment {
+    pub skybox: Handle<GpuTexture>,
+    pub specular: Handle<GpuTexture>,
+    pub brdf_lut: Handle<GpuTexture>,
+    pub irradiance: Sh9,
+    pub specular_mip_count: u32,
+    pub intensity: f32,
+}
+
+impl Environment {
+    pub fn uniform(&self) -> EnvironmentUniform {
+        EnvironmentUniform {
+            irradiance_sh: self.irradiance.convolved().map(|c| [c.x, c.y, c.z, 0.0]),
+            params: [self.specular_mip_count as f32, self.intensity, 0.0, 0.0],
+        }
+    }
+
+    pub fn create_uniform_buffer(&self, device: &wgpu::Device) -> wgpu::Buffer {
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("environment_uniform_buffer"),
+            contents: bytemuck::bytes_of(&self.uniform()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        })
+    }
+
+    /// Bind group группы 1 освещённого пайплайна: точечные источники и окружение
+    /// (layout [`LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES`]). `None`, если текстуры
+    /// окружения уже выгружены.
+    pub fn create_bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        textures: &Storage<GpuTexture>,
+        point_lights: &wgpu::Buffer,
+        uniform_buffer: &wgpu::Buffer,
+    ) -> Option<wgpu::BindGroup> {
+        let specular = textures.get(self.specular.clone())?;
+        let brdf_lut = textures.get(self.brdf_lut.clone())?;
+        Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lit_lighting_bind_group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: point_lights.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&specular.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::Sampler(&specular.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(&brdf_lut.view),
+                },
+            ],
+        }))
+    }
+}
+
+/// Записи layout'а группы 1 для [`PipelineType::Lit`](crate::core::PipelineType::Lit):
+/// точечные источники (`shaders/light.wgsl`) и окружение (`shaders/ibl.wgsl`)
+pub const LIT_LIGHTING_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 1,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 2,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::Cube,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 3,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None,
+    },
+    wgpu::BindGroupLayoutEntry {
+        binding: 4,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+        },
+        count: None,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant_faces(size: u32, value: f32) -> Vec<Vec<[f32; 4]>> {
+        (0..CUBE_FACE_COUNT)
+            .map(|_| vec![[value, value, value, 1.0]; (size * size) as usize])
+            .collect()
+    }
+
+    #[test]
+    fn test_sh9_constant_environment() {
+        // При постоянном сиянии L облучённость равна πL для любой нормали
+        let sh = Sh9::from_cube_faces(&constant_faces(16, 1.0), 16);
+        for normal in [Vec3::X, -Vec3::Y, Vec3::new(1.0, 1.0, -1.0)] {
+            let irradiance = sh.irradiance(normal);
+            assert!((irradiance - Vec3::splat(PI)).abs().max_element() < 1e-3, "{}", irradiance);
+        }
+        assert!(sh.coefficients[1..].iter().all(|c| c.abs().max_element() < 1e-4));
+    }
+
+    #[test]
+    fn test_sh9_directional_environment() {
+        // Светлое верхнее полушарие: сверху облучённость больше, чем снизу
+        let size = 16;
+        let faces: Vec<Vec<[f32; 4]>> = (0..CUBE_FACE_COUNT)
+            .map(|face| {
+                (0..size * size)
+                    .map(|i| {
+                        let u = ((i % size) as f32 + 0.5) / size as f32;
+                        let v = ((i / size) as f32 + 0.5) / size as f32;
+                        let lit = cube_face_direction(face, u, v).y > 0.0;
+                        if lit { [1.0; 4] } else { [0.0, 0.0, 0.0, 1.0] }
+                    })
+                    .collect()
+            })
+            .collect();
+        let sh = Sh9::from_cube_faces(&faces, size);
+
+        let up = sh.irradiance(Vec3::Y).x;
+        let down = sh.irradiance(-Vec3::Y).x;
+        let side = sh.irradiance(Vec3::X).x;
+        assert!(up > side && side > down, "{} {} {}", up, side, down);
+        assert!((side - PI / 2.0).abs() < 0.05, "{}", side);
+    }
+
+    #[test]
+    fn test_prefilter_specular_mips() {
+        let mips = prefilter_specular(&constant_faces(8, 2.0), 8, 4, 16);
+        let sizes: Vec<usize> = mips.iter().map(|faces| faces[0].len()).collect();
+        assert_eq!(sizes, vec![64, 16, 4, 1]);
+        // Постоянное окружение остаётся постоянным на любой шероховатости
+        for texel in mips.iter().flatten().flatten() {
+            assert!((texel[0] - 2.0).abs() < 1e-4, "{:?}", texel);
+        }
+    }
+
+    #[test]
+    fn test_settings_validate() {
+        assert!(IblSettings::default().validate().is_ok());
+        let settings = IblSettings { specular_size: 0, ..Default::default() };
+        assert!(matches!(settings.validate(), Err(AssetError::InvalidData(message)) if message.contains("specular_size")));
+    }
+
+    #[test]
+    fn test_brdf_lut() {
+        let size = 16;
+        let lut = brdf_lut(size, 256);
+        assert_eq!(lut.len(), (size * size) as usize);
+        assert!(lut.iter().flatten().all(|value| (0.0..=1.0).contains(value)));
+
+        // Гладкая поверхность при взгляде по нормали отражает почти всё
+        let [a, b] = lut[(size - 1) as usize];
+        assert!(a + b > 0.9 && b < 0.05, "{} {}", a, b);
+    }
+}
@@ -0,0 +1,131 @@
+//! Генерация mip-уровней на CPU фильтром 2x2 (box filter).
+//!
+//! Для sRGB-текстур усреднение идёт в линейном пространстве, иначе
+//! уменьшенные уровни получаются темнее исходника; нормали после
+//! усреднения перенормируются.
+
+use glam::Vec3;
+use image::RgbaImage;
+
+use super::{linear_to_srgb, srgb_to_linear, TextureKind};
+
+/// Число уровней полной цепочки для `width x height` (до 1x1 включительно)
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    width.max(height).max(1).ilog2() + 1
+}
+
+/// Уменьшает изображение вдвое по каждой оси (нечётная сторона округляется вниз,
+/// но не меньше 1). Альфа всегда усредняется линейно.
+pub fn downsample(image: &RgbaImage, kind: TextureKind) -> RgbaImage {
+    let srgb = kind.is_srgb();
+    let (width, height) = image.dimensions();
+    let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+    let decode = |value: u8, channel: usize| {
+        if srgb && channel < 3 {
+            srgb_to_linear(value)
+        } else {
+            value as f32 / 255.0
+        }
+    };
+    let encode = |value: f32, channel: usize| {
+        if srgb && channel < 3 {
+            linear_to_srgb(value)
+        } else {
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        }
+    };
+
+    RgbaImage::from_fn(next_width, next_height, |x, y| {
+        let xs = [(2 * x).min(width - 1), (2 * x + 1).min(width - 1)];
+        let ys = [(2 * y).min(height - 1), (2 * y + 1).min(height - 1)];
+        let mut pixel = [0u8; 4];
+        for (channel, value) in pixel.iter_mut().enumerate() {
+            let sum: f32 = ys
+                .iter()
+                .flat_map(|&sy| xs.iter().map(move |&sx| (sx, sy)))
+                .map(|(sx, sy)| decode(image.get_pixel(sx, sy)[channel], channel))
+                .sum();
+            *value = encode(sum / 4.0, channel);
+        }
+        if kind == TextureKind::Normal {
+            renormalize(&mut pixel);
+        }
+        image::Rgba(pixel)
+    })
+}
+
+/// Приводит нормаль, закодированную как `n * 0.5 + 0.5`, к единичной длине
+fn renormalize(pixel: &mut [u8; 4]) {
+    let normal = Vec3::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32) / 127.5 - Vec3::ONE;
+    let normal = normal.try_normalize().unwrap_or(Vec3::Z);
+    let encoded = (normal * 0.5 + Vec3::splat(0.5)) * 255.0;
+    pixel[0] = encoded.x.round() as u8;
+    pixel[1] = encoded.y.round() as u8;
+    pixel[2] = encoded.z.round() as u8;
+}
+
+/// Полная цепочка уровней, начиная с исходного изображения
+pub fn generate_mip_chain(image: &RgbaImage, kind: TextureKind) -> Vec<RgbaImage> {
+    let (width, height) = image.dimensions();
+    let mut levels = Vec::with_capacity(mip_level_count(width, height) as usize);
+    levels.push(image.clone());
+    while let Some(last) = levels.last() {
+        if last.width() == 1 && last.height() == 1 {
+            break;
+        }
+        let next = downsample(last, kind);
+        levels.push(next);
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mip_level_count() {
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(256, 256), 9);
+        assert_eq!(mip_level_count(300, 20), 9);
+    }
+
+    #[test]
+    fn test_generate_mip_chain() {
+        let image = RgbaImage::from_fn(6, 3, |x, _| {
+            if x % 2 == 0 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            }
+        });
+        let chain = generate_mip_chain(&image, TextureKind::Data);
+        let sizes: Vec<_> = chain.iter().map(|level| level.dimensions()).collect();
+        assert_eq!(sizes, vec![(6, 3), (3, 1), (1, 1)]);
+        assert_eq!(chain[1].get_pixel(0, 0).0, [128, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_srgb_downsample_is_linear() {
+        // Чёрный и белый в линейном пространстве дают ~188 в sRGB, а не 128
+        let image = RgbaImage::from_fn(2, 1, |x, _| {
+            let v = if x == 0 { 0 } else { 255 };
+            image::Rgba([v, v, v, 255])
+        });
+        let pixel = downsample(&image, TextureKind::Color).get_pixel(0, 0).0;
+        assert_eq!(pixel[3], 255);
+        assert!((186..=190).contains(&pixel[0]), "{:?}", pixel);
+    }
+
+    #[test]
+    fn test_normal_downsample_is_normalized() {
+        // Нормали, отклонённые в разные стороны, дают после усреднения единичную +Z
+        let image = RgbaImage::from_fn(2, 1, |x, _| {
+            let nx = if x == 0 { 218 } else { 37 };
+            image::Rgba([nx, 128, 218, 255])
+        });
+        let pixel = downsample(&image, TextureKind::Normal).get_pixel(0, 0).0;
+        assert_eq!(pixel[2], 255);
+        assert!((127..=128).contains(&pixel[0]), "{:?}", pixel);
+    }
+}
@@ -1,10 +1,17 @@
 pub mod gpu_texture;
 pub mod gpu_buffers;
-use std::{fmt, path::Path};
+pub mod compressed;
+pub mod cubemap;
+pub mod ibl;
+pub mod mipmap;
+pub mod sampler;
+use std::path::Path;
 
 use image::RgbaImage;
 
-use crate::res::{buffer::BufferData, image::load_gltf_image_data, load_binary, texture::gpu_texture::GpuTexture};
+use crate::res::{
+    buffer::BufferData, error::AssetError, image::load_gltf_image_data, load_binary, texture::gpu_texture::GpuTexture,
+};
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
@@ -24,36 +31,92 @@ impl TextureDescriptor {
     }
 }
 
+/// Назначение текстуры: определяет формат на GPU и обработку при загрузке
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextureKind {
+    /// Цвет в sRGB (base color, emissive): при выборке переводится в линейное пространство
+    #[default]
+    Color,
+    /// Линейные данные (metallic-roughness, occlusion)
+    Data,
+    /// Карта нормалей: линейные данные, mip-уровни перенормируются
+    Normal,
+}
+
+impl TextureKind {
+    pub fn format(self) -> wgpu::TextureFormat {
+        match self {
+            TextureKind::Color => wgpu::TextureFormat::Rgba8UnormSrgb,
+            TextureKind::Data | TextureKind::Normal => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+
+    pub fn is_srgb(self) -> bool {
+        self == TextureKind::Color
+    }
+}
+
+/// Перевод компоненты sRGB `[0, 255]` в линейное значение `[0, 1]`
+pub fn srgb_to_linear(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Перевод линейного значения `[0, 1]` в компоненту sRGB
+pub fn linear_to_srgb(value: f32) -> u8 {
+    let c = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
 pub struct Texture {
     dimensions: (u32, u32),
     data: Vec<[f32; 3]>,
 }
 
 impl Texture {
-    pub fn new_from_image(path: &str) -> Result<Self, LoadTextureDataError> {
+    pub fn new_from_image(path: &str) -> Result<Self, AssetError> {
         Self::new_from_scaled_image(path, 1_f32)
     }
 
-    pub fn new_from_scaled_image(path: &str, scale: f32) -> Result<Self, LoadTextureDataError> {
-        use std::fs::*;
-        use std::io::BufReader;
+    /// Загружает цветовую текстуру; значения sRGB переводятся в линейные,
+    /// как их видит растровый рендер после выборки из `Rgba8UnormSrgb`
+    pub fn new_from_scaled_image(path: &str, scale: f32) -> Result<Self, AssetError> {
+        Self::new_from_scaled_image_with_kind(path, scale, TextureKind::Color)
+    }
+
+    pub fn new_from_scaled_image_with_kind(
+        path: &str,
+        scale: f32,
+        kind: TextureKind,
+    ) -> Result<Self, AssetError> {
+        let image = image::open(path).map_err(|err| AssetError::from(err).in_file(Path::new(path)))?;
+        Ok(Self::from_rgba(&image.into_rgba8(), scale, kind))
+    }
 
-        let file = File::open(path).unwrap();
-        let pixels: RgbaImage = image::load(BufReader::new(file), image::ImageFormat::Jpeg).unwrap().into_rgba8();
-        let tex_scale = scale / 255_f32;
+    /// Переводит пиксели в линейные значения `[0, scale]`
+    pub fn from_rgba(pixels: &RgbaImage, scale: f32, kind: TextureKind) -> Self {
+        let decode = |value: u8| {
+            if kind.is_srgb() {
+                scale * srgb_to_linear(value)
+            } else {
+                scale * value as f32 / 255.0
+            }
+        };
         let dimensions = pixels.dimensions();
         let data = pixels
             .pixels()
-            .map(|p| -> [f32; 3] {
-                [
-                    tex_scale * (p[0] as f32),
-                    tex_scale * (p[1] as f32),
-                    tex_scale * (p[2] as f32),
-                ]
-            })
+            .map(|p| -> [f32; 3] { [decode(p[0]), decode(p[1]), decode(p[2])] })
             .collect();
 
-        Ok(Self { dimensions, data })
+        Self { dimensions, data }
     }
 
     pub fn new_from_color(color: glam::Vec3) -> Self {
@@ -74,48 +137,43 @@ impl Texture {
 
 
 
-pub async fn load_texture<'a>(
-    file_name: &'a str,
-    device: &'a wgpu::Device,
-    queue: &'a wgpu::Queue,
-) ->Result<GpuTexture, Box<dyn std::error::Error>> {
-    let data = load_binary(file_name).await?;
+/// Загружает текстуру из каталога `assets` в текущем потоке
+pub fn load_texture(file_name: &str, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<GpuTexture, AssetError> {
+    let data = load_binary(file_name)?;
     GpuTexture::from_bytes(device, queue, &data, file_name)
 }
 
-#[derive(Debug)]
-pub struct LoadTextureDataError {
-    message: std::string::String,
-}
-
-impl fmt::Display for LoadTextureDataError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Texture loading error: {}", self.message)  // Исправлено сообщение об ошибке
-    }
+pub fn load_gltf_texture_source_data(
+    base_path: Option<&Path>,
+    pbr: gltf::material::PbrMetallicRoughness<'_>,
+    buffers: &[BufferData],  
+) -> Result<Vec<u8>, AssetError> {
+    let source_image = pbr
+        .base_color_texture()
+        .map(|info| info.texture().source())
+        .ok_or_else(|| AssetError::invalid("material has no base color texture"))?;
+    Ok(load_gltf_image_data(base_path, buffers, source_image)?.data)
 }
 
-impl std::error::Error for LoadTextureDataError {}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl LoadTextureDataError {
-    fn new(message: impl Into<String>) -> Self {
-        Self {
-            message: message.into(),
+    #[test]
+    fn test_srgb_roundtrip() {
+        for value in [0u8, 1, 10, 128, 200, 255] {
+            assert_eq!(linear_to_srgb(srgb_to_linear(value)), value);
         }
+        assert!((srgb_to_linear(128) - 0.2158).abs() < 1e-3);
     }
-}
 
-pub fn load_gltf_texture_source_data(
-    base_path: Option<&Path>,
-    pbr: gltf::material::PbrMetallicRoughness<'_>,
-    buffers: &[BufferData],  
-) -> Result<Vec<u8>, LoadTextureDataError> {
-    match &pbr.base_color_texture().map(|tex|{tex.texture().source()}) {
-        Some(source_image) => {
-            match load_gltf_image_data(base_path, buffers, source_image.clone()) {
-                Ok(image) => return Ok(image.data),
-                Err(_) => return Err(LoadTextureDataError::new(format!("Texture load error"))),
-            };
-        },
-        None => {return Err(LoadTextureDataError::new(format!("Texture load error")))},
-    };
+    #[test]
+    fn test_cpu_texture_linearizes_color() {
+        let pixels = RgbaImage::from_pixel(1, 1, image::Rgba([128, 128, 128, 255]));
+        let color = Texture::from_rgba(&pixels, 1.0, TextureKind::Color);
+        let data = Texture::from_rgba(&pixels, 2.0, TextureKind::Data);
+
+        assert!((color.as_slice()[0][0] - srgb_to_linear(128)).abs() < 1e-6);
+        assert!((data.as_slice()[0][0] - 2.0 * 128.0 / 255.0).abs() < 1e-6);
+    }
 }
\ No newline at end of file
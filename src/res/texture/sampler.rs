@@ -0,0 +1,212 @@
+//! Сэмплеры текстур: перевод настроек glTF в [`wgpu::SamplerDescriptor`]
+//! и кэш, в котором одинаковые дескрипторы дают один и тот же сэмплер.
+
+use std::collections::HashMap;
+
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+/// Максимальная степень анизотропии, которую принимает wgpu
+pub const MAX_ANISOTROPY: u16 = 16;
+
+/// Хэшируемое представление [`wgpu::SamplerDescriptor`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerKey {
+    pub address_modes: [wgpu::AddressMode; 3],
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    lod_clamp_bits: [u32; 2],
+    pub compare: Option<wgpu::CompareFunction>,
+    pub anisotropy_clamp: u16,
+    pub border_color: Option<wgpu::SamplerBorderColor>,
+}
+
+impl SamplerKey {
+    pub fn from_descriptor(desc: &wgpu::SamplerDescriptor) -> Self {
+        Self {
+            address_modes: [desc.address_mode_u, desc.address_mode_v, desc.address_mode_w],
+            mag_filter: desc.mag_filter,
+            min_filter: desc.min_filter,
+            mipmap_filter: desc.mipmap_filter,
+            lod_clamp_bits: [desc.lod_min_clamp.to_bits(), desc.lod_max_clamp.to_bits()],
+            compare: desc.compare,
+            anisotropy_clamp: desc.anisotropy_clamp,
+            border_color: desc.border_color,
+        }
+    }
+
+    pub fn to_descriptor(&self) -> wgpu::SamplerDescriptor<'static> {
+        wgpu::SamplerDescriptor {
+            label: None,
+            address_mode_u: self.address_modes[0],
+            address_mode_v: self.address_modes[1],
+            address_mode_w: self.address_modes[2],
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            lod_min_clamp: f32::from_bits(self.lod_clamp_bits[0]),
+            lod_max_clamp: f32::from_bits(self.lod_clamp_bits[1]),
+            compare: self.compare,
+            anisotropy_clamp: self.anisotropy_clamp,
+            border_color: self.border_color,
+        }
+    }
+}
+
+/// Сэмплеры, созданные по содержимому дескриптора
+#[derive(Debug, Clone, Default)]
+pub struct SamplerCache {
+    samplers: HashMap<SamplerKey, wgpu::Sampler>,
+}
+
+impl SamplerCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Возвращает сэмплер для дескриптора, создавая его при первом запросе
+    pub fn get_or_create(&mut self, device: &wgpu::Device, desc: &wgpu::SamplerDescriptor) -> wgpu::Sampler {
+        let key = SamplerKey::from_descriptor(desc);
+        self.samplers
+            .entry(key)
+            .or_insert_with(|| device.create_sampler(&key.to_descriptor()))
+            .clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samplers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samplers.is_empty()
+    }
+}
+
+/// Наибольшая анизотропия, доступная на адаптере (1 — фильтрация не поддерживается)
+pub fn max_supported_anisotropy(adapter: &wgpu::Adapter) -> u16 {
+    if adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING)
+    {
+        MAX_ANISOTROPY
+    } else {
+        1
+    }
+}
+
+fn address_mode(mode: WrappingMode) -> wgpu::AddressMode {
+    match mode {
+        WrappingMode::ClampToEdge => wgpu::AddressMode::ClampToEdge,
+        WrappingMode::MirroredRepeat => wgpu::AddressMode::MirrorRepeat,
+        WrappingMode::Repeat => wgpu::AddressMode::Repeat,
+    }
+}
+
+/// Дескриптор сэмплера по настройкам glTF. Фильтры, не заданные в файле,
+/// берутся линейными с трилинейной выборкой mip-уровней. Анизотропия
+/// включается, только если все фильтры линейные (требование wgpu).
+pub fn sampler_descriptor_from_gltf(
+    sampler: &gltf::texture::Sampler,
+    max_anisotropy: u16,
+) -> wgpu::SamplerDescriptor<'static> {
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => wgpu::FilterMode::Nearest,
+        _ => wgpu::FilterMode::Linear,
+    };
+    let (min_filter, mipmap_filter) = match sampler.min_filter() {
+        Some(MinFilter::Nearest) | Some(MinFilter::NearestMipmapNearest) => {
+            (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest)
+        }
+        Some(MinFilter::Linear) | Some(MinFilter::LinearMipmapNearest) => {
+            (wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest)
+        }
+        Some(MinFilter::NearestMipmapLinear) => (wgpu::FilterMode::Nearest, wgpu::FilterMode::Linear),
+        Some(MinFilter::LinearMipmapLinear) | None => (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear),
+    };
+    // NEAREST и LINEAR без mipmap в glTF означают выборку только нулевого уровня
+    let lod_max_clamp = match sampler.min_filter() {
+        Some(MinFilter::Nearest) | Some(MinFilter::Linear) => 0.0,
+        _ => 32.0,
+    };
+
+    let all_linear = [mag_filter, min_filter, mipmap_filter]
+        .iter()
+        .all(|filter| *filter == wgpu::FilterMode::Linear);
+    let anisotropy_clamp = if all_linear {
+        max_anisotropy.clamp(1, MAX_ANISOTROPY)
+    } else {
+        1
+    };
+
+    wgpu::SamplerDescriptor {
+        label: None,
+        address_mode_u: address_mode(sampler.wrap_s()),
+        address_mode_v: address_mode(sampler.wrap_t()),
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter,
+        min_filter,
+        mipmap_filter,
+        lod_max_clamp,
+        anisotropy_clamp,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sampler_key_roundtrip() {
+        let desc = wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            lod_max_clamp: 8.0,
+            anisotropy_clamp: 4,
+            ..Default::default()
+        };
+        let key = SamplerKey::from_descriptor(&desc);
+
+        assert_eq!(key, SamplerKey::from_descriptor(&key.to_descriptor()));
+        assert_ne!(key, SamplerKey::from_descriptor(&wgpu::SamplerDescriptor::default()));
+    }
+
+    fn gltf_samplers() -> gltf::Gltf {
+        gltf::Gltf::from_slice(
+            br#"{
+                "asset": { "version": "2.0" },
+                "samplers": [
+                    {},
+                    { "magFilter": 9728, "minFilter": 9984, "wrapS": 33071, "wrapT": 33648 },
+                    { "magFilter": 9729, "minFilter": 9729 }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sampler_descriptor_from_gltf() {
+        let gltf = gltf_samplers();
+        let samplers: Vec<_> = gltf
+            .samplers()
+            .map(|sampler| sampler_descriptor_from_gltf(&sampler, 8))
+            .collect();
+
+        // По умолчанию: повтор, трилинейная фильтрация и анизотропия
+        assert_eq!(samplers[0].address_mode_u, wgpu::AddressMode::Repeat);
+        assert_eq!(samplers[0].mipmap_filter, wgpu::FilterMode::Linear);
+        assert_eq!(samplers[0].anisotropy_clamp, 8);
+
+        assert_eq!(samplers[1].address_mode_u, wgpu::AddressMode::ClampToEdge);
+        assert_eq!(samplers[1].address_mode_v, wgpu::AddressMode::MirrorRepeat);
+        assert_eq!(samplers[1].mag_filter, wgpu::FilterMode::Nearest);
+        assert_eq!(samplers[1].min_filter, wgpu::FilterMode::Nearest);
+        assert_eq!(samplers[1].anisotropy_clamp, 1);
+
+        // Без mipmap-фильтра выбирается только нулевой уровень
+        assert_eq!(samplers[2].lod_max_clamp, 0.0);
+        assert_eq!(samplers[2].anisotropy_clamp, 1);
+    }
+}
@@ -33,6 +33,211 @@ impl Vertex {
     }
 }
 
+/// Вершина скелетного меша: [`Vertex`] плюс `JOINTS_0` и `WEIGHTS_0`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkinnedVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coord: [f32; 2],
+    pub joints: [u16; 4],
+    /// Веса нормированы: сумма равна 1
+    pub weights: [f32; 4],
+}
+
+impl SkinnedVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        use std::mem;
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<SkinnedVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint16x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: (mem::size_of::<[f32; 8]>() + mem::size_of::<[u16; 4]>()) as wgpu::BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+/// Атрибут вершины меша; номер `@location` в шейдере у каждого свой
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VertexAttribute {
+    Position,
+    Normal,
+    TexCoord0,
+    Joints,
+    Weights,
+    /// `xyz` — касательная, `w` — знак битангенса
+    Tangent,
+    Color,
+    TexCoord1,
+}
+
+impl VertexAttribute {
+    /// Все атрибуты в порядке `@location`
+    pub const ALL: [VertexAttribute; 8] = [
+        VertexAttribute::Position,
+        VertexAttribute::Normal,
+        VertexAttribute::TexCoord0,
+        VertexAttribute::Joints,
+        VertexAttribute::Weights,
+        VertexAttribute::Tangent,
+        VertexAttribute::Color,
+        VertexAttribute::TexCoord1,
+    ];
+
+    pub const fn shader_location(self) -> u32 {
+        self as u32
+    }
+
+    pub const fn format(self) -> wgpu::VertexFormat {
+        match self {
+            VertexAttribute::Position | VertexAttribute::Normal => wgpu::VertexFormat::Float32x3,
+            VertexAttribute::TexCoord0 | VertexAttribute::TexCoord1 => wgpu::VertexFormat::Float32x2,
+            VertexAttribute::Joints => wgpu::VertexFormat::Uint16x4,
+            VertexAttribute::Weights | VertexAttribute::Tangent | VertexAttribute::Color => {
+                wgpu::VertexFormat::Float32x4
+            }
+        }
+    }
+}
+
+/// Состав вершины меша. Атрибуты идут в порядке `@location` без промежутков,
+/// так что [`VertexLayout::BASIC`] совпадает с [`Vertex`], а
+/// [`VertexLayout::SKINNED`] — с [`SkinnedVertex`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VertexLayout {
+    attributes: [wgpu::VertexAttribute; VertexAttribute::ALL.len()],
+    len: usize,
+    stride: wgpu::BufferAddress,
+    mask: u32,
+}
+
+impl VertexLayout {
+    pub const BASIC: VertexLayout =
+        VertexLayout::new(&[VertexAttribute::Position, VertexAttribute::Normal, VertexAttribute::TexCoord0]);
+
+    pub const SKINNED: VertexLayout = VertexLayout::new(&[
+        VertexAttribute::Position,
+        VertexAttribute::Normal,
+        VertexAttribute::TexCoord0,
+        VertexAttribute::Joints,
+        VertexAttribute::Weights,
+    ]);
+
+    /// Раскладка из набора атрибутов; порядок в `attributes` не важен
+    pub const fn new(attributes: &[VertexAttribute]) -> Self {
+        let mut mask = 0;
+        let mut i = 0;
+        while i < attributes.len() {
+            mask |= 1 << attributes[i].shader_location();
+            i += 1;
+        }
+
+        let unused = wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32,
+            offset: 0,
+            shader_location: 0,
+        };
+        let mut layout = Self {
+            attributes: [unused; VertexAttribute::ALL.len()],
+            len: 0,
+            stride: 0,
+            mask,
+        };
+        let mut i = 0;
+        while i < VertexAttribute::ALL.len() {
+            let attribute = VertexAttribute::ALL[i];
+            if mask & (1 << attribute.shader_location()) != 0 {
+                layout.attributes[layout.len] = wgpu::VertexAttribute {
+                    format: attribute.format(),
+                    offset: layout.stride,
+                    shader_location: attribute.shader_location(),
+                };
+                layout.len += 1;
+                layout.stride += attribute.format().size();
+            }
+            i += 1;
+        }
+        layout
+    }
+
+    pub const fn contains(&self, attribute: VertexAttribute) -> bool {
+        self.mask & (1 << attribute.shader_location()) != 0
+    }
+
+    /// Размер вершины в байтах
+    pub const fn stride(&self) -> wgpu::BufferAddress {
+        self.stride
+    }
+
+    pub fn attributes(&self) -> impl Iterator<Item = VertexAttribute> + '_ {
+        VertexAttribute::ALL.into_iter().filter(|attribute| self.contains(*attribute))
+    }
+
+    /// Смещение атрибута в вершине
+    pub fn offset(&self, attribute: VertexAttribute) -> Option<wgpu::BufferAddress> {
+        self.attributes[..self.len]
+            .iter()
+            .find(|desc| desc.shader_location == attribute.shader_location())
+            .map(|desc| desc.offset)
+    }
+
+    pub fn desc(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.stride,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &self.attributes[..self.len],
+        }
+    }
+
+    /// Define'ы шейдера `main`, включающие необязательные входы вершины
+    pub fn defines(&self) -> Vec<&'static str> {
+        [
+            (VertexAttribute::Color, "VERTEX_COLOR"),
+            (VertexAttribute::Tangent, "VERTEX_TANGENT"),
+            (VertexAttribute::TexCoord1, "VERTEX_UV1"),
+        ]
+        .into_iter()
+        .filter(|(attribute, _)| self.contains(*attribute))
+        .map(|(_, define)| define)
+        .collect()
+    }
+}
+
+/// Приводит веса к сумме 1; нулевые веса отдают всё первому суставу
+pub fn normalize_weights(weights: [f32; 4]) -> [f32; 4] {
+    let sum: f32 = weights.iter().sum();
+    if sum <= f32::EPSILON {
+        [1.0, 0.0, 0.0, 0.0]
+    } else {
+        weights.map(|weight| weight / sum)
+    }
+}
+
 
 pub const VERTICES: &[SimpleVertex] = &[
     SimpleVertex {
@@ -104,4 +309,39 @@ impl SimpleVertex {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn test_layout_matches_vertex_structs() {
+        for (layout, desc) in [
+            (VertexLayout::BASIC, Vertex::desc()),
+            (VertexLayout::SKINNED, SkinnedVertex::desc()),
+        ] {
+            assert_eq!(layout.desc().array_stride, desc.array_stride);
+            assert_eq!(layout.desc().attributes, desc.attributes);
+        }
+
+        let layout = VertexLayout::new(&[
+            VertexAttribute::Color,
+            VertexAttribute::Position,
+            VertexAttribute::Normal,
+            VertexAttribute::TexCoord0,
+            VertexAttribute::Tangent,
+        ]);
+        assert_eq!(layout.stride(), 32 + 16 + 16);
+        assert_eq!(layout.offset(VertexAttribute::Tangent), Some(32));
+        assert_eq!(layout.offset(VertexAttribute::Color), Some(48));
+        assert_eq!(layout.offset(VertexAttribute::TexCoord1), None);
+        assert_eq!(layout.defines(), ["VERTEX_COLOR", "VERTEX_TANGENT"]);
+        let layout = VertexLayout::new(&[
+            VertexAttribute::Position,
+            VertexAttribute::Normal,
+            VertexAttribute::TexCoord0,
+            VertexAttribute::TexCoord1,
+        ]);
+        assert_eq!(layout.defines(), ["VERTEX_UV1"]);
+        assert!(VertexLayout::BASIC.defines().is_empty());
+    }
+}
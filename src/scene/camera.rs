@@ -2,21 +2,28 @@ use glam::{Mat4, Vec3, Vec4};
 use gltf::buffer;
 use wgpu::{util::DeviceExt, wgc::device::queue, BindGroupLayout, Buffer, Device, Queue};
 
-use crate::{math::angle::Angle, scene::transform::{self, Transform}};
+use crate::{core::pipeline_cache::PipelineCache, math::angle::Angle, scene::transform::{self, Transform}};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct CameraUniform {
     view_proj: [[f32; 4]; 4],
+    view_position: [f32; 4],
 }
 
 impl CameraUniform {
     pub fn new() -> Self {
         Self {
             view_proj: Mat4::IDENTITY.to_cols_array_2d(),
+            view_position: [0.0, 0.0, 0.0, 1.0],
         }
     }
 
+    /// Обновляет позицию наблюдателя (нужна для освещения)
+    pub fn update_view_position(&mut self, position: glam::Vec3) {
+        self.view_position = position.extend(1.0).to_array();
+    }
+
     pub fn update_view_proj(&mut self, view_proj: Mat4) {
         self.view_proj = view_proj.to_cols_array_2d();
         println!("{:?}", view_proj);
@@ -88,23 +95,26 @@ impl Camera {
     
 }
 
+/// Записи layout'а для uniform-буфера камеры
+pub const CAMERA_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+];
+
+/// Layout камеры из кэша: одинаковые записи дают один объект
 pub fn get_camera_bind_group_layout(
+    cache: &mut PipelineCache,
     device: &wgpu::Device,
 ) -> BindGroupLayout {
-    return 
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        entries: &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        }],
-        label: Some("camera_bind_group_layout"),
-    });
+    cache.bind_group_layout(device, CAMERA_BIND_GROUP_LAYOUT_ENTRIES)
 }
 
 #[derive(Clone, Copy, PartialEq)]
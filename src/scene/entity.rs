@@ -4,7 +4,7 @@ use std::ops::Range;
 use glam::Vec3;
 use wgpu::{BindGroup, Buffer};
 
-use crate::{res::vertex::Vertex, scene::{camera::{Camera, CameraUniform}, light::{get_light_bind_group_layout, Light}}};
+use crate::{res::vertex::Vertex, scene::{camera::{Camera, CameraUniform}, light::Light}};
 
 use super::transform::Transform;
 
@@ -81,8 +81,11 @@ impl SceneEntity {
     }
     
 
+    /// Камера с uniform-буфером; `layout` — layout камеры из кэша
+    /// ([`get_camera_bind_group_layout`](super::camera::get_camera_bind_group_layout))
     pub fn new_camera(
         device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
         position: Vec3,
         rotation: glam::Quat,
         scale: Vec3,
@@ -104,12 +107,13 @@ impl SceneEntity {
         // let view_proj = transform.calculate_view_projection(aspect);
         let view_proj = transform.calculate_view_projection(&camera);
         uniform.update_view_proj(view_proj);
+        uniform.update_view_position(transform.position);
 
         let buffer = CameraUniform::create_buffer(device, uniform);
 
         camera.create_bind_group(
             device, 
-            &get_light_bind_group_layout(device), 
+            layout, 
             buffer.clone()
         );
 
@@ -1,5 +1,7 @@
 use glam::{Mat4, Vec3, Vec4};
-use wgpu::{util::DeviceExt, BindGroupDescriptor, BindGroupLayout, Buffer, Device};
+use wgpu::{util::DeviceExt, BindGroupLayout, Buffer, Device};
+
+use crate::core::pipeline_cache::PipelineCache;
 
 
 #[repr(C)]
@@ -28,6 +30,7 @@ impl LightUniform {
 }
 
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LightType {
     Directional,
     Point {
@@ -61,51 +64,53 @@ impl Light {
     pub fn create_bind_group(
         &mut self,
         device: &Device,
-        layout: Option<&BindGroupLayout>,
+        layout: &BindGroupLayout,
         buffer: Buffer
     ) {
-        match layout {
-            Some(layout) => {
-                self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    layout: &layout,
-                    entries: &[wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: buffer.as_entire_binding(),
-                    }],
-                    label: Some("light_bind_group"),
-                }));
-            },
-            None => {
-                let layout = get_light_bind_group_layout(device);
-                self.bind_group = Some(device.create_bind_group(&BindGroupDescriptor {
-                    layout: &layout,
-                    entries: &[wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: buffer.as_entire_binding(),
-                    }],
-                    label: Some("light_bind_group"),
-                }));
-            },
-        }  
+        self.bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("light_bind_group"),
+        }));
     }
     
 }
 
+/// Записи layout'а для uniform-буфера одного источника света
+pub const LIGHT_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+];
+
+/// Записи layout'а для storage-массива точечных источников (`shaders/light.wgsl`)
+pub const POINT_LIGHTS_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Storage { read_only: true },
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+];
+
+/// Layout источника света из кэша: одинаковые записи дают один объект
 pub fn get_light_bind_group_layout(
+    cache: &mut PipelineCache,
     device: &wgpu::Device,
 ) -> BindGroupLayout {
-    return 
-        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        label: Some("camera_bind_group_layout"),
-    });
+    cache.bind_group_layout(device, LIGHT_BIND_GROUP_LAYOUT_ENTRIES)
 }
@@ -5,7 +5,7 @@ pub mod light;
 
 use std::collections::HashMap;
 use entity::SceneEntity;
-use crate::res::{material::Material, mesh::Mesh, model::Model, texture::gpu_texture::GpuTexture, Handle};
+use crate::res::{animation::Pose, material::Material, mesh::Mesh, model::Model, texture::gpu_texture::GpuTexture, Handle};
 
 
 #[derive(Default)]
@@ -49,6 +49,17 @@ impl AppScene {
     //         })
     // }
 
+    /// Записывает позу анимации в преобразования сущностей;
+    /// `targets` сопоставляет индексам узлов glTF имена сущностей
+    pub fn apply_pose(&mut self, pose: &Pose, targets: &HashMap<usize, String>) {
+        for (node, node_pose) in &pose.nodes {
+            let Some(entity) = targets.get(node).and_then(|name| self.entities.get_mut(name)) else {
+                continue;
+            };
+            node_pose.apply(&mut entity.transform);
+        }
+    }
+
     pub fn update(&mut self, delta_time: f32) {
 
     }
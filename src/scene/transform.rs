@@ -1,8 +1,22 @@
-use glam::{Mat4, Quat, Vec3};
+use glam::{Mat4, Quat, Vec2, Vec3};
 use wgpu::{util::DeviceExt, Buffer, BufferUsages};
 
 use crate::scene::camera::Camera;
 
+/// Записи layout'а для uniform-буфера матрицы модели
+pub const TRANSFORM_BIND_GROUP_LAYOUT_ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility: wgpu::ShaderStages::VERTEX,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    },
+];
+
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -97,6 +111,15 @@ impl Transform {
         proj * view
     }
 
+    /// Луч камеры через точку кадра `ndc` (-1..1, `y` вверх): начало на
+    /// ближней плоскости и единичное направление
+    pub fn screen_ray(&self, camera: &Camera, ndc: Vec2) -> (Vec3, Vec3) {
+        let inverse = self.calculate_view_projection(camera).inverse();
+        let near = inverse.project_point3(ndc.extend(0.0));
+        let far = inverse.project_point3(ndc.extend(1.0));
+        (near, (far - near).normalize())
+    }
+
 
     pub fn create_buffer(
         device: &wgpu::Device, 